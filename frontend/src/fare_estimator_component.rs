@@ -0,0 +1,129 @@
+use crate::imports::*;
+use crate::schedules_state::*;
+
+const MAX_PASSENGERS: u32 = 20;
+
+#[derive(PartialEq, Properties)]
+pub struct FareEstimatorProps {
+    pub terminal_pairs: Vec<TerminalPair>,
+    pub date: Date,
+    pub has_thrufares: bool,
+}
+
+fn passenger_label(passenger_type: PassengerType) -> &'static str {
+    match passenger_type {
+        PassengerType::Adult => "Adults (12+)",
+        PassengerType::Senior => "Seniors (65+)",
+        PassengerType::Child => "Children (5-11)",
+        PassengerType::Infant => "Infants (0-4)",
+    }
+}
+
+fn onchange_passengers_callback(party: &UseStateHandle<FareParty>, passenger_type: PassengerType) -> Callback<Event> {
+    let party = party.clone();
+    Callback::from(move |e: Event| {
+        let count = e.target_unchecked_into::<HtmlInputElement>().value().trim().parse().unwrap_or(0);
+        let mut new_party = (*party).clone();
+        new_party.passengers.insert(passenger_type, min(count, MAX_PASSENGERS));
+        party.set(new_party);
+    })
+}
+
+fn onchange_vehicle_length_callback(party: &UseStateHandle<FareParty>) -> Callback<Event> {
+    let party = party.clone();
+    Callback::from(move |e: Event| {
+        let vehicle_length_ft = e.target_unchecked_into::<HtmlInputElement>().value().trim().parse().ok();
+        party.set(FareParty { vehicle_length_ft, ..(*party).clone() });
+    })
+}
+
+fn estimate_html(fare: &Fare, party: &FareParty, date: Date, is_thrufare: bool) -> Html {
+    match fare.estimate(party, date, is_thrufare) {
+        Some(cents) => html! { <strong>{ format_cents(cents) }</strong> },
+        None => html! { <span class="text-muted">{ "not available" }</span> },
+    }
+}
+
+fn fare_row_html(fares: &[Fare], terminal_pair: TerminalPair, party: &FareParty, props: &FareEstimatorProps) -> Html {
+    let fare = match find_fare(fares, terminal_pair, props.date) {
+        Some(fare) => fare,
+        None => return html! {},
+    };
+    html! {
+        <tr>
+            <td>
+                <span class="text-nowrap">{ terminal_pair.from.name() }</span>
+                { " to " }
+                <span class="text-nowrap">{ terminal_pair.to.name() }</span>
+            </td>
+            <td class="text-end">
+                <a class="link-dark" href={ fare.source_url.clone() } target="_blank">
+                    { estimate_html(fare, party, props.date, false) }
+                </a>
+                { if props.has_thrufares && fare.thrufare.is_some() { html! {
+                    <div class="small text-nowrap">
+                        { "Thru-fare " }
+                        { estimate_html(fare, party, props.date, true) }
+                    </div>
+                }} else {
+                    html! {}
+                }}
+            </td>
+        </tr>
+    }
+}
+
+#[function_component(FareEstimator)]
+pub fn fare_estimator_component(props: &FareEstimatorProps) -> Html {
+    let schedules_state = use_context::<SchedulesHandle>().expect("Expect schedules state to be available");
+    load_fares(&schedules_state);
+    let party =
+        use_state(|| FareParty { passengers: HashMap::from([(PassengerType::Adult, 1)]), vehicle_length_ft: None });
+    let fares = match &schedules_state.fares {
+        FaresState::Loaded(fares) => fares.clone(),
+        FaresState::Init | FaresState::Loading | FaresState::Failed => return html! {},
+    };
+    if !props.terminal_pairs.iter().any(|tp| find_fare(&fares, *tp, props.date).is_some()) {
+        return html! {};
+    }
+    html! {
+        <div class="row mt-4 d-print-none">
+            <div class="col-12 col-md-8 col-lg-6">
+                <h6>{ "Fare estimate" }</h6>
+                <div class="row g-2 mb-2">
+                    { for PassengerType::iter().map(|passenger_type| html! {
+                        <div class="col-6 col-sm-3">
+                            <label class="form-label small mb-0">{ passenger_label(passenger_type) }</label>
+                            <input
+                                type="number"
+                                class="form-control form-control-sm"
+                                min="0"
+                                max={ MAX_PASSENGERS.to_string() }
+                                value={ party.passengers.get(&passenger_type).copied().unwrap_or(0).to_string() }
+                                onchange={ onchange_passengers_callback(&party, passenger_type) }/>
+                        </div>
+                    })}
+                    <div class="col-12 col-sm-6">
+                        <label class="form-label small mb-0">{ "Vehicle length in feet (blank for no vehicle)" }</label>
+                        <input
+                            type="number"
+                            class="form-control form-control-sm"
+                            min="1"
+                            value={ party.vehicle_length_ft.map(|l| l.to_string()).unwrap_or_default() }
+                            onchange={ onchange_vehicle_length_callback(&party) }/>
+                    </div>
+                </div>
+                <table class="table table-light table-sm mb-0">
+                    <tbody>
+                        { for props.terminal_pairs.iter().map(|tp| fare_row_html(&fares, *tp, &party, props)) }
+                    </tbody>
+                </table>
+                <div class="text-muted">
+                    <small>
+                        { "Estimated one-way fares, not including surcharges or discounts. Confirm with BC Ferries before you travel." }
+                    </small>
+                </div>
+            </div>
+        </div>
+    }
+}
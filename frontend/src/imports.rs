@@ -1,10 +1,19 @@
 pub use std::result::Result as StdResult;
 pub use time_humanize::HumanTime;
-pub use web_sys::HtmlInputElement;
+pub use web_sys::{Element, HtmlInputElement};
 pub use yew::prelude::*;
 pub use yew_router::prelude::*;
 
 pub use ferrysched_shared::constants::*;
+pub use ferrysched_shared::data_format::*;
+pub use ferrysched_shared::date_set::*;
+pub use ferrysched_shared::fares::*;
+pub use ferrysched_shared::holidays::*;
+pub use ferrysched_shared::ical::*;
 pub use ferrysched_shared::imports::*;
+pub use ferrysched_shared::journeys::*;
+pub use ferrysched_shared::live::*;
+pub use ferrysched_shared::registry::*;
+pub use ferrysched_shared::sailings::*;
 pub use ferrysched_shared::types::*;
 pub use ferrysched_shared::utils::*;
@@ -1,13 +1,13 @@
+mod fare_estimator_component;
 mod imports;
 mod sailings_component;
-mod sailings_processor;
+mod schedules_state;
 mod types;
 mod utils;
 
-use reqwasm::http;
-
 use crate::imports::*;
 use crate::sailings_component::*;
+use crate::schedules_state::*;
 use crate::types::*;
 use crate::utils::*;
 
@@ -64,6 +64,8 @@ fn select_from_area_html(query: &SailingsQuery) -> Html {
 fn select_to_area_html(from: Area, query: &SailingsQuery) -> Html {
     let mut to_areas: Vec<Area> = ALL_AREA_PAIRS.iter().filter(|ap| ap.from == from).map(|ap| ap.to).collect();
     to_areas.sort_unstable();
+    let mut connecting_to_areas: Vec<Area> = connecting_areas(from, DEFAULT_MAX_JOURNEY_LEGS).into_iter().collect();
+    connecting_to_areas.sort_unstable();
     html! { <>
         <p class="mt-3">{ "Select your arrival area:" }</p>
         <ul>
@@ -73,6 +75,18 @@ fn select_to_area_html(from: Area, query: &SailingsQuery) -> Html {
                     </li>
             }) }
         </ul>
+        { if connecting_to_areas.is_empty() {
+            html! {}
+        } else { html! { <>
+            <p>{ "Or with connections:" }</p>
+            <ul>
+                { for connecting_to_areas.iter().map(|&to| html! {
+                        <li>
+                            { area_link_html(to, SailingsQuery{to: Some(to), ..*query}) }
+                        </li>
+                }) }
+            </ul>
+        </> }}}
     </> }
 }
 
@@ -80,10 +94,10 @@ fn home_html() -> Html {
     html! { <>
         <h1 class="display-6">
             { "Schedules for BC Ferries" }
-            <small class="text-muted">{ " for the Southern Gulf Islands" }</small>
+            <small class="text-muted">{ " for the South Coast" }</small>
         </h1>
         <p class="lead">
-            { "An easy to use and understand presentation of the BC Ferries schedules for the Southern Gulf Islands, Victoria, and Vancouver. Just select your locations and date, and you're shown the sailings for that day."}
+            { "An easy to use and understand presentation of the BC Ferries schedules for the Southern Gulf Islands, Victoria, Vancouver, Nanaimo, Gabriola Island, the Sunshine Coast, Comox, Powell River, and Quadra and Cortes Islands. Just select your locations and date, and you're shown the sailings for that day."}
         </p>
         { select_from_area_html(&SailingsQuery::new()) }
         <div class="p-2 bg-light border rounded">
@@ -103,7 +117,8 @@ fn sailings_page_component() -> Html {
     let location = use_location();
     let history = use_history().expect("Expect history to be available");
     let query = location
-        .and_then(|l| l.query().map_err(|e| error!("Invalid sailings query: {}", e)).ok())
+        .and_then(|l| l.query::<SailingsQuery>().map_err(|e| error!("Invalid sailings query: {}", e)).ok())
+        .map(|q| q.resolved())
         .unwrap_or_else(SailingsQuery::new);
     if query.is_empty() {
         history.replace(Route::Home);
@@ -123,7 +138,7 @@ fn sailings_page_component() -> Html {
                 html! {}
             }}
             { match query.to {
-                Some(to) if query.from.map(|from| ALL_AREA_PAIRS.contains(&AreaPair{ from, to })).unwrap_or(true) => html! {
+                Some(to) if query.from.map(|from| is_reachable_area_pair(AreaPair{ from, to })).unwrap_or(true) => html! {
                     <div>
                         { "To " }
                         <strong>{ to.long_name() }</strong>
@@ -136,7 +151,7 @@ fn sailings_page_component() -> Html {
             SailingsQuery { from: None, .. } => select_from_area_html(&query),
             SailingsQuery { from: Some(from), to: None, .. } => select_to_area_html(from, &query),
             SailingsQuery { from: Some(from), to: Some(to), date } => {
-                if ALL_AREA_PAIRS.contains(&AreaPair { from, to }) { html! {
+                if is_reachable_area_pair(AreaPair { from, to }) { html! {
                     <Sailings area_pair={AreaPair{from, to}} {date}/>
                 }} else {
                     select_to_area_html(from, &query)
@@ -184,36 +199,12 @@ fn footer_html() -> Html {
     }
 }
 
-async fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T> {
-    let response = http::Request::get(url).send().await?;
-    let json = response.json::<T>().await?;
-    Ok(json)
-}
-
-fn load_schedules_state(schedules_state: UseStateHandle<SchedulesState>) {
-    schedules_state.set(SchedulesState::Loading);
-    wasm_bindgen_futures::spawn_local(async move {
-        match fetch_json::<Vec<Schedule>>("/data/schedules.json").await {
-            Ok(schedules) => {
-                schedules_state
-                    .set(SchedulesState::Loaded(Rc::new(into_vec_group_map(schedules, |i| i.terminal_pair))));
-            }
-            Err(err) => {
-                error!("{}", err);
-                schedules_state.set(SchedulesState::Failed);
-            }
-        }
-    });
-}
-
 #[function_component(App)]
 fn app() -> Html {
-    let schedules_state = use_state(|| SchedulesState::Init);
-    if let SchedulesState::Init = *schedules_state {
-        load_schedules_state(schedules_state.clone());
-    }
+    let schedules_state = use_reducer(SchedulesState::new);
+    load_schedules_state(&schedules_state);
     html! {
-        <ContextProvider<SchedulesState> context={(*schedules_state).clone()}>
+        <ContextProvider<SchedulesHandle> context={schedules_state}>
             <BrowserRouter>
                 <div class="container">
                     <Navbar/>
@@ -221,7 +212,7 @@ fn app() -> Html {
                     { footer_html() }
                 </div>
             </BrowserRouter>
-        </ContextProvider<SchedulesState>>
+        </ContextProvider<SchedulesHandle>>
     }
 }
 
@@ -1,8 +1,11 @@
+use crate::fare_estimator_component::*;
 use crate::imports::*;
-use crate::sailings_processor::*;
+use crate::schedules_state::*;
 use crate::types::*;
 use crate::utils::*;
 
+use std::cell::RefCell;
+
 #[derive(Eq, PartialEq, Properties)]
 pub struct SailingsProps {
     pub area_pair: AreaPair,
@@ -18,16 +21,31 @@ enum SailingsStateModel<'a> {
     InvalidDate(String),
     LoadingSchedules,
     LoadSchedulesFailed,
+    ReloadRequired,
     NoSchedule,
+    NoDirectRoute,
     NoSailings,
     Sailings(Vec<(&'a Schedule, Vec<SailingWithNotes>)>),
 }
 
+// Planning journeys searches every combination of sailings, so the journeys are kept until the query or the schedules
+// of the routes they could use change
+struct JourneysCache {
+    area_pair: AreaPair,
+    date: Date,
+    routes: Vec<Rc<Vec<Schedule>>>,
+    journeys: Rc<Vec<Journey>>,
+}
+
 struct SailingsModel<'a> {
     sailings_state_model: SailingsStateModel<'a>,
+    journeys: Rc<Vec<Journey>>,
+    journeys_loading: bool,
     area_pair: AreaPair,
     view_date: Date,
     max_date: Date,
+    // Only for today's sailings
+    live: Option<Rc<LiveData>>,
 }
 
 struct FormModel {
@@ -38,6 +56,8 @@ struct FormModel {
     today: Date,
     view_date: Date,
     max_date: Date,
+    // Upcoming holidays whose sailings differ from the usual ones for the day of the week
+    holidays: Vec<(Date, Holiday)>,
 }
 
 struct InformationUrlsModel<'a> {
@@ -46,6 +66,21 @@ struct InformationUrlsModel<'a> {
     service_notices_url: &'a str,
 }
 
+fn direct_terminal_pairs(area_pair: AreaPair) -> Vec<TerminalPair> {
+    AREA_PAIR_TERMINAL_PAIRS.get(&area_pair).map(|tps| tps.iter().copied().sorted().collect()).unwrap_or_default()
+}
+
+fn stop_times_html(stop: &Stop) -> Html {
+    let times_text = match (stop.arrive_time, stop.depart_time) {
+        (Some(arrive), Some(depart)) if arrive != depart => {
+            format!("{}\u{2013}{}", format_time(arrive), format_time(depart))
+        }
+        (Some(time), _) | (None, Some(time)) => format_time(time),
+        (None, None) => return html! {},
+    };
+    html! { <small class="text-muted text-nowrap ms-1">{ times_text }</small> }
+}
+
 fn stop_html(stop: &Stop) -> Html {
     html! {
         <li>
@@ -56,10 +91,19 @@ fn stop_html(stop: &Stop) -> Html {
         }}
         { " " }
         { stop.terminal.area().short_name() }
+        { stop_times_html(stop) }
         </li>
     }
 }
 
+fn next_day_html(next_day: bool) -> Html {
+    if next_day {
+        html! { <small class="text-muted text-nowrap ms-1">{ "+1 day" }</small> }
+    } else {
+        html! {}
+    }
+}
+
 fn alert_row_html(alert: &Alert) -> Html {
     let alert_class = match &alert.level {
         AlertLevel::Info => "alert-info",
@@ -68,7 +112,7 @@ fn alert_row_html(alert: &Alert) -> Html {
     };
     html! {
         <tr>
-            <td colspan="3" class="border-bottom-0">
+            <td colspan="4" class="border-bottom-0">
                 <div class={ classes!("alert", alert_class, "mb-0") }>
                     { &alert.message }
                 </div>
@@ -77,14 +121,84 @@ fn alert_row_html(alert: &Alert) -> Html {
     }
 }
 
-fn sailing_row_html(sailing: &SailingWithNotes) -> Html {
-    let main_td_class = (!sailing.notes.is_empty()).then_some("border-bottom-0");
+fn sailing_ical_event(schedule: &Schedule, sailing: &SailingWithNotes) -> IcalEvent {
+    IcalEvent::for_sailing(
+        schedule.terminal_pair,
+        sailing.service_date,
+        &sailing.sailing,
+        &sailing.notes,
+        &schedule.source_url,
+        schedule.refreshed_at,
+    )
+}
+
+fn live_depart_html(live: &LiveSailing) -> Html {
+    let delayed = live.delay().map(|delay| delay >= Duration::minutes(10)).unwrap_or(false);
+    let (text, class) = match (live.cancelled, live.depart_time, live.departed) {
+        (true, _, _) => ("Cancelled".to_string(), "text-danger"),
+        (false, Some(time), true) => (format!("Departed {}", format_time(time)), ""),
+        (false, Some(time), false) => (format!("Expected {}", format_time(time)), ""),
+        (false, None, true) => ("Departed".to_string(), ""),
+        (false, None, false) => return html! {},
+    };
+    html! { <div class={ classes!("small", class, delayed.then_some("text-danger")) }>{ text }</div> }
+}
+
+fn live_arrive_html(live: &LiveSailing) -> Html {
+    match (live.arrive_time, live.arrived) {
+        (Some(time), true) => html! { <div class="small">{ format!("Arrived {}", format_time(time)) }</div> },
+        (Some(time), false) => html! { <div class="small">{ format!("ETA {}", format_time(time)) }</div> },
+        (None, _) => html! {},
+    }
+}
+
+fn live_deck_space_html(live: &LiveSailing) -> Html {
+    match live.deck_space_percent {
+        Some(0) => html! { <div class="small text-danger">{ "Deck space full" }</div> },
+        Some(percent) => html! { <div class="small">{ format!("{}% deck space available", percent) }</div> },
+        None => html! {},
+    }
+}
+
+fn sailing_row_html(schedule: &Schedule, date: Date, sailing: &SailingWithNotes, live: Option<&LiveSailing>) -> Html {
+    let main_td_class = (!sailing.notes.is_empty() || sailing.is_cancelled()).then_some("border-bottom-0");
     let all_td_class = sailing.sailing.is_thrufare().then_some("text-muted");
+    let cancelled_class = sailing.is_cancelled().then_some("text-decoration-line-through");
+    let ical_filename = format!(
+        "ferry-{}-{}-{}.ics",
+        schedule.terminal_pair,
+        format_iso8601_date(date),
+        sailing
+            .sailing
+            .depart_time
+            .format(format_description!("[hour][minute]"))
+            .expect("Expect sailing time to format")
+    );
+    let ical_calendar_name =
+        format!("Ferry {} to {}", schedule.terminal_pair.from.name(), schedule.terminal_pair.to.name());
     html! { <>
         <tr>
-            <td class={ classes!(all_td_class, main_td_class) }>{ format_time(sailing.sailing.depart_time) }</td>
-            <td class={ classes!(all_td_class, main_td_class) }>{ format_time(sailing.sailing.arrive_time) }</td>
-            <td class={ classes!("text-nowrap", all_td_class, main_td_class) }>
+            <td class={ classes!(all_td_class, main_td_class) }>
+                <span class={ classes!(cancelled_class) }>{ format_time(sailing.sailing.depart_time) }</span>
+                { if sailing.is_cancelled() {
+                    html! {}
+                } else {
+                    add_to_calendar_link_html(
+                        ical_filename,
+                        ical_calendar_name,
+                        vec![sailing_ical_event(schedule, sailing)],
+                        "",
+                        classes!("link-secondary", "ms-1", "d-print-none"),
+                    )
+                }}
+                { live.map(live_depart_html).unwrap_or_default() }
+            </td>
+            <td class={ classes!(all_td_class, main_td_class, cancelled_class) }>
+                { format_time(sailing.sailing.arrive_time) }
+                { next_day_html(sailing.sailing.arrives_next_day()) }
+                { live.map(live_arrive_html).unwrap_or_default() }
+            </td>
+            <td class={ classes!("text-nowrap", all_td_class, main_td_class, cancelled_class) }>
                 { if sailing.sailing.stops.is_empty() { html! {
                     <span class="text-muted">{ "non-stop" }</span>
                 }} else { html! {
@@ -93,11 +207,19 @@ fn sailing_row_html(sailing: &SailingWithNotes) -> Html {
                     </ul>
                 }}}
             </td>
+            <td class={ classes!(all_td_class, main_td_class, cancelled_class) }>
+                // The vessel may have been swapped since the schedule was published
+                { live.and_then(|l| l.vessel.as_deref()).or(sailing.sailing.vessel.as_deref()).unwrap_or_default() }
+                { live.map(live_deck_space_html).unwrap_or_default() }
+            </td>
         </tr>
-        { if !sailing.notes.is_empty() { html! {
+        { if !sailing.notes.is_empty() || sailing.is_cancelled() { html! {
             <tr>
-                <td colspan="3" class={ classes!("small", "pt-0", all_td_class) }>
+                <td colspan="4" class={ classes!("small", "pt-0", all_td_class) }>
                     <ul class="mb-0">
+                        { for sailing.cancellations.iter().map(|notice| { html! {
+                            <li class="text-danger">{ "Cancelled: " }{ notice }</li>
+                        }})}
                         { for sailing.notes.iter().map(|note| { html! {
                             <li>{ note }</li>
                         }})}
@@ -110,6 +232,95 @@ fn sailing_row_html(sailing: &SailingWithNotes) -> Html {
     }</>}
 }
 
+fn journey_leg_html(leg: &JourneyLeg) -> Html {
+    let terminal_pair = leg.terminal_pair();
+    let notes: Vec<_> = leg.notes().collect();
+    html! {
+        <li>
+            { format_time(leg.sailing().depart_time) }
+            { next_day_html(leg.departs_next_day()) }
+            { " " }
+            <span class="text-nowrap">{ terminal_pair.from.name() }</span>
+            { " to " }
+            <span class="text-nowrap">{ terminal_pair.to.name() }</span>
+            { if notes.is_empty() {
+                html! {}
+            } else { html! {
+                <ul class="small text-muted mb-0">
+                    { for notes.iter().map(|note| html! { <li>{ note }</li> }) }
+                </ul>
+            }}}
+        </li>
+    }
+}
+
+fn journey_drive_html(drive: &Option<JourneyDrive>) -> Html {
+    match drive {
+        Some(drive) => html! {
+            <li class="text-muted">
+                { "Drive to " }
+                <span class="text-nowrap">{ drive.to.long_name() }</span>
+                { format!(" ({} min)", drive.duration.whole_minutes()) }
+            </li>
+        },
+        None => html! {},
+    }
+}
+
+// Journeys that connect between sailings, since the direct ones are already in the sailings table
+fn connecting_journeys(
+    journeys_cache: &RefCell<Option<JourneysCache>>,
+    area_pair: AreaPair,
+    date: Date,
+    schedules_map: &SchedulesMap,
+) -> Rc<Vec<Journey>> {
+    let routes: Vec<_> = journey_terminal_pairs(area_pair, DEFAULT_MAX_JOURNEY_LEGS)
+        .into_iter()
+        .sorted()
+        .filter_map(|tp| schedules_map.get(&tp).cloned())
+        .collect();
+    let mut journeys_cache = journeys_cache.borrow_mut();
+    match &*journeys_cache {
+        Some(cache)
+            if cache.area_pair == area_pair
+                && cache.date == date
+                && cache.routes.len() == routes.len()
+                && cache.routes.iter().zip(&routes).all(|(a, b)| Rc::ptr_eq(a, b)) =>
+        {
+            cache.journeys.clone()
+        }
+        _ => {
+            let journeys: Rc<Vec<_>> = Rc::new(
+                plan_journeys(&JourneyQuery::new(area_pair, date), schedules_map)
+                    .into_iter()
+                    .filter(|j| !j.is_direct())
+                    .collect(),
+            );
+            *journeys_cache = Some(JourneysCache { area_pair, date, routes, journeys: journeys.clone() });
+            journeys
+        }
+    }
+}
+
+fn journey_row_html(journey: &Journey) -> Html {
+    html! {
+        <tr>
+            <td>{ format_time(journey.depart_time()) }</td>
+            <td>
+                { format_time(journey.arrive_time()) }
+                { next_day_html(journey.arrives_next_day()) }
+            </td>
+            <td>
+                <ol class="ps-3 mb-0">
+                    { journey_drive_html(&journey.depart_drive) }
+                    { for journey.legs.iter().map(journey_leg_html) }
+                    { journey_drive_html(&journey.arrive_drive) }
+                </ol>
+            </td>
+        </tr>
+    }
+}
+
 fn schedule_sailings_header_row_html(schedule: &Schedule) -> Html {
     html! {
         <tr>
@@ -124,11 +335,21 @@ fn schedule_sailings_header_row_html(schedule: &Schedule) -> Html {
             <th class="bg-heading fw-normal">
                 { "Stops" }
             </th>
+            <th class="bg-heading fw-normal">
+                { "Vessel" }
+            </th>
         </tr>
     }
 }
 
-fn schedule_sailings_rows_html(first: bool, last: bool, schedule: &Schedule, sailings: &[SailingWithNotes]) -> Html {
+fn schedule_sailings_rows_html(
+    first: bool,
+    last: bool,
+    date: Date,
+    schedule: &Schedule,
+    sailings: &[SailingWithNotes],
+    live: Option<&LiveData>,
+) -> Html {
     let bottom_class = (!last).then_some("pb-3");
     html! { <>
         { if first {
@@ -145,12 +366,12 @@ fn schedule_sailings_rows_html(first: bool, last: bool, schedule: &Schedule, sai
             }
         }}
         <tbody>
-        { for schedule.alerts.iter().map(alert_row_html) }
-        { for sailings.iter().map(sailing_row_html) }
+        { for schedule.alerts.iter().filter(|a| a.applies_on(date)).map(alert_row_html) }
+        { for sailings.iter().map(|sailing| sailing_row_html(schedule, date, sailing, live.and_then(|l| l.find(sailing)))) }
         </tbody>
         <tbody>
             <tr>
-                <td colspan=3 class={classes!("text-end", "text-muted", "d-print-none", "border-bottom-0", "p-0", "bg-transparent", bottom_class)}>
+                <td colspan=4 class={classes!("text-end", "text-muted", "d-print-none", "border-bottom-0", "p-0", "bg-transparent", bottom_class)}>
                     <small>
                         { "Data updated " }
                         { human_time(schedule.refreshed_at) }
@@ -168,64 +389,73 @@ fn schedule_sailings_rows_html(first: bool, last: bool, schedule: &Schedule, sai
 impl<'a> SailingsModel<'a> {
     fn new(
         schedules_state: &'a SchedulesState,
+        journeys_cache: &RefCell<Option<JourneysCache>>,
         date_input_state: &DateInputState,
         area_pair: AreaPair,
         query_date_or_today: Date,
     ) -> SailingsModel<'a> {
         let base = SailingsModel {
             sailings_state_model: SailingsStateModel::NoSailings,
+            journeys: Rc::new(Vec::new()),
+            journeys_loading: false,
             area_pair,
             view_date: query_date_or_today,
             max_date: query_date_or_today,
+            live: None,
         };
-        match (date_input_state.value, schedules_state) {
+        let direct_terminal_pairs = direct_terminal_pairs(area_pair);
+        match (date_input_state.value, schedules_state.routes_status(direct_terminal_pairs.iter().copied())) {
             (Err(err), _) => {
                 SailingsModel { sailings_state_model: SailingsStateModel::InvalidDate(err.to_string()), ..base }
             }
-            (Ok(view_date), SchedulesState::Init) | (Ok(view_date), SchedulesState::Loading) => SailingsModel {
+            (Ok(view_date), RoutesStatus::Loading) => SailingsModel {
                 sailings_state_model: SailingsStateModel::LoadingSchedules,
                 view_date,
                 max_date: view_date,
                 ..base
             },
-            (Ok(view_date), SchedulesState::Failed) => SailingsModel {
+            (Ok(view_date), RoutesStatus::Failed) => SailingsModel {
                 sailings_state_model: SailingsStateModel::LoadSchedulesFailed,
                 view_date,
                 max_date: view_date,
                 ..base
             },
-            (Ok(view_date), SchedulesState::Loaded(schedules_map)) => {
+            (Ok(view_date), RoutesStatus::ReloadRequired) => SailingsModel {
+                sailings_state_model: SailingsStateModel::ReloadRequired,
+                view_date,
+                max_date: view_date,
+                ..base
+            },
+            (Ok(view_date), RoutesStatus::Loaded(schedules_map)) => {
+                let has_direct_route = !direct_terminal_pairs.is_empty();
+                let journey_terminal_pairs = journey_terminal_pairs(area_pair, DEFAULT_MAX_JOURNEY_LEGS);
                 let max_date = max(
                     view_date,
-                    AREA_PAIR_TERMINAL_PAIRS
-                        .get(&area_pair)
-                        .and_then(|tps| {
-                            tps.iter()
-                                .flat_map(|tp| {
-                                    schedules_map.get(tp).and_then(|ss| ss.iter().map(|s| s.date_range.to).max())
-                                })
-                                .max()
-                        })
-                        .unwrap_or(view_date),
+                    if has_direct_route {
+                        schedules_state.max_date(direct_terminal_pairs.iter().copied())
+                    } else {
+                        schedules_state.max_date(journey_terminal_pairs.iter().copied())
+                    }
+                    .unwrap_or(view_date),
                 );
-                if let Some(schedules_sailings) = area_sailings_for_date(area_pair, view_date, schedules_map) {
+                let (journeys, journeys_loading) = match schedules_state.routes_status(journey_terminal_pairs) {
+                    RoutesStatus::Loaded(schedules_map) => {
+                        (connecting_journeys(journeys_cache, area_pair, view_date, schedules_map), false)
+                    }
+                    RoutesStatus::Loading => (Rc::new(Vec::new()), true),
+                    RoutesStatus::Failed | RoutesStatus::ReloadRequired => (Rc::new(Vec::new()), false),
+                };
+                let base = SailingsModel { journeys, journeys_loading, view_date, max_date, ..base };
+                if !has_direct_route {
+                    SailingsModel { sailings_state_model: SailingsStateModel::NoDirectRoute, ..base }
+                } else if let Some(schedules_sailings) = area_sailings_for_date(area_pair, view_date, schedules_map) {
                     if schedules_sailings.is_empty() {
-                        SailingsModel {
-                            sailings_state_model: SailingsStateModel::NoSailings,
-                            view_date,
-                            max_date,
-                            ..base
-                        }
+                        SailingsModel { sailings_state_model: SailingsStateModel::NoSailings, ..base }
                     } else {
-                        SailingsModel {
-                            sailings_state_model: SailingsStateModel::Sailings(schedules_sailings),
-                            view_date,
-                            max_date,
-                            ..base
-                        }
+                        SailingsModel { sailings_state_model: SailingsStateModel::Sailings(schedules_sailings), ..base }
                     }
                 } else {
-                    SailingsModel { sailings_state_model: SailingsStateModel::NoSchedule, view_date, max_date, ..base }
+                    SailingsModel { sailings_state_model: SailingsStateModel::NoSchedule, ..base }
                 }
             }
         }
@@ -233,13 +463,41 @@ impl<'a> SailingsModel<'a> {
 
     fn sailings_table_html(&self, schedule_sailings: &[(&Schedule, Vec<SailingWithNotes>)]) -> Html {
         let last_schedule_index = schedule_sailings.len() - 1;
+        let day_ical_events: Vec<_> = schedule_sailings
+            .iter()
+            .flat_map(|(schedule, sailings)| {
+                sailings.iter().filter(|s| !s.is_cancelled()).map(|s| sailing_ical_event(schedule, s))
+            })
+            .collect();
+        let day_ical_filename = format!(
+            "ferries-{}-{}-{}.ics",
+            self.area_pair.from.short_name(),
+            self.area_pair.to.short_name(),
+            format_iso8601_date(self.view_date)
+        )
+        .replace(' ', "-");
+        let day_ical_calendar_name =
+            format!("Ferries {} to {}", self.area_pair.from.long_name(), self.area_pair.to.long_name());
         html! { <>
-            <div>
-                <h6>{ self.view_date.format(format_description!("[weekday], [day padding:none] [month repr:long], [year]")).expect("Expect friendly date to format") }</h6>
+            <div class="d-flex">
+                <h6 class="me-auto">{ self.view_date.format(format_description!("[weekday], [day padding:none] [month repr:long], [year]")).expect("Expect friendly date to format") }</h6>
+                { if day_ical_events.is_empty() {
+                    html! {}
+                } else { html! {
+                    <small>
+                        { add_to_calendar_link_html(
+                            day_ical_filename,
+                            day_ical_calendar_name,
+                            day_ical_events,
+                            " Add to calendar",
+                            classes!("link-secondary", "d-print-none", "text-nowrap"),
+                        ) }
+                    </small>
+                }}}
             </div>
             <table class="table table-light mb-0">
                 { for schedule_sailings.iter().enumerate().map(|(index, (schedule, sailings))|
-                    schedule_sailings_rows_html(index == 0, index == last_schedule_index, schedule, sailings)
+                    schedule_sailings_rows_html(index == 0, index == last_schedule_index, self.view_date, schedule, sailings, self.live.as_deref())
                 ) }
             </table>
         </> }
@@ -261,11 +519,21 @@ impl<'a> SailingsModel<'a> {
                     { "There was a problem loading the ferry schedules; please refresh your browser to try again." }
                 </div>
             },
+            SailingsStateModel::ReloadRequired => html! {
+                <div class="alert alert-warning text-center" role="alert">
+                    { "The ferry schedules have been updated for a newer version of this site; please reload your browser to see them." }
+                </div>
+            },
             SailingsStateModel::NoSchedule => html! {
                 <div class="alert alert-warning text-center" role="alert">
                     { "There is no schedule available for this date yet; please check back later!" }
                 </div>
             },
+            SailingsStateModel::NoDirectRoute => html! {
+                <div class="alert alert-light border text-center" role="alert">
+                    { "There are no direct sailings between these areas, but you can get there with connections." }
+                </div>
+            },
             SailingsStateModel::NoSailings => html! {
                 <div class="alert alert-light border text-center" role="alert">
                     { "There are no sailings between the these terminals on the specified date." }
@@ -275,8 +543,71 @@ impl<'a> SailingsModel<'a> {
         }
     }
 
+    fn journeys_html(&self) -> Html {
+        if self.journeys_loading {
+            html! {
+                <div class="mt-3 text-muted text-center">
+                    <div class="spinner-border spinner-border-sm" role="status"/>
+                    <small>{ " Loading connections..." }</small>
+                </div>
+            }
+        } else if self.journeys.is_empty() {
+            if let SailingsStateModel::NoDirectRoute = self.sailings_state_model {
+                html! {
+                    <div class="alert alert-light border text-center" role="alert">
+                        { "There are no connecting sailings between these areas on the specified date." }
+                    </div>
+                }
+            } else {
+                html! {}
+            }
+        } else {
+            html! { <>
+                <h6 class="mt-4">{ "Connections" }</h6>
+                <table class="table table-light mb-0">
+                    <thead class="table-dark">
+                        <tr>
+                            <th class="bg-heading">
+                                <span class="fw-normal">{ "Depart " }</span>
+                                <span class="text-nowrap">{ self.area_pair.from.long_name() }</span>
+                            </th>
+                            <th class="bg-heading">
+                                <span class="fw-normal">{ "Arrive " }</span>
+                                <span class="text-nowrap">{ self.area_pair.to.long_name() }</span>
+                            </th>
+                            <th class="bg-heading fw-normal">
+                                { "Sailings" }
+                            </th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for self.journeys.iter().map(journey_row_html) }
+                    </tbody>
+                </table>
+                <div class="text-muted">
+                    <small>
+                        { format!(
+                            "Connections allow at least {} minutes between sailings at the same terminal, or {} minutes to drive between terminals in the same area. Each sailing is ticketed separately.",
+                            DEFAULT_MIN_CONNECTION_TIME.whole_minutes(),
+                            DEFAULT_MIN_TERMINAL_CHANGE_TIME.whole_minutes(),
+                        ) }
+                    </small>
+                </div>
+            </> }
+        }
+    }
+
+    fn has_thrufares(&self) -> bool {
+        match &self.sailings_state_model {
+            SailingsStateModel::Sailings(schedule_sailings) => {
+                schedule_sailings.iter().any(|(_, a)| a.iter().any(|b| b.sailing.is_thrufare()))
+            }
+            _ => false,
+        }
+    }
+
     fn html(self) -> Html {
-        let info_urls = if self.area_pair.includes_terminal(Terminal::SWB)
+        let info_urls = if self.area_pair.includes_terminal(Terminal::new("SWB"))
             && self.area_pair.includes_any_terminal(&*ROUTE_5_AND_9_GULF_ISLAND_TERMINALS)
         {
             InformationUrlsModel {
@@ -284,7 +615,7 @@ impl<'a> SailingsModel<'a> {
                 departures_url: SWB_DEPARTURES_URL,
                 service_notices_url: SWB_SGI_SERVICE_NOTICES_URL,
             }
-        } else if self.area_pair.includes_terminal(Terminal::TSA)
+        } else if self.area_pair.includes_terminal(Terminal::new("TSA"))
             && self.area_pair.includes_any_terminal(&*ROUTE_5_AND_9_GULF_ISLAND_TERMINALS)
         {
             InformationUrlsModel {
@@ -300,16 +631,12 @@ impl<'a> SailingsModel<'a> {
             }
         };
         let is_reservable = self.area_pair.is_reservable();
-        let has_thrufares = match &self.sailings_state_model {
-            SailingsStateModel::Sailings(schedule_sailings) => {
-                schedule_sailings.iter().any(|(_, a)| a.iter().any(|b| b.sailing.is_thrufare()))
-            }
-            _ => false,
-        };
+        let has_thrufares = self.has_thrufares();
         html! { <>
             <div class="row mt-4">
                 <div class="col-12 col-md-8 col-lg-6">
                     { self.sailings_html() }
+                    { self.journeys_html() }
                 </div>
             </div>
             { if is_reservable || has_thrufares { html! { <>
@@ -442,6 +769,29 @@ impl FormModel {
         })
     }
 
+    fn holidays_html(&self) -> Html {
+        if self.holidays.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="row mb-3">
+                <div class="offset-2 offset-md-1 col-10 col-md-7 col-lg-5 small text-muted">
+                    { "Holidays with different sailings: " }
+                    { for self.holidays.iter().map(|&(date, holiday)| html! {
+                        <button
+                            type="button"
+                            class={ classes!("btn", "btn-sm", "py-0", "px-1", if date == self.view_date { "btn-secondary" } else { "btn-link" }) }
+                            title={ holiday.name() }
+                            onclick={ self.onclick_adjust_date_button_callback(Some(date)) }
+                        >
+                            { format!("{} ({})", holiday.name(), date.format(format_description!("[month repr:short] [day padding:none]")).expect("Expect holiday date to format")) }
+                        </button>
+                    }) }
+                </div>
+            </div>
+        }
+    }
+
     fn html(self) -> Html {
         html! {
             <div class="d-print-none">
@@ -471,7 +821,7 @@ impl FormModel {
                         </span>
                     </div>
                 </div>
-                <div class="row mb-3">
+                <div class={ classes!("row", if self.holidays.is_empty() { "mb-3" } else { "mb-1" }) }>
                     <label for="date-input" class="col-2 col-md-1 col-form-label">{ "Date" }</label>
                     <div class="col-10 col-md-7 col-lg-5 d-flex">
                         <input
@@ -522,6 +872,7 @@ impl FormModel {
                         </button>
                     </div>
                 </div>
+                { self.holidays_html() }
             </div>
         }
     }
@@ -538,12 +889,34 @@ pub fn sailings_component(props: &SailingsProps) -> Html {
         Some(date) => date,
     };
     let history = use_history().expect("Expect history to be available");
-    let schedules_state = use_context::<SchedulesState>().expect("Expect schedules state to be available");
+    let schedules_state = use_context::<SchedulesHandle>().expect("Expect schedules state to be available");
+    // Only fetch connecting routes once the direct routes have loaded, so that the main table isn't held up
+    let direct_terminal_pairs = direct_terminal_pairs(area_pair);
+    load_routes(&schedules_state, direct_terminal_pairs.iter().copied());
+    if let RoutesStatus::Loaded(_) = schedules_state.routes_status(direct_terminal_pairs.iter().copied()) {
+        load_routes(&schedules_state, journey_terminal_pairs(area_pair, DEFAULT_MAX_JOURNEY_LEGS));
+    }
     let date_input_state = use_state(|| DateInputState {
         input: format_iso8601_date(query_date_or_today),
         value: Ok(query_date_or_today),
     });
-    let sailings_model = SailingsModel::new(&schedules_state, &date_input_state, area_pair, query_date_or_today);
+    let journeys_cache = use_mut_ref(|| None);
+    let mut sailings_model =
+        SailingsModel::new(&schedules_state, &journeys_cache, &date_input_state, area_pair, query_date_or_today);
+    if sailings_model.view_date == today {
+        load_live(&schedules_state);
+        if let LiveState::Loaded(live) = &schedules_state.live {
+            sailings_model.live = Some(live.clone());
+        }
+    }
+    let holidays = match schedules_state.routes_status(direct_terminal_pairs.iter().copied()) {
+        RoutesStatus::Loaded(schedules_map) => holidays_with_different_sailings(
+            area_pair,
+            DateRange { from: today, to: sailings_model.max_date },
+            schedules_map,
+        ),
+        _ => Vec::new(),
+    };
     let form_model = FormModel {
         history,
         date_input_state,
@@ -552,9 +925,13 @@ pub fn sailings_component(props: &SailingsProps) -> Html {
         today,
         view_date: sailings_model.view_date,
         max_date: sailings_model.max_date,
+        holidays,
     };
+    let view_date = sailings_model.view_date;
+    let has_thrufares = sailings_model.has_thrufares();
     html! { <>
         { form_model.html() }
         { sailings_model.html() }
+        <FareEstimator terminal_pairs={ direct_terminal_pairs } date={ view_date } { has_thrufares }/>
     </> }
 }
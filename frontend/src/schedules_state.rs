@@ -0,0 +1,252 @@
+use reqwasm::http;
+
+use crate::imports::*;
+
+const ROUTE_INDEX_URL: &str = concatcp!("/data/", ROUTES_DIRECTORY, "/", ROUTE_INDEX_FILENAME);
+const FARES_URL: &str = concatcp!("/data/", FARES_FILENAME);
+const LIVE_URL: &str = concatcp!("/data/", LIVE_FILENAME);
+
+#[derive(Clone)]
+pub enum RouteIndexState {
+    Init,
+    Loading,
+    Loaded(Rc<RouteIndex>),
+    // Schedules were written in a newer format than this build of the frontend understands
+    ReloadRequired,
+    Failed,
+}
+
+#[derive(Clone)]
+pub enum FaresState {
+    Init,
+    Loading,
+    Loaded(Rc<Vec<Fare>>),
+    Failed,
+}
+
+#[derive(Clone)]
+pub enum LiveState {
+    Init,
+    Loading,
+    Loaded(Rc<LiveData>),
+    Failed,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum RouteState {
+    Loading,
+    Loaded,
+    Failed,
+}
+
+pub enum RoutesStatus<'a> {
+    Loading,
+    Loaded(&'a SchedulesMap),
+    ReloadRequired,
+    Failed,
+}
+
+pub enum SchedulesAction {
+    IndexLoading,
+    IndexLoaded(RouteIndex),
+    IndexFailed,
+    RouteLoading(TerminalPair),
+    RouteLoaded(TerminalPair, Vec<Schedule>),
+    RouteFailed(TerminalPair),
+    ReloadRequired,
+    FaresLoading,
+    FaresLoaded(Vec<Fare>),
+    FaresFailed,
+    LiveLoading,
+    LiveLoaded(LiveData),
+    LiveFailed,
+}
+
+#[derive(Clone)]
+pub struct SchedulesState {
+    pub index: RouteIndexState,
+    pub fares: FaresState,
+    pub live: LiveState,
+    route_states: HashMap<TerminalPair, RouteState>,
+    // Cloning the state for each action only copies the pointers to each route's schedules
+    schedules_map: SchedulesMap,
+    // Incremented on every change, so comparing states doesn't need to compare their contents
+    generation: u64,
+}
+
+pub type SchedulesHandle = UseReducerHandle<SchedulesState>;
+
+impl SchedulesState {
+    pub fn new() -> SchedulesState {
+        SchedulesState {
+            index: RouteIndexState::Init,
+            fares: FaresState::Init,
+            live: LiveState::Init,
+            route_states: HashMap::new(),
+            schedules_map: HashMap::new(),
+            generation: 0,
+        }
+    }
+
+    pub fn routes_status<I: IntoIterator<Item = TerminalPair>>(&self, terminal_pairs: I) -> RoutesStatus {
+        match &self.index {
+            RouteIndexState::Init | RouteIndexState::Loading => RoutesStatus::Loading,
+            RouteIndexState::ReloadRequired => RoutesStatus::ReloadRequired,
+            RouteIndexState::Failed => RoutesStatus::Failed,
+            RouteIndexState::Loaded(index) => {
+                let mut status = RoutesStatus::Loaded(&self.schedules_map);
+                // Routes that aren't in the index have no published schedules, so there is nothing to wait for
+                for terminal_pair in terminal_pairs.into_iter().filter(|tp| index.get(*tp).is_some()) {
+                    match self.route_states.get(&terminal_pair) {
+                        Some(RouteState::Loaded) => {}
+                        Some(RouteState::Failed) => return RoutesStatus::Failed,
+                        Some(RouteState::Loading) | None => status = RoutesStatus::Loading,
+                    }
+                }
+                status
+            }
+        }
+    }
+
+    pub fn max_date<I: IntoIterator<Item = TerminalPair>>(&self, terminal_pairs: I) -> Option<Date> {
+        match &self.index {
+            RouteIndexState::Loaded(index) => terminal_pairs
+                .into_iter()
+                .filter_map(|tp| index.get(tp))
+                .flat_map(|entry| entry.date_ranges.iter().map(|dr| dr.to))
+                .max(),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for SchedulesState {
+    fn eq(&self, other: &SchedulesState) -> bool {
+        self.generation == other.generation
+    }
+}
+
+impl Reducible for SchedulesState {
+    type Action = SchedulesAction;
+
+    fn reduce(self: Rc<Self>, action: SchedulesAction) -> Rc<Self> {
+        let mut state = (*self).clone();
+        state.generation += 1;
+        match action {
+            SchedulesAction::IndexLoading => state.index = RouteIndexState::Loading,
+            SchedulesAction::IndexLoaded(index) => state.index = RouteIndexState::Loaded(Rc::new(index)),
+            SchedulesAction::IndexFailed => state.index = RouteIndexState::Failed,
+            SchedulesAction::ReloadRequired => state.index = RouteIndexState::ReloadRequired,
+            SchedulesAction::RouteLoading(terminal_pair) => {
+                state.route_states.insert(terminal_pair, RouteState::Loading);
+            }
+            SchedulesAction::RouteLoaded(terminal_pair, schedules) => {
+                state.route_states.insert(terminal_pair, RouteState::Loaded);
+                state.schedules_map.insert(terminal_pair, Rc::new(schedules));
+            }
+            SchedulesAction::RouteFailed(terminal_pair) => {
+                state.route_states.insert(terminal_pair, RouteState::Failed);
+            }
+            SchedulesAction::FaresLoading => state.fares = FaresState::Loading,
+            SchedulesAction::FaresLoaded(fares) => state.fares = FaresState::Loaded(Rc::new(fares)),
+            SchedulesAction::FaresFailed => state.fares = FaresState::Failed,
+            SchedulesAction::LiveLoading => state.live = LiveState::Loading,
+            SchedulesAction::LiveLoaded(live) => state.live = LiveState::Loaded(Rc::new(live)),
+            SchedulesAction::LiveFailed => state.live = LiveState::Failed,
+        }
+        Rc::new(state)
+    }
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    let response = http::Request::get(url).send().await?;
+    let text = response.text().await?;
+    Ok(text)
+}
+
+// Schedules in a format this page can't read leave nothing to show, so the whole page asks to be reloaded
+fn dispatch_load_error(schedules: &SchedulesHandle, err: anyhow::Error, failed_action: SchedulesAction) {
+    if err.downcast_ref::<NewerFormatVersionError>().is_some() {
+        warn!("{:?}", err);
+        schedules.dispatch(SchedulesAction::ReloadRequired);
+    } else {
+        error!("{:?}", err);
+        schedules.dispatch(failed_action);
+    }
+}
+
+pub fn load_schedules_state(schedules: &SchedulesHandle) {
+    if !matches!(schedules.index, RouteIndexState::Init) {
+        return;
+    }
+    schedules.dispatch(SchedulesAction::IndexLoading);
+    let schedules = schedules.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        match fetch_text(ROUTE_INDEX_URL).await.and_then(|json| RouteIndex::from_json_str(&json)) {
+            Ok(index) => schedules.dispatch(SchedulesAction::IndexLoaded(index)),
+            Err(err) => dispatch_load_error(&schedules, err, SchedulesAction::IndexFailed),
+        }
+    });
+}
+
+pub fn load_routes<I: IntoIterator<Item = TerminalPair>>(schedules: &SchedulesHandle, terminal_pairs: I) {
+    let index = match &schedules.index {
+        RouteIndexState::Loaded(index) => index.clone(),
+        _ => return,
+    };
+    for terminal_pair in terminal_pairs {
+        if schedules.route_states.contains_key(&terminal_pair) {
+            continue;
+        }
+        let entry = match index.get(terminal_pair) {
+            Some(entry) => entry.clone(),
+            None => continue,
+        };
+        schedules.dispatch(SchedulesAction::RouteLoading(terminal_pair));
+        let schedules = schedules.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            // Hash in the URL ensures browser caches never serve an outdated route file
+            let url = format!("/data/{}/{}?v={}", ROUTES_DIRECTORY, entry.path, entry.hash);
+            match fetch_text(&url).await.and_then(|json| SchedulesData::from_json_str(&json)) {
+                Ok(data) => schedules.dispatch(SchedulesAction::RouteLoaded(terminal_pair, data.schedules)),
+                Err(err) => dispatch_load_error(&schedules, err, SchedulesAction::RouteFailed(terminal_pair)),
+            }
+        });
+    }
+}
+
+pub fn load_fares(schedules: &SchedulesHandle) {
+    if !matches!(schedules.fares, FaresState::Init) {
+        return;
+    }
+    schedules.dispatch(SchedulesAction::FaresLoading);
+    let schedules = schedules.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        match fetch_text(FARES_URL).await.and_then(|json| FaresData::from_json_str(&json)) {
+            Ok(data) => schedules.dispatch(SchedulesAction::FaresLoaded(data.fares)),
+            // Fares in a newer format only hide the fares, like any other failure, rather than the whole page
+            Err(err) => {
+                error!("{:?}", err);
+                schedules.dispatch(SchedulesAction::FaresFailed);
+            }
+        }
+    });
+}
+
+pub fn load_live(schedules: &SchedulesHandle) {
+    if !matches!(schedules.live, LiveState::Init) {
+        return;
+    }
+    schedules.dispatch(SchedulesAction::LiveLoading);
+    let schedules = schedules.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        match fetch_text(LIVE_URL).await.and_then(|json| LiveData::from_json_str(&json)) {
+            Ok(data) => schedules.dispatch(SchedulesAction::LiveLoaded(data)),
+            // As with fares, a newer format only hides the live sailing status
+            Err(err) => {
+                error!("{:?}", err);
+                schedules.dispatch(SchedulesAction::LiveFailed);
+            }
+        }
+    });
+}
@@ -23,28 +23,12 @@ impl SailingsQuery {
         SailingsQuery { from: None, to: None, date: None }
     }
 
-    pub fn is_empty(&self) -> bool {
-        matches!(self, SailingsQuery { from: None, to: None, date: None })
+    /// Areas in a URL may be aliases (e.g. "SWB" from old links), or not exist at all
+    pub fn resolved(&self) -> SailingsQuery {
+        SailingsQuery { from: self.from.and_then(|a| a.resolve()), to: self.to.and_then(|a| a.resolve()), ..*self }
     }
-}
 
-#[derive(Clone)]
-pub enum SchedulesState {
-    Init,
-    Loading,
-    Loaded(Rc<HashMap<TerminalPair, Vec<Schedule>>>),
-    Failed,
-}
-
-impl PartialEq for SchedulesState {
-    fn eq(&self, other: &SchedulesState) -> bool {
-        // For efficiency, we don't compare the contents because schedules are only loaded once
-        matches!(
-            (self, other),
-            (SchedulesState::Init, SchedulesState::Init)
-                | (SchedulesState::Loading, SchedulesState::Loading)
-                | (SchedulesState::Loaded(_), SchedulesState::Loaded(_))
-                | (SchedulesState::Failed, SchedulesState::Failed)
-        )
+    pub fn is_empty(&self) -> bool {
+        matches!(self, SailingsQuery { from: None, to: None, date: None })
     }
 }
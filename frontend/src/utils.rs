@@ -16,3 +16,43 @@ pub fn area_link_html(area: Area, query: SailingsQuery) -> Html {
         <Link<Route, SailingsQuery> to={Route::Sailings} {query}>{ area.long_name() }</Link<Route, SailingsQuery>>
     }
 }
+
+pub fn ical_data_url(ical: &str) -> String {
+    let mut url = String::from("data:text/calendar;charset=utf-8,");
+    for byte in ical.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// A link that downloads the events; the file is only generated when the link is clicked, since a page can have one
+/// for every sailing
+pub fn add_to_calendar_link_html(
+    filename: String,
+    calendar_name: String,
+    events: Vec<IcalEvent>,
+    label: &'static str,
+    classes: Classes,
+) -> Html {
+    let link_ref = NodeRef::default();
+    let onclick = {
+        let link_ref = link_ref.clone();
+        // The browser follows the link after the click handlers run, so it gets the href set here
+        Callback::from(move |_: MouseEvent| {
+            if let Some(link) = link_ref.cast::<Element>() {
+                link.set_attribute("href", &ical_data_url(&format_ical(&calendar_name, &events)))
+                    .expect("Expect calendar link href to set");
+            }
+        })
+    };
+    html! {
+        <a ref={ link_ref } class={ classes } title="Add to calendar" download={ filename } href="#" { onclick }>
+            <i class="bi bi-calendar-plus"/>
+            { label }
+        </a>
+    }
+}
@@ -0,0 +1,449 @@
+use crate::imports::*;
+use crate::macros::*;
+
+/// Byte offsets of part of an annotation's text
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Clause {
+    Only,
+    Except,
+    /// Dates on which the sailing carries only dangerous goods
+    DangerousGoodsOnly,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HolidayRef {
+    HolidayMondays,
+    AllHolidays,
+    Named(Holiday),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DateItemKind {
+    /// A month and day, whose year comes from the schedule's date range
+    Date(Month, u8),
+    /// First and last month and day, inclusive
+    DateSpan((Month, u8), (Month, u8)),
+    Holiday(HolidayRef),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DateItem {
+    pub kind: DateItemKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Annotation {
+    /// Dates on which the sailings in the cell do (or don't) run
+    Dates(Clause, Vec<DateItem>),
+    /// Dates on which the sailing at the time, which is marked with a star, does (or doesn't) run
+    TimeDates(Time, Clause, Vec<DateItem>),
+    DangerousGoodsOnly,
+    /// Any other text, without leading markers such as '!' or trailing punctuation
+    Note(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TokenKind {
+    Word,
+    Number,
+    Punct(char),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    span: Span,
+}
+
+/// Error for the part of an annotation's text that doesn't fit what is expected there
+pub fn annotation_span_error<T>(text: &str, span: Span, expected: &str) -> Result<T> {
+    let found = if span.start >= text.len() { "end of text" } else { &text[span.start..span.end] };
+    bail!("Expect {} at {}..{} of {:?}, but found {:?}", expected, span.start, span.end, text, found)
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = if c.is_alphabetic() {
+            TokenKind::Word
+        } else if c.is_ascii_digit() {
+            TokenKind::Number
+        } else if c.is_whitespace() {
+            continue;
+        } else {
+            TokenKind::Punct(c)
+        };
+        let mut end = start + c.len_utf8();
+        if kind != TokenKind::Punct(c) {
+            // Apostrophes are part of words such as "Year's"
+            let continues = |c: char| match kind {
+                TokenKind::Word => c.is_alphabetic() || c == '\'' || c == '’',
+                _ => c.is_ascii_digit(),
+            };
+            while let Some((index, c)) = chars.next_if(|(_, c)| continues(*c)) {
+                end = index + c.len_utf8();
+            }
+        }
+        tokens.push(Token { kind, text: &text[start..end], span: Span { start, end } });
+    }
+    tokens
+}
+
+fn parse_month(word: &str) -> Option<Month> {
+    let word = word.to_lowercase();
+    let word = if word == "sept" { "sep" } else { &word };
+    iter::successors(Some(Month::January), |m| Some(m.next())).take(12).find(|m| {
+        let name = m.to_string().to_lowercase();
+        word == name || word == &name[..3]
+    })
+}
+
+fn parse_holiday_ref(text: &str) -> Option<HolidayRef> {
+    if regex!(r"(?i)^holiday mondays$").is_match(text) {
+        Some(HolidayRef::HolidayMondays)
+    } else if regex!(r"(?i)^(statutory )?holidays$").is_match(text) {
+        Some(HolidayRef::AllHolidays)
+    } else {
+        Holiday::from_name(text).map(HolidayRef::Named)
+    }
+}
+
+// Notes that mean the sailing carries only dangerous goods, so it isn't shown
+fn is_dangerous_goods_only_note(text: &str) -> bool {
+    regex!(r"(?i)^(dangerous goods only|no passengers permitted - (dg sailing only|only sails on .*))$").is_match(text)
+}
+
+fn note_text(text: &str) -> String {
+    text.trim_start_matches(|c: char| c == '!' || c == '#' || c == '*' || c.is_whitespace())
+        .trim_end_matches(|c: char| c == '.' || c == ',' || c.is_whitespace())
+        .to_string()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek_at(&self, offset: usize) -> Option<Token<'a>> {
+        self.tokens.get(self.pos + offset).copied()
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.peek_at(0)
+    }
+
+    fn next_span(&self) -> Span {
+        self.peek().map(|t| t.span).unwrap_or(Span { start: self.text.len(), end: self.text.len() })
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T> {
+        annotation_span_error(self.text, self.next_span(), expected)
+    }
+
+    fn is_word_at(&self, offset: usize, word: &str) -> bool {
+        self.peek_at(offset).map(|t| t.kind == TokenKind::Word && t.text.eq_ignore_ascii_case(word)).unwrap_or(false)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek().map(|t| t.kind == TokenKind::Punct(c)).unwrap_or(false)
+    }
+
+    /// Consume the words if they're next, or nothing if they aren't all there
+    fn eat_words(&mut self, words: &[&str]) -> bool {
+        let matches = words.iter().enumerate().all(|(offset, word)| self.is_word_at(offset, word));
+        if matches {
+            self.pos += words.len();
+        }
+        matches
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let matches = self.is_punct(c);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    // The longest run of words (up to a holiday name's length) that refers to holidays, and the number of tokens
+    fn peek_holiday_ref(&self) -> Option<(HolidayRef, usize)> {
+        const MAX_HOLIDAY_WORDS: usize = 6;
+        let word_count = (0..MAX_HOLIDAY_WORDS)
+            .take_while(|offset| self.peek_at(*offset).map(|t| t.kind == TokenKind::Word).unwrap_or(false))
+            .count();
+        (1..=word_count).rev().find_map(|count| {
+            let start = self.tokens[self.pos].span.start;
+            let end = self.tokens[self.pos + count - 1].span.end;
+            parse_holiday_ref(&self.text[start..end]).map(|holiday_ref| (holiday_ref, count))
+        })
+    }
+
+    fn is_date_item_next(&self) -> bool {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Number, .. }) => true,
+            Some(Token { kind: TokenKind::Word, text, .. }) => {
+                parse_month(text).is_some() || self.peek_holiday_ref().is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_day(&mut self) -> Result<u8> {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Number, text, .. }) if text.len() <= 2 => {
+                self.pos += 1;
+                Ok(text.parse().expect("Expect one or two digits to parse to integer"))
+            }
+            _ => self.error("a day of the month"),
+        }
+    }
+
+    fn parse_optional_year(&mut self) {
+        let is_year = |t: Option<Token>| t.map(|t| t.kind == TokenKind::Number && t.text.len() == 4).unwrap_or(false);
+        if is_year(self.peek()) {
+            self.pos += 1;
+        } else if self.is_punct(',') && is_year(self.peek_at(1)) {
+            self.pos += 2;
+        }
+    }
+
+    // A month and day ("Jan 5" or "5 Jan"), or a day alone after another date in the same month ("Jan 5, 6")
+    fn parse_month_day(&mut self, previous_month: Option<Month>) -> Result<(Month, u8)> {
+        let start_span = self.next_span();
+        let month_at = |parser: &Self, offset| {
+            parser.peek_at(offset).filter(|t| t.kind == TokenKind::Word).and_then(|t| parse_month(t.text))
+        };
+        let (month, day) = if let Some(month) = month_at(self, 0) {
+            self.pos += 1;
+            self.eat_punct('.');
+            (month, self.parse_day()?)
+        } else if let Some(month) = month_at(self, 1) {
+            let day = self.parse_day()?;
+            self.pos += 1;
+            (month, day)
+        } else if let Some(month) = previous_month {
+            (month, self.parse_day()?)
+        } else {
+            return self.error("a date");
+        };
+        // Use a leap year, so that Feb 29 is valid
+        if Date::from_calendar_date(2020, month, day).is_err() {
+            let span = Span { start: start_span.start, end: self.tokens[self.pos - 1].span.end };
+            return annotation_span_error(self.text, span, "a valid date");
+        }
+        self.parse_optional_year();
+        Ok((month, day))
+    }
+
+    fn parse_date_item(&mut self, previous_month: Option<Month>) -> Result<DateItem> {
+        let start = self.next_span().start;
+        let kind = if let Some((holiday_ref, token_count)) = self.peek_holiday_ref() {
+            self.pos += token_count;
+            DateItemKind::Holiday(holiday_ref)
+        } else {
+            let from = self.parse_month_day(previous_month)?;
+            if self.eat_punct('-') || self.eat_punct('–') || self.eat_words(&["to"]) {
+                DateItemKind::DateSpan(from, self.parse_month_day(Some(from.0))?)
+            } else {
+                DateItemKind::Date(from.0, from.1)
+            }
+        };
+        let end = self.tokens[self.pos - 1].span.end;
+        Ok(DateItem { kind, span: Span { start, end } })
+    }
+
+    // Dates separated by commas, '&' or "and" (or nothing at all)
+    fn parse_date_list(&mut self) -> Result<Vec<DateItem>> {
+        let mut items = vec![self.parse_date_item(None)?];
+        loop {
+            let restart_pos = self.pos;
+            while self.eat_punct(',') || self.eat_punct('&') || self.eat_punct(';') || self.eat_words(&["and"]) {}
+            if !self.is_date_item_next() {
+                self.pos = restart_pos;
+                return Ok(items);
+            }
+            let previous_month = items.last().and_then(|item| match item.kind {
+                DateItemKind::Date(month, _) | DateItemKind::DateSpan(_, (month, _)) => Some(month),
+                DateItemKind::Holiday(_) => None,
+            });
+            items.push(self.parse_date_item(previous_month)?);
+        }
+    }
+
+    fn parse_clause_keyword(&mut self) -> Option<Clause> {
+        if self.eat_words(&["except"]) || self.eat_words(&["not", "available"]) {
+            Some(Clause::Except)
+        } else if self.eat_words(&["dg", "sailing", "only"]) {
+            Some(Clause::DangerousGoodsOnly)
+        } else if self.eat_words(&["only"]) {
+            Some(Clause::Only)
+        } else {
+            None
+        }
+    }
+
+    fn parse_time(&mut self) -> Result<Time> {
+        let start_span = self.next_span();
+        let is_kind = |t: Option<Token>, kind| t.map(|t| t.kind == kind).unwrap_or(false);
+        if !(is_kind(self.peek(), TokenKind::Number)
+            && is_kind(self.peek_at(1), TokenKind::Punct(':'))
+            && is_kind(self.peek_at(2), TokenKind::Number)
+            && is_kind(self.peek_at(3), TokenKind::Word))
+        {
+            return self.error("a time");
+        }
+        self.pos += 4;
+        let span = Span { start: start_span.start, end: self.tokens[self.pos - 1].span.end };
+        let time_text = regex!(r"\s+").replace_all(&self.text[span.start..span.end], "");
+        Time::parse(
+            &time_text,
+            format_description!("[hour repr:12 padding:none]:[minute][period case:lower case_sensitive:false]"),
+        )
+        .or_else(|_| annotation_span_error(self.text, span, "a valid time"))
+    }
+
+    // What may follow a clause: nothing, or a note that starts with '!'
+    fn parse_clause_end(&mut self, annotation: Annotation) -> Result<Vec<Annotation>> {
+        if self.is_at_end() {
+            Ok(vec![annotation])
+        } else if self.is_punct('!') {
+            let note = note_text(&self.text[self.next_span().start..]);
+            Ok(vec![annotation, Annotation::Note(note)])
+        } else {
+            self.error("the end of the dates")
+        }
+    }
+
+    // "*10:30 AM Not Available on: Jan 5, Jan 6*"
+    fn parse_time_dates(&mut self) -> Result<Vec<Annotation>> {
+        self.eat_punct('*');
+        let time = self.parse_time()?;
+        let clause = match self.parse_clause_keyword() {
+            Some(clause @ (Clause::Only | Clause::Except)) => clause,
+            _ => return self.error("\"Only\" or \"Not Available\""),
+        };
+        self.eat_words(&["on"]);
+        self.eat_punct(':');
+        let items = self.parse_date_list()?;
+        if !self.eat_punct('*') {
+            return self.error("'*'");
+        }
+        self.parse_clause_end(Annotation::TimeDates(time, clause, items))
+    }
+
+    fn parse(&mut self) -> Result<Vec<Annotation>> {
+        if self.is_punct('*') && self.peek_at(2).map(|t| t.kind == TokenKind::Punct(':')).unwrap_or(false) {
+            return self.parse_time_dates();
+        }
+        if let Some(clause) = self.parse_clause_keyword() {
+            let has_on = self.eat_words(&["on"]) || self.eat_words(&["onon"]);
+            let has_colon = self.eat_punct(':');
+            // Without "on" or a colon to introduce dates, the keyword may just start a note
+            if has_on || has_colon || self.is_date_item_next() {
+                let items = self.parse_date_list()?;
+                if clause == Clause::DangerousGoodsOnly {
+                    self.eat_punct(',');
+                    self.eat_words(&["no", "other", "passengers", "permitted"]);
+                }
+                return self.parse_clause_end(Annotation::Dates(clause, items));
+            }
+        } else if self.is_date_item_next() {
+            // "Jan 5, Jan 6 only"; a note may also happen to start with a number or month (e.g. "May be cancelled")
+            if let Ok(items) = self.parse_date_list() {
+                if self.eat_words(&["only"]) {
+                    return self.parse_clause_end(Annotation::Dates(Clause::Only, items));
+                }
+            }
+        }
+        Ok(vec![Annotation::Note(note_text(self.text))])
+    }
+}
+
+/// Parse the text of an annotation from a schedule's depart time cell, which may hold a clause and a note following
+/// it. Text that isn't a clause is a note, but text that starts like one and then doesn't fit is an error.
+pub fn parse_annotation(text: &str) -> Result<Vec<Annotation>> {
+    let text = text.trim().trim_end_matches('.');
+    if is_dangerous_goods_only_note(&note_text(text)) {
+        return Ok(vec![Annotation::DangerousGoodsOnly]);
+    }
+    Parser { text, tokens: tokenize(text), pos: 0 }.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_kinds(annotations: &[Annotation]) -> Vec<DateItemKind> {
+        annotations
+            .iter()
+            .flat_map(|a| match a {
+                Annotation::Dates(_, items) | Annotation::TimeDates(_, _, items) => items.clone(),
+                _ => vec![],
+            })
+            .map(|item| item.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_annotation() -> Result<()> {
+        use DateItemKind as K;
+        let annotations = parse_annotation("Except on: Jan 5, 6 & 7, Feb 1 - 3 and Family Day")?;
+        assert!(matches!(annotations[..], [Annotation::Dates(Clause::Except, _)]));
+        assert_eq!(
+            date_kinds(&annotations),
+            vec![
+                K::Date(Month::January, 5),
+                K::Date(Month::January, 6),
+                K::Date(Month::January, 7),
+                K::DateSpan((Month::February, 1), (Month::February, 3)),
+                K::Holiday(HolidayRef::Named(Holiday::FamilyDay)),
+            ]
+        );
+        assert_eq!(
+            date_kinds(&parse_annotation("Apr 3, 2024 and April 10, 2024 only.")?),
+            vec![K::Date(Month::April, 3), K::Date(Month::April, 10)]
+        );
+        assert_eq!(
+            date_kinds(&parse_annotation("Only on: National Day for Truth and Reconciliation and Sept 29")?),
+            vec![K::Holiday(HolidayRef::Named(Holiday::TruthAndReconciliationDay)), K::Date(Month::September, 29)]
+        );
+        let annotations = parse_annotation("*10:30 AM Not Available on: Dec 24, 31*")?;
+        assert!(matches!(annotations[..], [Annotation::TimeDates(t, Clause::Except, _)] if t == time!(10:30)));
+        assert_eq!(date_kinds(&annotations), vec![K::Date(Month::December, 24), K::Date(Month::December, 31)]);
+        assert_eq!(
+            parse_annotation("Only on: Jun 5 ! Reservations recommended")?[1],
+            Annotation::Note("Reservations recommended".to_string())
+        );
+        assert_eq!(
+            parse_annotation("No passengers permitted - DG Sailing only.")?,
+            vec![Annotation::DangerousGoodsOnly]
+        );
+        assert_eq!(
+            parse_annotation("May be cancelled due to low tide")?,
+            vec![Annotation::Note("May be cancelled due to low tide".to_string())]
+        );
+        let error = parse_annotation("Except on: Jan 5, Jan 32").expect_err("Expect invalid date to fail");
+        assert_eq!(
+            error.to_string(),
+            "Expect a valid date at 18..24 of \"Except on: Jan 5, Jan 32\", but found \"Jan 32\""
+        );
+        let error = parse_annotation("Except on: Jan 5 (weather permitting)").expect_err("Expect extra text to fail");
+        assert!(error.to_string().contains("at 17..18"), "{}", error);
+        Ok(())
+    }
+}
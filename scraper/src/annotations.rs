@@ -1,3 +1,4 @@
+use crate::annotation_grammar::*;
 use crate::imports::*;
 use crate::macros::*;
 
@@ -5,6 +6,8 @@ use crate::macros::*;
 pub struct AnnotationDates {
     pub only: HashSet<Date>,
     pub except: HashSet<Date>,
+    // An "only" clause was given, even if none of its dates are within the schedule (so the sailing never runs)
+    pub has_only_clause: bool,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -16,10 +19,14 @@ pub struct AnnotationNotes {
 pub struct Annotations {
     pub dg_dates: AnnotationDates,
     pub is_dg_only: bool,
+    // Sailing is listed on one day's schedule but departs just after midnight
+    pub is_after_midnight: bool,
     pub star_dates: AnnotationDates,
     pub star_dates_by_time: HashMap<Time, AnnotationDates>,
     pub all_dates: AnnotationDates,
     pub all_notes: AnnotationNotes,
+    /// Notes that were not recognized, which are passed through as they are but should be checked
+    pub notes_to_review: Vec<String>,
 }
 
 fn text_date_restriction<T: Into<Cow<'static, str>>>(notes: &mut AnnotationNotes, text: T) -> &mut AnnotationDates {
@@ -34,18 +41,39 @@ pub fn annotation_notes_date_restictions(
     AnnotationDates::map_to_date_restrictions_by_weekday(row_notes.map.into_iter(), weekday, date_restriction)
 }
 
+fn holiday_dates_within(date_range: &DateRange, holiday_ref: HolidayRef) -> Vec<Date> {
+    match holiday_ref {
+        HolidayRef::HolidayMondays => {
+            holidays_within(date_range).into_iter().map(|(d, _)| d).filter(|d| d.weekday() == Weekday::Monday).collect()
+        }
+        HolidayRef::AllHolidays => holidays_within(date_range).into_iter().map(|(d, _)| d).collect(),
+        HolidayRef::Named(holiday) => holiday.dates_within(date_range),
+    }
+}
+
+// Notes that are understood, so don't need review
+fn is_known_note(text: &str) -> bool {
+    [
+        "Saturna-bound vehicles arriving at the booth at least 15 minutes prior to sailing time are offered priority on this sailing",
+        "Foot passengers only",
+        "This sailing departs just before midnight",
+    ]
+    .contains(&text)
+}
+
 impl AnnotationDates {
     pub fn new() -> AnnotationDates {
-        AnnotationDates { only: HashSet::new(), except: HashSet::new() }
+        AnnotationDates { only: HashSet::new(), except: HashSet::new(), has_only_clause: false }
     }
 
     pub fn is_always(&self) -> bool {
-        self.only.is_empty() && self.except.is_empty()
+        self.only.is_empty() && self.except.is_empty() && !self.has_only_clause
     }
 
     pub fn extend(&mut self, other: &AnnotationDates) {
         self.except.extend(&other.except);
         self.only.extend(&other.only);
+        self.has_only_clause |= other.has_only_clause;
     }
 
     fn into_date_restriction(mut self) -> DateRestriction {
@@ -54,10 +82,10 @@ impl AnnotationDates {
             self.except.remove(&common_date);
             self.only.remove(&common_date);
         }
-        if !self.only.is_empty() {
-            DateRestriction::Only(self.only)
+        if !self.only.is_empty() || self.has_only_clause {
+            DateRestriction::Only(self.only.into_iter().collect())
         } else if !self.except.is_empty() {
-            DateRestriction::Except(self.except)
+            DateRestriction::Except(self.except.into_iter().collect())
         } else {
             DateRestriction::All
         }
@@ -117,106 +145,105 @@ impl Annotations {
         Annotations {
             dg_dates: AnnotationDates::new(),
             is_dg_only: false,
+            is_after_midnight: false,
             star_dates: AnnotationDates::new(),
             star_dates_by_time: HashMap::new(),
             all_dates: AnnotationDates::new(),
             all_notes: AnnotationNotes::new(),
+            notes_to_review: Vec::new(),
         }
     }
 
-    fn parse_single(&mut self, date_range: &DateRange, annotation_text: &str) -> Result<()> {
-        let mut inner = || {
-            let annotation_text = regex!(r"\.*$").replace(annotation_text, "");
-            let annotation_text = regex!(r"(?i)\bApril\b").replace_all(annotation_text.as_ref(), "Apr");
-            let annotation_text = regex!(r", \d{4}\b").replace_all(annotation_text.as_ref(), "");
-            let annotation_text = regex!(r"(?i)( & |, and | and )").replace_all(annotation_text.as_ref(), ", ");
-            let annotation_text = regex!(r"(?i)\b([a-z]{3})(\d{1,2})\b").replace_all(annotation_text.as_ref(), "$1 $2");
-            let annotation_text = regex!(r"(?i)\b([a-z]{3} \d{1,2}) ([a-z]{3} \d{1,2})\b")
-                .replace_all(annotation_text.as_ref(), "$1, $2");
-            let annotation_text = regex!(r"(?i)\b([a-z]{3}) (\d{1,2}),? (\d{1,2}),? (\d{1,2})\b")
-                .replace_all(annotation_text.as_ref(), "$1 $2, $1 $3, $1 $4");
-            let annotation_text = regex!(r"(?i)\b([a-z]{3}) (\d{1,2}),? (\d{1,2})\b")
-                .replace_all(annotation_text.as_ref(), "$1 $2, $1 $3");
-            let annotation_text = regex!(r"(?i)^([a-z]{3} \d{1,2})(, [a-z]{3} \d{1,2})* only$")
-                .replace(annotation_text.as_ref(), "Only $1$2");
-            let annotation_text = regex!(r"(?i)^(DG Sailing only .*), no other passengers permitted$")
-                .replace(annotation_text.as_ref(), "$1");
-            if let Some(captures) =
-                regex!(r"(?i)^\*(\d+:\d+ [AP]M) (Not Available|Only) on: (.*)\*").captures(annotation_text.as_ref())
-            {
-                let time_text = &captures[1];
-                let time = Time::parse(
-                    time_text,
-                    format_description!(
-                        "[hour repr:12 padding:none]:[minute] [period case:lower case_sensitive:false]"
-                    ),
-                )
-                .with_context(|| format!("Failed to parse time: {:?}", time_text))?;
-                let dates = self.star_dates_by_time.entry(time).or_insert_with(AnnotationDates::new);
-                let dates_hashset = match &captures[2] {
-                    "Not Available" => &mut dates.except,
-                    "Only" => &mut dates.only,
-                    other => bail!("Expect \"Not Available\" or \"Only\" in: {:?}", other),
-                };
-                for date_text in captures[3].split(',').map(|s| s.trim()) {
-                    let date_within_range = date_range.parse_date_within(date_text).with_context(|| {
-                        format!("Failed to parse sailing date {:?} in {:?}", date_text, annotation_text)
-                    })?;
-                    if let Some(date) = date_within_range {
-                        dates_hashset.insert(date);
-                    } else {
-                        warn!("Date is outside date range of schedule ({}): {:?}", date_range, date_text);
+    fn dates_for_clause(&mut self, clause: Clause, time: Option<Time>) -> &mut HashSet<Date> {
+        let dates = match (time, clause) {
+            (Some(time), _) => self.star_dates_by_time.entry(time).or_insert_with(AnnotationDates::new),
+            (None, Clause::DangerousGoodsOnly) => &mut self.dg_dates,
+            (None, _) => &mut self.all_dates,
+        };
+        match clause {
+            Clause::Except => &mut dates.except,
+            Clause::Only => {
+                dates.has_only_clause = true;
+                &mut dates.only
+            }
+            Clause::DangerousGoodsOnly => &mut dates.only,
+        }
+    }
+
+    fn add_dates(
+        &mut self,
+        date_range: &DateRange,
+        text: &str,
+        clause: Clause,
+        time: Option<Time>,
+        items: &[DateItem],
+    ) -> Result<()> {
+        let dates = self.dates_for_clause(clause, time);
+        for item in items {
+            let item_text = &text[item.span.start..item.span.end];
+            match item.kind {
+                DateItemKind::Holiday(holiday_ref) => dates.extend(holiday_dates_within(date_range, holiday_ref)),
+                DateItemKind::Date(month, day) => match date_range.month_day_within(month, day) {
+                    Some(date) => {
+                        dates.insert(date);
                     }
-                }
-            } else if let Some(captures) =
-                regex!(r"(?i)^(Except|Not Available|Only|DG Sailing only)( on| onon)?:? (.*)")
-                    .captures(annotation_text.as_ref())
-            {
-                let dates_hashset = match &captures[1] {
-                    "Except" | "Not Available" => &mut self.all_dates.except,
-                    "Only" => &mut self.all_dates.only,
-                    "DG Sailing only" => &mut self.dg_dates.only,
-                    other => bail!("Expect \"Except\", \"Only\", or \"DG Sailing only\" in: {:?}", other),
-                };
-                for date_text in captures[3].split(&[',', '&']).map(|s| s.trim()) {
-                    let date_within_range = date_range
-                        .parse_date_within(date_text)
-                        .with_context(|| format!("Failed to parse date {:?} in {:?}", date_text, annotation_text))?;
-                    if let Some(date) = date_within_range {
-                        dates_hashset.insert(date);
-                    } else {
-                        warn!("Date is outside date range of schedule ({}): {:?}", date_range, date_text);
+                    None => warn!("Date is outside date range of schedule ({}): {:?}", date_range, item_text),
+                },
+                DateItemKind::DateSpan((from_month, from_day), (to_month, to_day)) => {
+                    if from_month == to_month && from_day > to_day {
+                        return annotation_span_error(text, item.span, "a date span in order");
                     }
-                }
-            } else {
-                let replaced_annotation_text = regex!(r"([!#*]*)\s*").replace(annotation_text.as_ref(), "$1 ");
-                let replaced_annotation_text = regex!(r"[\.,]$").replace(replaced_annotation_text.as_ref(), "");
-                let annotation_text = replaced_annotation_text.trim();
-                if regex!(r"^(Dangerous goods only)|(No passengers permitted - DG Sailing only)|(No passengers permitted - only sails on .*)$").is_match(annotation_text) {
-                    self.is_dg_only = true;
-                } else {
-                    match annotation_text {
-                        "! Saturna-bound vehicles arriving at the booth at least 15 minutes prior to sailing time are offered priority on this sailing" => {
-                            text_date_restriction(
-                                &mut self.all_notes,
-                                "Saturna-bound vehicles arriving at the booth at least 15 minutes prior to sailing time are offered priority on this sailing"
-                            );
-                        }
-                        "Foot passengers only" => {
-                            text_date_restriction(&mut self.all_notes, "Foot passengers only");
-                        }
-                        "Note: This sailing departs just after midnight" => {
-                            text_date_restriction(&mut self.all_notes, "This sailing departs just after midnight");
+                    // A span such as "Dec 20 - Jan 3" ends in the following year
+                    let to_year_offset = i32::from(u8::from(from_month) > u8::from(to_month));
+                    let mut is_within = false;
+                    for year in date_range.from.year() - 1..=date_range.to.year() {
+                        let span_from = Date::from_calendar_date(year, from_month, from_day);
+                        let span_to = Date::from_calendar_date(year + to_year_offset, to_month, to_day);
+                        if let (Ok(span_from), Ok(span_to)) = (span_from, span_to) {
+                            // Only the part of the span that overlaps the schedule matters
+                            let from = span_from.max(date_range.from);
+                            let to = span_to.min(date_range.to);
+                            if from <= to {
+                                dates.extend(DateRange { from, to }.iter_days());
+                                is_within = true;
+                            }
                         }
-                        "This sailing departs just before midnight" => {
-                            text_date_restriction(&mut self.all_notes, "This sailing departs just before midnight");
-                        }
-                        "No sailings available on this route for these dates" => {}
-                        _ => bail!("Unrecognized annotation text: {:?}", annotation_text),
+                    }
+                    if !is_within {
+                        warn!("Date span is outside date range of schedule ({}): {:?}", date_range, item_text);
                     }
                 }
             }
-            Ok(())
+        }
+        Ok(())
+    }
+
+    fn add_note(&mut self, text: String) {
+        if regex!(r"(?i)^(note: )?this sailing departs just after midnight$").is_match(&text) {
+            self.is_after_midnight = true;
+        } else if text != "No sailings available on this route for these dates" {
+            if !is_known_note(&text) {
+                self.notes_to_review.push(text.clone());
+            }
+            text_date_restriction(&mut self.all_notes, text);
+        }
+    }
+
+    fn parse_single(&mut self, date_range: &DateRange, annotation_text: &str) -> Result<()> {
+        let mut inner = || {
+            for annotation in parse_annotation(annotation_text)? {
+                match annotation {
+                    Annotation::Dates(clause, items) => {
+                        self.add_dates(date_range, annotation_text, clause, None, &items)?
+                    }
+                    Annotation::TimeDates(time, clause, items) => {
+                        self.add_dates(date_range, annotation_text, clause, Some(time), &items)?
+                    }
+                    Annotation::DangerousGoodsOnly => self.is_dg_only = true,
+                    Annotation::Note(text) => self.add_note(text),
+                }
+            }
+            Ok(()) as Result<_>
         };
         inner().with_context(|| format!("Failed to parse annotation: {:?}", annotation_text))
     }
@@ -227,14 +254,86 @@ impl Annotations {
         annotation_texts: I,
     ) -> Result<()> {
         for annotation_text in annotation_texts {
-            if let Some(captures) = regex!(r"(?i)^((Except|Not Available|Only)( on)?:? [a-z]* \d*) (! .*)")
-                .captures(annotation_text.as_ref())
-            {
-                self.parse(date_range, [&captures[1], &captures[4]])?;
-            } else {
-                self.parse_single(date_range, annotation_text.as_ref())?;
-            }
+            self.parse_single(date_range, annotation_text.as_ref())?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_span_annotation() -> Result<()> {
+        let date_range = DateRange { from: date!(2023 - 10 - 10), to: date!(2024 - 03 - 31) };
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Except on: Dec 20 - Jan 3"])?;
+        assert_eq!(
+            annotations.all_dates.clone().into_date_restriction(),
+            DateRestriction::Except(
+                DateRange { from: date!(2023 - 12 - 20), to: date!(2024 - 01 - 03) }.iter_days().collect()
+            )
+        );
+        assert_eq!(
+            annotations.all_dates.into_date_restriction_by_weekday(Weekday::Saturday),
+            DateRestriction::Except([date!(2023 - 12 - 23), date!(2023 - 12 - 30)].into_iter().collect())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_date_span_outside_range_annotation() -> Result<()> {
+        let date_range = DateRange { from: date!(2024 - 06 - 27), to: date!(2024 - 09 - 02) };
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Except on: Dec 24 - Dec 26"])?;
+        assert_eq!(annotations.all_dates.clone().into_date_restriction(), DateRestriction::All);
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Only on: Dec 24 - Dec 26"])?;
+        assert!(annotations.all_dates.clone().into_date_restriction().is_never());
+        // A span that overlaps the end of the schedule only covers the overlapping part
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Only on: Aug 30 - Sep 5"])?;
+        assert_eq!(
+            annotations.all_dates.only,
+            HashSet::from([date!(2024 - 08 - 30), date!(2024 - 08 - 31), date!(2024 - 09 - 01), date!(2024 - 09 - 02)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_holiday_annotation() -> Result<()> {
+        let date_range = DateRange { from: date!(2024 - 06 - 27), to: date!(2024 - 10 - 14) };
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Except holiday Mondays"])?;
+        assert_eq!(
+            annotations.all_dates.except,
+            HashSet::from([
+                date!(2024 - 07 - 01),
+                date!(2024 - 08 - 05),
+                date!(2024 - 09 - 02),
+                date!(2024 - 09 - 30),
+                date!(2024 - 10 - 14),
+            ])
+        );
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["Only on: Canada Day & Thanksgiving Day"])?;
+        assert_eq!(annotations.all_dates.only, HashSet::from([date!(2024 - 07 - 01), date!(2024 - 10 - 14)]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_unrecognized_note() -> Result<()> {
+        let date_range = DateRange { from: date!(2024 - 06 - 27), to: date!(2024 - 10 - 14) };
+        let mut annotations = Annotations::new();
+        annotations.parse(&date_range, ["! Foot passengers only.", "# Vehicle reservations are required"])?;
+        assert_eq!(
+            annotations.all_notes.map.keys().sorted().collect::<Vec<_>>(),
+            vec!["Foot passengers only", "Vehicle reservations are required"]
+        );
+        assert_eq!(annotations.notes_to_review, vec!["Vehicle reservations are required"]);
+        let error = annotations.parse(&date_range, ["Except on: Jul 3 - Jul 1"]).expect_err("Expect span to fail");
+        assert!(format!("{:?}", error).contains("at 11..24"), "{:?}", error);
+        Ok(())
+    }
+}
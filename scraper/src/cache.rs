@@ -1,46 +1,152 @@
-use directories::ProjectDirs;
 use scraper::Html;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::Path;
 use std::sync::atomic::AtomicUsize;
-use std::sync::{atomic, Arc};
+use std::sync::{atomic, Arc, Mutex};
 use tokio_retry::{strategy, Retry};
 
+use crate::fetch::*;
 use crate::imports::*;
-use crate::macros::*;
-use crate::utils::*;
+use crate::types::*;
+use ferrysched_shared::data_format::*;
 
 const MAX_RETRIES: usize = 5;
 
+const INDEX_FILENAME: &str = "index.json";
+const LAST_GOOD_DIRECTORY: &str = "last-good";
+const LAST_GOOD_FARES_FILENAME: &str = "fares.json";
+
+/// What the cache knows about a page besides its contents, which are in the file named by `saved_page_filename`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CacheEntry {
+    pub status: u16,
+    #[serde(flatten)]
+    pub validators: Validators,
+    /// When the contents were last downloaded
+    pub fetched_at: OffsetDateTime,
+    /// When the server last confirmed the contents are current
+    pub checked_at: OffsetDateTime,
+    /// When the contents last differed from the previous download
+    pub changed_at: OffsetDateTime,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CacheIndex {
+    /// Keyed by URL
+    pub pages: BTreeMap<String, CacheEntry>,
+}
+
+impl CacheIndex {
+    pub fn read(cache_dir: &Path) -> Result<CacheIndex> {
+        let path = cache_dir.join(INDEX_FILENAME);
+        let inner = || {
+            let index = match fs::read_to_string(&path) {
+                Ok(json) => serde_json::from_str(&json)?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => CacheIndex::default(),
+                Err(err) => return Err(err.into()),
+            };
+            Ok(index) as Result<_>
+        };
+        inner().with_context(|| format!("Failed to read cache index: {:?}", path))
+    }
+
+    // Replaces the file in one step, so that an interrupted scrape can't leave it half-written
+    pub fn write(&self, cache_dir: &Path) -> Result<()> {
+        let path = cache_dir.join(INDEX_FILENAME);
+        let inner = || {
+            fs::create_dir_all(cache_dir)?;
+            let mut file = tempfile::NamedTempFile::new_in(cache_dir)?;
+            serde_json::to_writer_pretty(&mut file, self)?;
+            file.persist(&path)?;
+            Ok(()) as Result<_>
+        };
+        inner().with_context(|| format!("Failed to write cache index: {:?}", path))
+    }
+}
+
 #[derive(Debug)]
-pub struct Cache<'a> {
+pub struct Cache {
     max_cache_age: Duration,
-    project_dirs: &'a ProjectDirs,
-    reqwest_client: reqwest::Client,
+    /// Directory to cache pages in, or `None` to always use the fetcher
+    cache_dir: Option<PathBuf>,
+    index: Mutex<CacheIndex>,
+    fetcher: Box<dyn Fetcher>,
+    /// Pages downloaded in this run whose contents differ from what was cached
+    changed_urls: Mutex<BTreeSet<String>>,
+    /// Current time, so that tests can control it
+    now: fn() -> OffsetDateTime,
 }
 
-impl<'a> Cache<'a> {
-    pub fn new(max_cache_age: Duration, project_dirs: &'a ProjectDirs) -> Cache<'a> {
-        let reqwest_client =
-            reqwest::ClientBuilder::new().cookie_store(true).build().expect("Build build reqwest client to succeed");
-        Cache { max_cache_age, project_dirs, reqwest_client }
+impl Cache {
+    pub fn new(max_cache_age: Duration, cache_dir: Option<PathBuf>, fetcher: Box<dyn Fetcher>) -> Cache {
+        // A missing or damaged index only means pages get downloaded again
+        let index = cache_dir
+            .as_deref()
+            .map(|dir| {
+                CacheIndex::read(dir).unwrap_or_else(|err| {
+                    warn!("Ignoring cache index: {:?}", err);
+                    CacheIndex::default()
+                })
+            })
+            .unwrap_or_default();
+        Cache {
+            max_cache_age,
+            cache_dir,
+            index: Mutex::new(index),
+            fetcher,
+            changed_urls: Mutex::new(BTreeSet::new()),
+            now: now_utc,
+        }
+    }
+
+    /// Fetch pages the way the options say: from the live site through the cache directory, from the cache directory
+    /// only when offline, or from a replay directory
+    pub fn from_options(options: &Options, cache_dir: PathBuf) -> Cache {
+        let max_cache_age = Duration::hours(options.max_cache_age);
+        let http_fetcher =
+            || HttpFetcher::new(options.parallelism, std::time::Duration::from_millis(options.min_request_interval));
+        if let Some(replay_dir) = &options.replay_dir {
+            Cache::new(max_cache_age, None, Box::new(ReplayFetcher::new(replay_dir.clone())))
+        } else if options.offline {
+            Cache::new(max_cache_age, None, Box::new(ReplayFetcher::new(cache_dir)))
+        } else if let Some(record_dir) = &options.record_dir {
+            // Bypass the cache, so that every page the run sees is fetched and recorded
+            Cache::new(
+                max_cache_age,
+                None,
+                Box::new(RecordingFetcher::new(Box::new(http_fetcher()), record_dir.clone())),
+            )
+        } else {
+            Cache::new(max_cache_age, Some(cache_dir), Box::new(http_fetcher()))
+        }
+    }
+
+    /// Use a different maximum age for cached pages, such as zero for pages that change by the minute
+    pub fn with_max_cache_age(self, max_cache_age: Duration) -> Cache {
+        Cache { max_cache_age, ..self }
     }
 
     async fn fetch_retry_action<T, F>(
         &self,
         url: &str,
+        validators: &Validators,
         retry_number: Arc<AtomicUsize>,
         transform: &F,
-    ) -> Result<(T, String)>
+    ) -> Result<Option<(T, FetchedPage)>>
     where
         F: Fn(String) -> Result<(T, String)>,
     {
         let retry_number = retry_number.fetch_add(1, atomic::Ordering::SeqCst) + 1;
-        let inner = async {
-            let response = self.reqwest_client.get(url).send().await?.error_for_status()?;
-            transform(response.text().await?)
+        let result = match self.fetcher.fetch(url, validators).await {
+            Ok(Fetched::Page(page)) => {
+                transform(page.contents).map(|(value, contents)| Some((value, FetchedPage { contents, ..page })))
+            }
+            Ok(Fetched::NotModified) => Ok(None),
+            Err(err) => Err(err),
         };
-        let result = inner.await;
         match &result {
-            Err(err) if retry_number <= MAX_RETRIES => {
+            Err(err) if retry_number <= MAX_RETRIES && self.fetcher.is_retryable() => {
                 warn!("Will retry (#{} of {}) fetching {:?} due to: {}", retry_number, MAX_RETRIES, url, err)
             }
             _ => {}
@@ -48,39 +154,170 @@ impl<'a> Cache<'a> {
         result
     }
 
+    // The cached value of the page, if it's in the index and its contents still transform successfully
+    fn read_cached<T, F>(&self, url: &str, transform: &F) -> Option<(T, CacheEntry)>
+    where
+        F: Fn(String) -> Result<(T, String)>,
+    {
+        let cache_dir = self.cache_dir.as_ref()?;
+        let entry = self.index.lock().expect("Expect cache index lock to succeed").pages.get(url)?.clone();
+        let cache_path = cache_dir.join(saved_page_filename(url));
+        debug!("Cache path: {:?}", cache_path);
+        let (value, _) = transform(fs::read_to_string(&cache_path).ok()?).ok()?;
+        Some((value, entry))
+    }
+
+    fn update_index<F: FnOnce(&mut CacheIndex)>(&self, cache_dir: &Path, update: F) -> Result<()> {
+        let mut index = self.index.lock().expect("Expect cache index lock to succeed");
+        update(&mut index);
+        index.write(cache_dir)
+    }
+
+    fn store_page(&self, url: &str, page: FetchedPage) -> Result<()> {
+        let cache_dir = match &self.cache_dir {
+            Some(cache_dir) => cache_dir,
+            None => return Ok(()),
+        };
+        let cache_path = cache_dir.join(saved_page_filename(url));
+        let changed = fs::read_to_string(&cache_path).ok().as_ref() != Some(&page.contents);
+        fs::create_dir_all(cache_dir)?;
+        fs::write(&cache_path, &page.contents)?;
+        if changed {
+            self.changed_urls.lock().expect("Expect changed URLs lock to succeed").insert(url.to_string());
+        }
+        let now = (self.now)();
+        self.update_index(cache_dir, |index| {
+            let changed_at = match index.pages.get(url) {
+                Some(entry) if !changed => entry.changed_at,
+                _ => now,
+            };
+            let entry = CacheEntry {
+                status: page.status,
+                validators: page.validators,
+                fetched_at: now,
+                checked_at: now,
+                changed_at,
+            };
+            index.pages.insert(url.to_string(), entry);
+        })
+    }
+
     pub async fn fetch_url<T, F>(&self, url: &str, transform: F) -> Result<T>
     where
         F: Fn(String) -> Result<(T, String)>,
     {
         let inner = async {
-            let mut cache_path = PathBuf::new();
-            cache_path.push(self.project_dirs.cache_dir());
-            let cache_filename = format!("{}_{}", regex!(r"[^\w\d-]+").replace_all(url, "_"), calculate_hash(&url));
-            cache_path.push(&cache_filename);
-            debug!("Cache path: {:?}", cache_path);
-            if let Ok(cache_metadata) = fs::metadata(&cache_path) {
-                let cache_modified_time: OffsetDateTime = cache_metadata.modified()?.into();
-                if OffsetDateTime::now_utc() - cache_modified_time < self.max_cache_age {
-                    if let Ok((cached_value, _)) = transform(fs::read_to_string(&cache_path)?) {
-                        info!("Using cache: {:?}", url);
-                        return Ok(cached_value);
-                    }
+            let cached = match self.read_cached(url, &transform) {
+                Some((cached_value, entry)) if (self.now)() - entry.checked_at < self.max_cache_age => {
+                    info!("Using cache: {:?}", url);
+                    return Ok(cached_value);
                 }
-            }
-            info!("Fetching: {:?}", url);
+                cached => cached,
+            };
+            // Only ask whether the page changed if there's a usable cached copy to fall back on
+            let validators = cached.as_ref().map(|(_, entry)| entry.validators.clone()).unwrap_or_default();
             let retry_number = Arc::new(AtomicUsize::new(0));
-            let (value, contents): (_, String) =
-                Retry::spawn(strategy::FibonacciBackoff::from_millis(5).factor(1000).take(MAX_RETRIES), || {
-                    self.fetch_retry_action(url, retry_number.clone(), &transform)
+            let max_retries = if self.fetcher.is_retryable() { MAX_RETRIES } else { 0 };
+            let fetched =
+                Retry::spawn(strategy::FibonacciBackoff::from_millis(5).factor(1000).take(max_retries), || {
+                    self.fetch_retry_action(url, &validators, retry_number.clone(), &transform)
                 })
                 .await?;
-            fs::create_dir_all(self.project_dirs.cache_dir())?;
-            fs::write(&cache_path, &contents)?;
-            Ok(value) as Result<_>
+            match (fetched, cached, &self.cache_dir) {
+                (Some((value, page)), _, _) => {
+                    self.store_page(url, page)?;
+                    Ok(value)
+                }
+                (None, Some((cached_value, _)), Some(cache_dir)) => {
+                    info!("Not modified: {:?}", url);
+                    let now = (self.now)();
+                    self.update_index(cache_dir, |index| {
+                        if let Some(entry) = index.pages.get_mut(url) {
+                            entry.checked_at = now;
+                        }
+                    })?;
+                    Ok(cached_value)
+                }
+                (None, _, _) => bail!("Server says the page is not modified, but it isn't cached"),
+            }
         };
         inner.await.with_context(|| format!("Failed to fetch URL with cache: {:?}", url))
     }
 
+    fn last_good_schedules_path(&self, terminal_pair: TerminalPair) -> Option<PathBuf> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        Some(cache_dir.join(LAST_GOOD_DIRECTORY).join(format!("{}.json", terminal_pair)))
+    }
+
+    /// Keep the route's schedules, in case a later scrape of it fails
+    pub fn write_last_good_schedules(&self, terminal_pair: TerminalPair, schedules: &[Schedule]) -> Result<()> {
+        let path = match self.last_good_schedules_path(terminal_pair) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let inner = || {
+            fs::create_dir_all(path.parent().expect("Expect last good schedules path to have parent"))?;
+            fs::write(&path, serde_json::to_vec(&SchedulesData::new(schedules.to_vec(), (self.now)()))?)?;
+            Ok(()) as Result<_>
+        };
+        inner().with_context(|| format!("Failed to write last known good schedules: {:?}", path))
+    }
+
+    /// The route's schedules from the last time it was scraped successfully, if they were kept
+    pub fn read_last_good_schedules(&self, terminal_pair: TerminalPair) -> Result<Option<Vec<Schedule>>> {
+        let path = match self.last_good_schedules_path(terminal_pair) {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let inner = || Ok(Some(SchedulesData::from_json_str(&fs::read_to_string(&path)?)?.schedules)) as Result<_>;
+        inner().with_context(|| format!("Failed to read last known good schedules: {:?}", path))
+    }
+
+    fn last_good_fares_path(&self) -> Option<PathBuf> {
+        Some(self.cache_dir.as_ref()?.join(LAST_GOOD_DIRECTORY).join(LAST_GOOD_FARES_FILENAME))
+    }
+
+    /// Replace the kept fares of the routes that were scraped, in case a later scrape of them fails
+    pub fn update_last_good_fares(&self, fares: &[Fare], terminal_pairs: &HashSet<TerminalPair>) -> Result<()> {
+        let path = match self.last_good_fares_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let inner = || {
+            let mut last_good_fares = self.read_last_good_fares()?.unwrap_or_default();
+            last_good_fares.retain(|f| !terminal_pairs.contains(&f.terminal_pair));
+            last_good_fares.extend(fares.iter().filter(|f| terminal_pairs.contains(&f.terminal_pair)).cloned());
+            fs::create_dir_all(path.parent().expect("Expect last good fares path to have parent"))?;
+            fs::write(&path, serde_json::to_vec(&FaresData::new(last_good_fares, (self.now)()))?)?;
+            Ok(()) as Result<_>
+        };
+        inner().with_context(|| format!("Failed to write last known good fares: {:?}", path))
+    }
+
+    /// Every route's fares from the last time they were scraped successfully, if they were kept
+    pub fn read_last_good_fares(&self) -> Result<Option<Vec<Fare>>> {
+        let path = match self.last_good_fares_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let inner = || Ok(Some(FaresData::from_json_str(&fs::read_to_string(&path)?)?.fares)) as Result<_>;
+        inner().with_context(|| format!("Failed to read last known good fares: {:?}", path))
+    }
+
+    /// Log the pages downloaded in this run whose contents changed since they were cached
+    pub fn log_changed_pages(&self) {
+        if self.cache_dir.is_none() {
+            return;
+        }
+        let changed_urls = self.changed_urls.lock().expect("Expect changed URLs lock to succeed");
+        if changed_urls.is_empty() {
+            info!("No downloaded pages changed since they were cached");
+        } else {
+            info!("{} downloaded page(s) changed since they were cached:", changed_urls.len());
+            changed_urls.iter().for_each(|url| info!("  {}", url));
+        }
+    }
+
     pub async fn get_html(&self, url: &str, error_regex: &Regex) -> Result<Html> {
         let transform_html = |contents: String| {
             if error_regex.is_match(&contents) {
@@ -94,3 +331,184 @@ impl<'a> Cache<'a> {
         self.fetch_url(url, transform_html).await
     }
 }
+
+fn remove_cached_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("Failed to remove cached file: {:?}", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Removes pages that aren't to be kept, and files that aren't pages in the index (such as those cached before there was
+// an index), but not directories such as the last known good data; returns the number of files removed
+fn remove_cached_pages<F: Fn(&CacheEntry) -> bool>(cache_dir: &Path, keep: F) -> Result<usize> {
+    let mut index = CacheIndex::read(cache_dir)?;
+    index.pages.retain(|url, entry| keep(entry) && cache_dir.join(saved_page_filename(url)).exists());
+    let keep_filenames: HashSet<_> =
+        index.pages.keys().map(|url| saved_page_filename(url)).chain(iter::once(INDEX_FILENAME.to_string())).collect();
+    let mut removed_count = 0;
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read cache directory: {:?}", cache_dir)),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let filename = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_file() && !keep_filenames.contains(&filename) {
+            remove_cached_file(&entry.path())?;
+            removed_count += 1;
+        }
+    }
+    index.write(cache_dir)?;
+    Ok(removed_count)
+}
+
+// Removes pages not checked within the maximum age
+fn prune_cache(cache_dir: &Path, max_age: Duration, now: OffsetDateTime) -> Result<usize> {
+    remove_cached_pages(cache_dir, |entry| now - entry.checked_at < max_age)
+}
+
+fn clear_cache(cache_dir: &Path) -> Result<usize> {
+    remove_cached_pages(cache_dir, |_| false)
+}
+
+pub fn run_cache_command(options: &CacheOptions, cache_dir: &Path) -> Result<()> {
+    const TIME_FORMAT: &TimeFormat = format_description!("[year]-[month]-[day] [hour]:[minute]");
+    let format_time = |datetime: OffsetDateTime| {
+        datetime.to_timezone(timezones::db::america::VANCOUVER).format(TIME_FORMAT).expect("Expect time to format")
+    };
+    let inner = || {
+        match &options.command {
+            CacheCommand::List => {
+                let index = CacheIndex::read(cache_dir)?;
+                let mut stdout = io::stdout().lock();
+                for (url, entry) in &index.pages {
+                    writeln!(
+                        stdout,
+                        "{}  checked {}  changed {}  {}",
+                        entry.status,
+                        format_time(entry.checked_at),
+                        format_time(entry.changed_at),
+                        url
+                    )?;
+                }
+                info!("{} cached page(s) in: {:?}", index.pages.len(), cache_dir);
+            }
+            CacheCommand::Prune(prune_options) => {
+                let removed_count = prune_cache(cache_dir, Duration::hours(prune_options.max_age), now_utc())?;
+                info!("Removed {} cached file(s)", removed_count);
+            }
+            CacheCommand::Clear => {
+                let removed_count = clear_cache(cache_dir)?;
+                info!("Cleared cache, removing {} file(s): {:?}", removed_count, cache_dir);
+            }
+        }
+        Ok(()) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to manage cache: {:?}", cache_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future::BoxFuture;
+    use std::sync::atomic::AtomicI64;
+
+    // Seconds since the epoch of the time the test cache sees, which only changes when the test advances it
+    static TEST_NOW_TIMESTAMP: AtomicI64 = AtomicI64::new(1_719_792_000);
+
+    fn test_now() -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(TEST_NOW_TIMESTAMP.load(atomic::Ordering::SeqCst))
+            .expect("Expect test time to be valid")
+    }
+
+    fn advance_test_now(duration: Duration) {
+        TEST_NOW_TIMESTAMP.fetch_add(duration.whole_seconds(), atomic::Ordering::SeqCst);
+    }
+
+    // Serves a page whose contents can be changed, and honours its ETag
+    #[derive(Debug)]
+    struct VersionedFetcher {
+        version: Arc<Mutex<u32>>,
+    }
+
+    impl Fetcher for VersionedFetcher {
+        fn fetch<'a>(&'a self, _url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>> {
+            Box::pin(async move {
+                let version = *self.version.lock().expect("Expect version lock to succeed");
+                let etag = format!("\"{}\"", version);
+                Ok(if validators.etag.as_ref() == Some(&etag) {
+                    Fetched::NotModified
+                } else {
+                    Fetched::Page(FetchedPage {
+                        status: 200,
+                        validators: Validators { etag: Some(etag), last_modified: None },
+                        contents: format!("version {}", version),
+                    })
+                })
+            })
+        }
+
+        fn is_retryable(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_fetch() -> Result<()> {
+        let url = "https://www.bcferries.com/routes-fares/schedules/seasonal/HSB-NAN";
+        let identity = |contents: String| Ok((contents.clone(), contents)) as Result<(String, String)>;
+        let dir = tempfile::tempdir()?;
+        let version = Arc::new(Mutex::new(1));
+        // With no maximum age, every fetch checks whether the page changed
+        let new_cache = || {
+            let fetcher = VersionedFetcher { version: version.clone() };
+            Cache { now: test_now, ..Cache::new(Duration::ZERO, Some(dir.path().to_path_buf()), Box::new(fetcher)) }
+        };
+        let cache = new_cache();
+        assert_eq!(cache.fetch_url(url, identity).await?, "version 1");
+        let first_entry = CacheIndex::read(dir.path())?.pages[url].clone();
+        assert_eq!(first_entry.validators.etag.as_deref(), Some("\"1\""));
+
+        advance_test_now(Duration::minutes(10));
+        let cache = new_cache();
+        assert_eq!(cache.fetch_url(url, identity).await?, "version 1");
+        let entry = CacheIndex::read(dir.path())?.pages[url].clone();
+        assert_eq!((entry.fetched_at, entry.changed_at), (first_entry.fetched_at, first_entry.changed_at));
+        assert_eq!(entry.checked_at, first_entry.checked_at + Duration::minutes(10));
+        assert!(cache.changed_urls.lock().expect("Expect changed URLs lock to succeed").is_empty());
+
+        advance_test_now(Duration::minutes(10));
+        *version.lock().expect("Expect version lock to succeed") = 2;
+        assert_eq!(cache.fetch_url(url, identity).await?, "version 2");
+        let entry = CacheIndex::read(dir.path())?.pages[url].clone();
+        assert_eq!(entry.changed_at, first_entry.changed_at + Duration::minutes(20));
+        assert!(cache.changed_urls.lock().expect("Expect changed URLs lock to succeed").contains(url));
+
+        fs::write(dir.path().join("orphan"), "")?;
+        assert_eq!(prune_cache(dir.path(), Duration::hours(1), test_now())?, 1);
+        assert_eq!(prune_cache(dir.path(), Duration::hours(1), test_now() + Duration::hours(2))?, 1);
+        assert!(CacheIndex::read(dir.path())?.pages.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clear_keeps_last_good() -> Result<()> {
+        let url = "https://www.bcferries.com/routes-fares/schedules/seasonal/HSB-NAN";
+        let identity = |contents: String| Ok((contents.clone(), contents)) as Result<(String, String)>;
+        let dir = tempfile::tempdir()?;
+        let fetcher = VersionedFetcher { version: Arc::new(Mutex::new(1)) };
+        let cache = Cache::new(Duration::ZERO, Some(dir.path().to_path_buf()), Box::new(fetcher));
+        cache.fetch_url(url, identity).await?;
+        let terminal_pair = TerminalPair { from: Terminal::new("HSB"), to: Terminal::new("NAN") };
+        cache.write_last_good_schedules(terminal_pair, &[])?;
+        assert_eq!(clear_cache(dir.path())?, 1);
+        assert!(CacheIndex::read(dir.path())?.pages.is_empty());
+        assert!(!dir.path().join(saved_page_filename(url)).exists());
+        assert_eq!(cache.read_last_good_schedules(terminal_pair)?.map(|s| s.len()), Some(0));
+        Ok(())
+    }
+}
@@ -3,4 +3,12 @@ use crate::imports::*;
 pub static HTML_ERROR_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"An error occurred, try again").expect("Expect HTML error regex to parse"));
 
-pub static DISABLED_TERMINAL_PAIRS: Lazy<HashSet<TerminalPair>> = Lazy::new(|| HashSet::from_iter([]));
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Monday,
+    Weekday::Tuesday,
+    Weekday::Wednesday,
+    Weekday::Thursday,
+    Weekday::Friday,
+    Weekday::Saturday,
+    Weekday::Sunday,
+];
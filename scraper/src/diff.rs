@@ -0,0 +1,390 @@
+use crate::constants::*;
+use crate::imports::*;
+use crate::types::*;
+
+use ferrysched_shared::data_format::*;
+
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum ScheduleChangeKind {
+    ScheduleAdded,
+    ScheduleRemoved,
+    DateRangeChanged { old_date_range: DateRange },
+    SailingAdded { sailing: Sailing },
+    SailingRemoved { sailing: Sailing },
+    SailingRetimed { old_sailing: Sailing, new_sailing: Sailing },
+    StopsChanged { depart_time: Time, old_stops: Vec<Stop>, new_stops: Vec<Stop> },
+    VesselChanged { depart_time: Time, old_vessel: Option<String>, new_vessel: Option<String> },
+    WeekdayChanged { depart_time: Time, weekday: Weekday, old: Option<DateRestriction>, new: Option<DateRestriction> },
+    NoteChanged { depart_time: Time, note: String, old: Option<DateRestriction>, new: Option<DateRestriction> },
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct ScheduleChange {
+    pub terminal_pair: TerminalPair,
+    pub date_range: DateRange,
+    #[serde(flatten)]
+    pub kind: ScheduleChangeKind,
+}
+
+struct ScheduleDiffer<'a> {
+    old_schedule: &'a Schedule,
+    new_schedule: &'a Schedule,
+    changes: Vec<ScheduleChange>,
+}
+
+fn format_diff_time(time: Time) -> String {
+    time.format(format_description!("[hour]:[minute]")).expect("Expect diff time to format")
+}
+
+fn format_diff_day_offset(day_offset: u8) -> String {
+    match day_offset {
+        0 => String::new(),
+        n => format!(" (+{}d)", n),
+    }
+}
+
+fn format_sailing(sailing: &Sailing) -> String {
+    let times = format!(
+        "{}{}-{}{}",
+        format_diff_time(sailing.depart_time),
+        format_diff_day_offset(sailing.depart_day_offset),
+        format_diff_time(sailing.arrive_time),
+        format_diff_day_offset(sailing.arrive_day_offset)
+    );
+    if sailing.stops.is_empty() {
+        times
+    } else {
+        format!("{} ({})", times, format_stops(&sailing.stops))
+    }
+}
+
+fn format_stops(stops: &[Stop]) -> String {
+    if stops.is_empty() {
+        "non-stop".to_string()
+    } else {
+        stops.iter().map(|s| format!("{:?} {}", s.type_, s.terminal)).join(", ")
+    }
+}
+
+fn format_vessel(vessel: &Option<String>) -> &str {
+    vessel.as_deref().unwrap_or("unknown vessel")
+}
+
+fn format_date_restriction(date_restriction: &Option<DateRestriction>) -> String {
+    match date_restriction {
+        None => "never".to_string(),
+        Some(DateRestriction::All) => "always".to_string(),
+        Some(DateRestriction::Only(dates)) => format!("only {}", dates),
+        Some(DateRestriction::Except(dates)) => format!("except {}", dates),
+    }
+}
+
+fn date_range_overlap_days(a: &DateRange, b: &DateRange) -> i64 {
+    (min(a.to, b.to) - max(a.from, b.from)).whole_days() + 1
+}
+
+impl fmt::Display for ScheduleChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: ", self.terminal_pair, self.date_range)?;
+        match &self.kind {
+            ScheduleChangeKind::ScheduleAdded => write!(f, "schedule added"),
+            ScheduleChangeKind::ScheduleRemoved => write!(f, "schedule removed"),
+            ScheduleChangeKind::DateRangeChanged { old_date_range } => {
+                write!(f, "date range changed from {}", old_date_range)
+            }
+            ScheduleChangeKind::SailingAdded { sailing } => write!(f, "sailing added: {}", format_sailing(sailing)),
+            ScheduleChangeKind::SailingRemoved { sailing } => {
+                write!(f, "sailing removed: {}", format_sailing(sailing))
+            }
+            ScheduleChangeKind::SailingRetimed { old_sailing, new_sailing } => {
+                write!(f, "sailing retimed: {} -> {}", format_sailing(old_sailing), format_sailing(new_sailing))
+            }
+            ScheduleChangeKind::StopsChanged { depart_time, old_stops, new_stops } => write!(
+                f,
+                "sailing {} stops changed: {} -> {}",
+                format_diff_time(*depart_time),
+                format_stops(old_stops),
+                format_stops(new_stops)
+            ),
+            ScheduleChangeKind::VesselChanged { depart_time, old_vessel, new_vessel } => write!(
+                f,
+                "sailing {} vessel changed: {} -> {}",
+                format_diff_time(*depart_time),
+                format_vessel(old_vessel),
+                format_vessel(new_vessel)
+            ),
+            ScheduleChangeKind::WeekdayChanged { depart_time, weekday, old, new } => write!(
+                f,
+                "sailing {} on {}: {} -> {}",
+                format_diff_time(*depart_time),
+                weekday,
+                format_date_restriction(old),
+                format_date_restriction(new)
+            ),
+            ScheduleChangeKind::NoteChanged { depart_time, note, old, new } => write!(
+                f,
+                "sailing {} note {:?}: {} -> {}",
+                format_diff_time(*depart_time),
+                note,
+                format_date_restriction(old),
+                format_date_restriction(new)
+            ),
+        }
+    }
+}
+
+impl<'a> ScheduleDiffer<'a> {
+    fn push(&mut self, kind: ScheduleChangeKind) {
+        self.changes.push(ScheduleChange {
+            terminal_pair: self.new_schedule.terminal_pair,
+            date_range: self.new_schedule.date_range,
+            kind,
+        });
+    }
+
+    fn diff_date_restriction_maps<K, F>(
+        &mut self,
+        old_map: &HashMap<K, DateRestriction>,
+        new_map: &HashMap<K, DateRestriction>,
+        keys: Vec<K>,
+        f: F,
+    ) where
+        K: Eq + Hash,
+        F: Fn(K, Option<DateRestriction>, Option<DateRestriction>) -> ScheduleChangeKind,
+    {
+        for key in keys {
+            let (old, new) = (old_map.get(&key), new_map.get(&key));
+            if old != new {
+                self.push(f(key, old.cloned(), new.cloned()));
+            }
+        }
+    }
+
+    fn diff_items(&mut self, old_item: &ScheduleItem, new_item: &ScheduleItem) {
+        let depart_time = new_item.sailing.depart_time;
+        if old_item.sailing.depart_service_time() != new_item.sailing.depart_service_time()
+            || old_item.sailing.arrive_service_time() != new_item.sailing.arrive_service_time()
+        {
+            self.push(ScheduleChangeKind::SailingRetimed {
+                old_sailing: old_item.sailing.clone(),
+                new_sailing: new_item.sailing.clone(),
+            });
+        } else if old_item.sailing.stops != new_item.sailing.stops {
+            self.push(ScheduleChangeKind::StopsChanged {
+                depart_time,
+                old_stops: old_item.sailing.stops.clone(),
+                new_stops: new_item.sailing.stops.clone(),
+            });
+        }
+        if old_item.sailing.vessel != new_item.sailing.vessel {
+            self.push(ScheduleChangeKind::VesselChanged {
+                depart_time,
+                old_vessel: old_item.sailing.vessel.clone(),
+                new_vessel: new_item.sailing.vessel.clone(),
+            });
+        }
+        self.diff_date_restriction_maps(
+            &old_item.weekdays,
+            &new_item.weekdays,
+            WEEKDAYS.to_vec(),
+            |weekday, old, new| ScheduleChangeKind::WeekdayChanged { depart_time, weekday, old, new },
+        );
+        let notes = old_item.notes.keys().chain(new_item.notes.keys()).cloned().sorted().dedup().collect();
+        self.diff_date_restriction_maps(&old_item.notes, &new_item.notes, notes, |note, old, new| {
+            ScheduleChangeKind::NoteChanged { depart_time, note: note.into_owned(), old, new }
+        });
+    }
+
+    fn diff(mut self) -> Vec<ScheduleChange> {
+        if self.old_schedule.date_range != self.new_schedule.date_range {
+            self.push(ScheduleChangeKind::DateRangeChanged { old_date_range: self.old_schedule.date_range });
+        }
+        let mut old_items: Vec<_> = self.old_schedule.items.iter().collect();
+        let mut pairs = Vec::new();
+        // Match identical sailings first, then sailings with the same departure or arrival time as probable retimings
+        let match_fns: [fn(&Sailing, &Sailing) -> bool; 3] = [
+            |a, b| a == b,
+            |a, b| a.depart_service_time() == b.depart_service_time(),
+            |a, b| a.arrive_service_time() == b.arrive_service_time() && a.stops == b.stops,
+        ];
+        let mut unmatched_new_items: Vec<_> = self.new_schedule.items.iter().collect();
+        for match_fn in match_fns {
+            let mut still_unmatched = Vec::new();
+            for new_item in unmatched_new_items {
+                if let Some(index) = old_items.iter().position(|o| match_fn(&o.sailing, &new_item.sailing)) {
+                    pairs.push((old_items.remove(index), new_item));
+                } else {
+                    still_unmatched.push(new_item);
+                }
+            }
+            unmatched_new_items = still_unmatched;
+        }
+        for old_item in old_items {
+            self.push(ScheduleChangeKind::SailingRemoved { sailing: old_item.sailing.clone() });
+        }
+        for new_item in unmatched_new_items {
+            self.push(ScheduleChangeKind::SailingAdded { sailing: new_item.sailing.clone() });
+        }
+        pairs.sort_by(|(_, a), (_, b)| a.sailing.cmp(&b.sailing));
+        for (old_item, new_item) in pairs {
+            self.diff_items(old_item, new_item);
+        }
+        self.changes
+    }
+}
+
+fn schedule_change(schedule: &Schedule, kind: ScheduleChangeKind) -> ScheduleChange {
+    ScheduleChange { terminal_pair: schedule.terminal_pair, date_range: schedule.date_range, kind }
+}
+
+fn diff_terminal_pair_schedules(
+    mut old_schedules: Vec<&Schedule>,
+    new_schedules: Vec<&Schedule>,
+) -> Vec<ScheduleChange> {
+    let mut changes = Vec::new();
+    let mut unmatched_new_schedules = Vec::new();
+    for new_schedule in new_schedules {
+        let exact_index = old_schedules.iter().position(|o| o.date_range == new_schedule.date_range);
+        let overlap_index = || {
+            old_schedules
+                .iter()
+                .enumerate()
+                .map(|(index, o)| (index, date_range_overlap_days(&o.date_range, &new_schedule.date_range)))
+                .filter(|(_, days)| *days > 0)
+                .max_by_key(|(_, days)| *days)
+                .map(|(index, _)| index)
+        };
+        if let Some(index) = exact_index.or_else(overlap_index) {
+            let old_schedule = old_schedules.remove(index);
+            changes.extend(ScheduleDiffer { old_schedule, new_schedule, changes: Vec::new() }.diff());
+        } else {
+            unmatched_new_schedules.push(new_schedule);
+        }
+    }
+    changes.extend(old_schedules.into_iter().map(|s| schedule_change(s, ScheduleChangeKind::ScheduleRemoved)));
+    changes.extend(unmatched_new_schedules.into_iter().map(|s| schedule_change(s, ScheduleChangeKind::ScheduleAdded)));
+    changes
+}
+
+fn terminal_pair_schedules(schedules: &[Schedule], terminal_pair: TerminalPair) -> Vec<&Schedule> {
+    schedules
+        .iter()
+        .filter(|s| s.terminal_pair == terminal_pair)
+        .sorted_by_key(|s| (s.date_range.from, s.date_range.to))
+        .collect()
+}
+
+pub fn diff_schedules(old_schedules: &[Schedule], new_schedules: &[Schedule]) -> Vec<ScheduleChange> {
+    let terminal_pairs =
+        old_schedules.iter().chain(new_schedules.iter()).map(|s| s.terminal_pair).sorted().dedup().collect::<Vec<_>>();
+    let mut changes = Vec::new();
+    for terminal_pair in terminal_pairs {
+        let mut terminal_pair_changes = diff_terminal_pair_schedules(
+            terminal_pair_schedules(old_schedules, terminal_pair),
+            terminal_pair_schedules(new_schedules, terminal_pair),
+        );
+        terminal_pair_changes.sort_by_key(|c| (c.date_range.from, c.date_range.to));
+        changes.extend(terminal_pair_changes);
+    }
+    changes
+}
+
+fn read_schedules_file(path: &Path) -> Result<Vec<Schedule>> {
+    let inner = || {
+        let json = fs::read_to_string(path).context("Failed to read file")?;
+        Ok(SchedulesData::from_json_str(&json)?.schedules) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to read schedules file: {:?}", path))
+}
+
+pub fn run_diff(options: &DiffOptions) -> Result<()> {
+    let inner = || {
+        let old_schedules = read_schedules_file(&options.old_file)?;
+        let new_schedules = read_schedules_file(&options.new_file)?;
+        let changes = diff_schedules(&old_schedules, &new_schedules);
+        let mut stdout = io::stdout().lock();
+        if options.json {
+            serde_json::to_writer_pretty(&mut stdout, &changes).context("Failed to write changes JSON")?;
+            writeln!(stdout).context("Failed to write changes JSON")?;
+        } else if changes.is_empty() {
+            info!("No changes");
+        } else {
+            for change in &changes {
+                writeln!(stdout, "{}", change).context("Failed to write changes")?;
+            }
+        }
+        Ok(()) as Result<_>
+    };
+    inner().context("Failed to diff schedules")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrysched_shared::test_support::*;
+
+    #[test]
+    fn test_diff_schedules() -> Result<()> {
+        let tsa_swb = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") };
+        let old = vec![ScheduleBuilder::new(tsa_swb)
+            .items([
+                ScheduleItemBuilder::new(time!(07:00), time!(08:35)).weekdays(&[Weekday::Monday]).build(),
+                ScheduleItemBuilder::new(time!(09:00), time!(10:35)).weekdays(&[Weekday::Monday]).build(),
+                ScheduleItemBuilder::new(time!(11:00), time!(12:35)).weekdays(&[Weekday::Monday]).build(),
+            ])
+            .build()];
+        let new = vec![ScheduleBuilder::new(tsa_swb)
+            .items([
+                ScheduleItemBuilder::new(time!(07:00), time!(08:35))
+                    .weekdays(&[Weekday::Monday, Weekday::Tuesday])
+                    .build(),
+                ScheduleItemBuilder::new(time!(09:00), time!(10:45)).weekdays(&[Weekday::Monday]).build(),
+            ])
+            .build()];
+        let kinds: Vec<_> = diff_schedules(&old, &new).into_iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ScheduleChangeKind::SailingRemoved { sailing: old[0].items[2].sailing.clone() },
+                ScheduleChangeKind::WeekdayChanged {
+                    depart_time: time!(07:00),
+                    weekday: Weekday::Tuesday,
+                    old: None,
+                    new: Some(DateRestriction::All)
+                },
+                ScheduleChangeKind::SailingRetimed {
+                    old_sailing: old[0].items[1].sailing.clone(),
+                    new_sailing: new[0].items[1].sailing.clone()
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_schedules_date_ranges() -> Result<()> {
+        let schedule = |date_range| {
+            ScheduleBuilder::new(TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") })
+                .date_range(date_range)
+                .build()
+        };
+        let old = vec![schedule(DateRange { from: date!(2023 - 06 - 01), to: date!(2023 - 09 - 04) })];
+        let new = vec![
+            schedule(DateRange { from: date!(2023 - 06 - 01), to: date!(2023 - 09 - 10) }),
+            schedule(DateRange { from: date!(2023 - 09 - 11), to: date!(2023 - 10 - 10) }),
+        ];
+        let kinds: Vec<_> = diff_schedules(&old, &new).into_iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ScheduleChangeKind::DateRangeChanged { old_date_range: old[0].date_range },
+                ScheduleChangeKind::ScheduleAdded,
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::cache::*;
+use crate::imports::*;
+use crate::output::*;
+use crate::stop_times::*;
+use crate::types::*;
+use crate::utils::*;
+use ferrysched_shared::validation::*;
+
+const STALE_ALERT_PREFIX: &str = "This schedule could not be updated from BC Ferries' website";
+
+/// Some routes failed to scrape, so the output has their last known good schedules instead (or nothing for them, if
+/// there were none)
+#[derive(Debug)]
+pub struct DegradedRoutesError {
+    pub terminal_pairs: Vec<TerminalPair>,
+}
+
+impl fmt::Display for DegradedRoutesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Failed to scrape {} route(s), so used their last known good schedules instead: {}",
+            self.terminal_pairs.len(),
+            self.terminal_pairs.iter().join(", ")
+        )
+    }
+}
+
+impl std::error::Error for DegradedRoutesError {}
+
+// Replaces the alert from any earlier failure, so that it says how old the schedule really is
+fn mark_stale(mut schedules: Vec<Schedule>) -> Vec<Schedule> {
+    for schedule in &mut schedules {
+        let refreshed_date = vancouver_date(schedule.refreshed_at)
+            .format(format_description!("[month repr:long] [day padding:none], [year]"))
+            .expect("Expect refreshed date to format");
+        schedule.alerts.retain(|alert| !alert.message.starts_with(STALE_ALERT_PREFIX));
+        schedule.alerts.push(Alert {
+            message: format!("{} since {}, so it may be out of date.", STALE_ALERT_PREFIX, refreshed_date),
+            level: AlertLevel::Warning,
+            date_range: None,
+        });
+    }
+    schedules
+}
+
+// A route whose schedules have validation errors fails the same way as one whose pages couldn't be scraped
+fn validate_route_schedules(terminal_pair: TerminalPair, schedules: Vec<Schedule>) -> Result<Vec<Schedule>> {
+    report_findings(&validate_schedules(&schedules))
+        .with_context(|| format!("Failed to validate schedules for {}", terminal_pair))?;
+    Ok(schedules)
+}
+
+// Schedules from the published output, or else those kept in the cache, that the scrape would have included
+fn last_good_schedules(
+    options: &Options,
+    cache: &Cache,
+    terminal_pair: TerminalPair,
+    published_schedules: &[Schedule],
+) -> Option<Vec<Schedule>> {
+    let today = today_vancouver();
+    let current = |schedules: Vec<Schedule>| {
+        let schedules: Vec<_> = schedules
+            .into_iter()
+            .filter(|s| s.terminal_pair == terminal_pair)
+            .filter(|s| should_scrape_schedule_date(s.date_range, today, options.date))
+            .collect();
+        (!schedules.is_empty()).then(|| schedules)
+    };
+    current(published_schedules.to_vec()).or_else(|| {
+        let cached_schedules = cache.read_last_good_schedules(terminal_pair).unwrap_or_else(|err| {
+            warn!("{:?}", err);
+            None
+        });
+        cached_schedules.and_then(current)
+    })
+}
+
+/// Validate each route's scraped schedules, and use the last known good schedules for routes that failed. Returns the
+/// schedules, and the routes that failed.
+pub async fn resolve_route_schedules(
+    options: &Options,
+    cache: &Cache,
+    route_results: Vec<(TerminalPair, Result<Vec<Schedule>>)>,
+) -> Result<(Vec<Schedule>, Vec<TerminalPair>)> {
+    let mut schedules = Vec::new();
+    let mut failed_terminal_pairs = Vec::new();
+    // Only read when a route fails, since that may mean downloading it
+    let mut published_schedules: Option<Vec<Schedule>> = None;
+    for (terminal_pair, result) in route_results {
+        match result.and_then(|s| validate_route_schedules(terminal_pair, s)) {
+            Ok(route_schedules) => {
+                if !route_schedules.is_empty() {
+                    if let Err(err) = cache.write_last_good_schedules(terminal_pair, &route_schedules) {
+                        warn!("{:?}", err);
+                    }
+                }
+                schedules.extend(route_schedules);
+            }
+            Err(err) => {
+                error!("{:?}", err);
+                failed_terminal_pairs.push(terminal_pair);
+                if published_schedules.is_none() {
+                    let read_schedules = read_published_schedules(options).await.unwrap_or_else(|err| {
+                        warn!("{:?}", err);
+                        None
+                    });
+                    published_schedules = Some(read_schedules.unwrap_or_default());
+                }
+                let published_schedules = published_schedules.as_deref().unwrap_or_default();
+                match last_good_schedules(options, cache, terminal_pair, published_schedules) {
+                    Some(route_schedules) => {
+                        warn!("Using last known good schedules for {}", terminal_pair);
+                        schedules.extend(mark_stale(route_schedules));
+                    }
+                    None => error!("No last known good schedules for {}, so leaving it out", terminal_pair),
+                }
+            }
+        }
+    }
+    ensure!(!schedules.is_empty() || failed_terminal_pairs.is_empty(), "Failed to scrape any schedules");
+    fill_stop_times(&mut schedules);
+    Ok((schedules, failed_terminal_pairs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_stale() -> Result<()> {
+        let schedule = Schedule {
+            terminal_pair: TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") },
+            date_range: DateRange { from: date!(2024 - 06 - 27), to: date!(2024 - 09 - 02) },
+            items: vec![],
+            source_url: String::new(),
+            refreshed_at: datetime!(2024 - 07 - 02 01:00 UTC),
+            alerts: vec![],
+            notes_to_review: vec![],
+        };
+        let schedules = mark_stale(mark_stale(vec![schedule]));
+        assert_eq!(
+            schedules[0].alerts.iter().map(|a| a.message.as_str()).collect::<Vec<_>>(),
+            vec!["This schedule could not be updated from BC Ferries' website since July 1, 2024, so it may be out of date."]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use crate::cache::*;
+use crate::constants::*;
+use crate::imports::*;
+use crate::macros::*;
+use crate::types::*;
+use crate::utils::*;
+
+fn parse_fare_amount(text: &str) -> Result<Cents> {
+    let inner = || {
+        if text.eq_ignore_ascii_case("free") {
+            return Ok(0);
+        }
+        let captures = regex!(r"^\$(\d+)\.(\d{2})$").captures(text).ok_or_else(|| anyhow!("Invalid fare amount"))?;
+        Ok(captures[1].parse::<Cents>()? * 100 + captures[2].parse::<Cents>()?) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to parse fare amount: {:?}", text))
+}
+
+fn parse_fare_table(table_elem: ElementRef) -> Result<FareTable> {
+    let inner = || {
+        let mut passengers = HashMap::new();
+        let mut standard_vehicle = None;
+        let mut per_extra_foot = None;
+        for row_elem in table_elem.select(selector!("tbody tr")) {
+            let cell_texts: Vec<_> = row_elem.select(selector!("td")).map(|e| element_text(&e)).collect();
+            ensure!(cell_texts.len() == 2, "Expect fare row to have two cells: {:?}", cell_texts);
+            let (label, amount) = (cell_texts[0].as_str(), parse_fare_amount(&cell_texts[1])?);
+            if let Some(captures) = regex!(r"(?i)^(Adult|Senior|Child|Infant)\b").captures(label) {
+                let passenger_type = match captures[1].to_lowercase().as_str() {
+                    "adult" => PassengerType::Adult,
+                    "senior" => PassengerType::Senior,
+                    "child" => PassengerType::Child,
+                    _ => PassengerType::Infant,
+                };
+                passengers.insert(passenger_type, amount);
+            } else if let Some(captures) = regex!(r"(?i)^standard vehicle \(up to (\d+) ft\)").captures(label) {
+                standard_vehicle = Some((amount, captures[1].parse()?));
+            } else if regex!(r"(?i)^each additional foot").is_match(label) {
+                per_extra_foot = Some(amount);
+            } else {
+                // Surcharges and other vehicle types aren't part of the fare model
+                debug!("Ignoring fare row: {:?}", label);
+            }
+        }
+        ensure!(passengers.contains_key(&PassengerType::Adult), "Missing adult fare");
+        let vehicle = match (standard_vehicle, per_extra_foot) {
+            (Some((standard, standard_max_length_ft)), Some(per_extra_foot)) => {
+                Some(VehicleFare { standard, standard_max_length_ft, per_extra_foot })
+            }
+            (None, None) => None,
+            _ => bail!("Expect both standard vehicle and additional foot fares, or neither"),
+        };
+        Ok(FareTable { passengers, vehicle }) as Result<_>
+    };
+    inner().context("Failed to parse fare table")
+}
+
+fn parse_weekday_name(text: &str) -> Result<Weekday> {
+    WEEKDAYS
+        .iter()
+        .copied()
+        .find(|w| w.to_string().eq_ignore_ascii_case(text))
+        .ok_or_else(|| anyhow!("Unrecognized weekday name: {:?}", text))
+}
+
+// Reads a note such as "Senior fares apply Monday through Thursday"; without a note, the senior fare applies every day
+fn parse_senior_weekdays(document: &Html) -> Result<Vec<Weekday>> {
+    let note_text = match document.select(selector!("p.senior-fare-note")).next() {
+        Some(note_elem) => element_text(&note_elem),
+        None => return Ok(WEEKDAYS.to_vec()),
+    };
+    let inner = || {
+        let captures = regex!(r"(?i)(\w+day) (?:to|through) (\w+day)")
+            .captures(&note_text)
+            .ok_or_else(|| anyhow!("Expect weekday range in note"))?;
+        let (first, last) = (parse_weekday_name(&captures[1])?, parse_weekday_name(&captures[2])?);
+        let mut weekdays = vec![first];
+        while *weekdays.last().expect("Expect at least one weekday") != last {
+            weekdays.push(weekdays.last().expect("Expect at least one weekday").next());
+        }
+        Ok(weekdays) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to parse senior fare note: {:?}", note_text))
+}
+
+fn parse_fares_document(document: &Html, terminal_pair: TerminalPair, source_url: &str) -> Result<Fare> {
+    let mut regular = None;
+    let mut thrufare = None;
+    for table_elem in document.select(selector!("table.fares-table")) {
+        match table_elem.value().attr("data-fare-type") {
+            Some("regular") => regular = Some(parse_fare_table(table_elem)?),
+            Some("thru-fare") => thrufare = Some(parse_fare_table(table_elem)?),
+            fare_type => bail!("Unexpected fare table type: {:?}", fare_type),
+        }
+    }
+    // A page without fares most likely means the page has changed, rather than that the route has no fares
+    let regular = regular.context("Missing regular fares table")?;
+    let date_range_text = element_text(
+        &document.select(selector!("div.fares-date-range")).next().context("Missing fares date range element")?,
+    );
+    let date_range = DateRange::parse(
+        &date_range_text,
+        format_description!("[month repr:short case_sensitive:false] [day padding:none], [year]"),
+        " - ",
+    )
+    .with_context(|| format!("Failed to parse fares date range: {:?}", date_range_text))?;
+    Ok(Fare {
+        terminal_pair,
+        date_range,
+        regular,
+        thrufare,
+        senior_weekdays: parse_senior_weekdays(document)?,
+        source_url: source_url.to_string(),
+    })
+}
+
+async fn scrape_route_fares(cache: &Cache, terminal_pair: TerminalPair) -> Result<Fare> {
+    let source_url = format!("{}/{}/arrival-terminal/{}", FARES_BASE_URL, terminal_pair.from, terminal_pair.to);
+    let inner = async {
+        let document = cache.get_html(&source_url, &HTML_ERROR_REGEX).await?;
+        info!("Parsing fares for {}", terminal_pair);
+        parse_fares_document(&document, terminal_pair, &source_url)
+    };
+    inner.await.with_context(|| format!("Failed to scrape fares for {} from: {:?}", terminal_pair, source_url))
+}
+
+/// Scrape every route's fares. Fares are only used for estimates, so a route that fails uses its last known good
+/// fares (or is left out) rather than failing the run.
+pub async fn scrape_fares(options: &Options, cache: &Cache) -> Vec<Fare> {
+    let mut fares = Vec::new();
+    let mut scraped_terminal_pairs = HashSet::new();
+    // Only read when a route fails
+    let mut last_good_fares: Option<Vec<Fare>> = None;
+    for &terminal_pair in ALL_TERMINAL_PAIRS.iter().sorted() {
+        if options.terminals.is_some() && options.terminals != Some(terminal_pair) {
+            continue;
+        }
+        match scrape_route_fares(cache, terminal_pair).await {
+            Ok(fare) => {
+                scraped_terminal_pairs.insert(terminal_pair);
+                fares.push(fare);
+            }
+            Err(err) => {
+                error!("{:?}", err);
+                let last_good_fares = last_good_fares.get_or_insert_with(|| {
+                    cache
+                        .read_last_good_fares()
+                        .unwrap_or_else(|err| {
+                            warn!("{:?}", err);
+                            None
+                        })
+                        .unwrap_or_default()
+                });
+                match last_good_fares.iter().find(|f| f.terminal_pair == terminal_pair) {
+                    Some(fare) => {
+                        warn!("Using last known good fares for {}", terminal_pair);
+                        fares.push(fare.clone());
+                    }
+                    None => error!("No last known good fares for {}, so leaving them out", terminal_pair),
+                }
+            }
+        }
+    }
+    if !scraped_terminal_pairs.is_empty() {
+        if let Err(err) = cache.update_last_good_fares(&fares, &scraped_terminal_pairs) {
+            warn!("{:?}", err);
+        }
+    }
+    fares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fares_fixture() -> Result<()> {
+        let path = format!("{}/fixtures/fares/TSA-PSB.html", env!("CARGO_MANIFEST_DIR"));
+        let document = Html::parse_document(&fs::read_to_string(&path)?);
+        let terminal_pair = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("PSB") };
+        let fare = parse_fares_document(&document, terminal_pair, "")?;
+        assert_eq!(fare.date_range, DateRange { from: date!(2026 - 04 - 01), to: date!(2027 - 03 - 31) });
+        assert_eq!(fare.regular.passengers.get(&PassengerType::Adult), Some(&2230));
+        assert_eq!(fare.regular.passengers.get(&PassengerType::Infant), Some(&0));
+        assert_eq!(
+            fare.regular.vehicle,
+            Some(VehicleFare { standard: 8470, standard_max_length_ft: 20, per_extra_foot: 835 })
+        );
+        assert_eq!(fare.thrufare.and_then(|t| t.vehicle).map(|v| v.standard), Some(9585));
+        assert_eq!(
+            fare.senior_weekdays,
+            vec![Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_fares_table() -> Result<()> {
+        let document = Html::parse_document("<html><body><p>Fares are changing</p></body></html>");
+        let terminal_pair = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("PSB") };
+        assert!(parse_fares_document(&document, terminal_pair, "").is_err());
+        Ok(())
+    }
+}
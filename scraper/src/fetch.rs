@@ -0,0 +1,242 @@
+use futures_util::future::BoxFuture;
+use reqwest::header;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+use crate::imports::*;
+use crate::macros::*;
+
+/// Values from an earlier response that let the server reply that a page hasn't changed since
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct FetchedPage {
+    pub status: u16,
+    pub validators: Validators,
+    pub contents: String,
+}
+
+#[derive(Debug)]
+pub enum Fetched {
+    Page(FetchedPage),
+    /// The page is the same as when the validators were returned
+    NotModified,
+}
+
+impl Fetched {
+    fn page(contents: String) -> Fetched {
+        Fetched::Page(FetchedPage { status: 200, validators: Validators::default(), contents })
+    }
+}
+
+/// Gets the contents of pages from somewhere: the BC Ferries site, or pages saved from it
+pub trait Fetcher: Debug + Send + Sync {
+    /// Fetch the page, unless the validators (which may be empty) show that it hasn't changed
+    fn fetch<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>>;
+
+    /// Whether a failed fetch might succeed if tried again
+    fn is_retryable(&self) -> bool;
+}
+
+/// Name of the file that a page is saved to, in the cache directory or a recording. The hash has to be the same in
+/// every build, so that recordings can be replayed by other builds.
+pub fn saved_page_filename(url: &str) -> String {
+    format!("{}_{}", regex!(r"[^\w\d-]+").replace_all(url, "_"), content_hash(url.as_bytes()))
+}
+
+pub fn read_saved_page(dir: &Path, url: &str) -> Result<String> {
+    let path = dir.join(saved_page_filename(url));
+    fs::read_to_string(&path).with_context(|| format!("Failed to read saved page for {:?} from: {:?}", url, path))
+}
+
+fn write_saved_page(dir: &Path, url: &str, contents: &str) -> Result<()> {
+    let path = dir.join(saved_page_filename(url));
+    let inner = || {
+        fs::create_dir_all(dir)?;
+        fs::write(&path, contents)?;
+        Ok(()) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to save page for {:?} to: {:?}", url, path))
+}
+
+// Spaces out the start of requests to each host, so that concurrent scraping doesn't overload it
+#[derive(Debug)]
+struct HostRateLimiter {
+    min_interval: std::time::Duration,
+    next_request_times: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostRateLimiter {
+    async fn wait(&self, url: &str) {
+        let host = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(String::from)).unwrap_or_default();
+        let request_time = {
+            let mut next_request_times = self.next_request_times.lock().expect("Expect rate limiter lock to succeed");
+            let now = Instant::now();
+            let request_time = next_request_times.get(&host).map(|t| max(*t, now)).unwrap_or(now);
+            next_request_times.insert(host, request_time + self.min_interval);
+            request_time
+        };
+        tokio::time::sleep_until(request_time).await;
+    }
+}
+
+/// Fetches pages from the live site
+#[derive(Debug)]
+pub struct HttpFetcher {
+    reqwest_client: reqwest::Client,
+    fetch_permits: Semaphore,
+    rate_limiter: HostRateLimiter,
+}
+
+impl HttpFetcher {
+    pub fn new(parallelism: NonZeroUsize, min_request_interval: std::time::Duration) -> HttpFetcher {
+        let reqwest_client =
+            reqwest::ClientBuilder::new().cookie_store(true).build().expect("Build build reqwest client to succeed");
+        HttpFetcher {
+            reqwest_client,
+            fetch_permits: Semaphore::new(parallelism.get()),
+            rate_limiter: HostRateLimiter {
+                min_interval: min_request_interval,
+                next_request_times: Mutex::new(HashMap::new()),
+            },
+        }
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>> {
+        Box::pin(async move {
+            let _permit = self.fetch_permits.acquire().await?;
+            self.rate_limiter.wait(url).await;
+            info!("Fetching: {:?}", url);
+            let mut request = self.reqwest_client.get(url);
+            if let Some(etag) = &validators.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+            let response = request.send().await?;
+            if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                return Ok(Fetched::NotModified);
+            }
+            let response = response.error_for_status()?;
+            let header_value = |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+            let validators =
+                Validators { etag: header_value(header::ETAG), last_modified: header_value(header::LAST_MODIFIED) };
+            let status = response.status().as_u16();
+            Ok(Fetched::Page(FetchedPage { status, validators, contents: response.text().await? }))
+        })
+    }
+
+    fn is_retryable(&self) -> bool {
+        true
+    }
+}
+
+/// Reads pages saved by a previous run (in its cache directory or a recording) instead of using the network
+#[derive(Debug)]
+pub struct ReplayFetcher {
+    dir: PathBuf,
+}
+
+impl ReplayFetcher {
+    pub fn new(dir: PathBuf) -> ReplayFetcher {
+        ReplayFetcher { dir }
+    }
+}
+
+impl Fetcher for ReplayFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, _validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>> {
+        Box::pin(async move {
+            info!("Replaying: {:?}", url);
+            Ok(Fetched::page(read_saved_page(&self.dir, url)?))
+        })
+    }
+
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
+/// Saves every page that another fetcher gets, so that the run can be replayed later
+#[derive(Debug)]
+pub struct RecordingFetcher {
+    inner: Box<dyn Fetcher>,
+    dir: PathBuf,
+}
+
+impl RecordingFetcher {
+    pub fn new(inner: Box<dyn Fetcher>, dir: PathBuf) -> RecordingFetcher {
+        RecordingFetcher { inner, dir }
+    }
+}
+
+impl Fetcher for RecordingFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>> {
+        Box::pin(async move {
+            let fetched = self.inner.fetch(url, validators).await?;
+            if let Fetched::Page(page) = &fetched {
+                write_saved_page(&self.dir, url, &page.contents)?;
+            }
+            Ok(fetched)
+        })
+    }
+
+    fn is_retryable(&self) -> bool {
+        self.inner.is_retryable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_replay() -> Result<()> {
+        #[derive(Debug)]
+        struct StaticFetcher;
+
+        impl Fetcher for StaticFetcher {
+            fn fetch<'a>(&'a self, url: &'a str, _validators: &'a Validators) -> BoxFuture<'a, Result<Fetched>> {
+                Box::pin(async move { Ok(Fetched::page(format!("<html>{}</html>", url))) })
+            }
+
+            fn is_retryable(&self) -> bool {
+                true
+            }
+        }
+
+        let contents = |fetched| match fetched {
+            Fetched::Page(page) => Some(page.contents),
+            Fetched::NotModified => None,
+        };
+        let dir = tempfile::tempdir()?;
+        let validators = Validators::default();
+        let url = "https://www.bcferries.com/routes-fares/schedules/seasonal/HSB-NAN?departureDate=2026-06-25";
+        let recorder = RecordingFetcher::new(Box::new(StaticFetcher), dir.path().to_path_buf());
+        assert_eq!(contents(recorder.fetch(url, &validators).await?), Some(format!("<html>{}</html>", url)));
+        let replayer = ReplayFetcher::new(dir.path().to_path_buf());
+        assert_eq!(contents(replayer.fetch(url, &validators).await?), Some(format!("<html>{}</html>", url)));
+        assert!(replayer.fetch("https://www.bcferries.com/", &validators).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_saved_page_filename_is_stable() -> Result<()> {
+        assert_eq!(
+            saved_page_filename("https://www.bcferries.com/routes-fares/schedules"),
+            "https_www_bcferries_com_routes-fares_schedules_e65849c0755535d9"
+        );
+        Ok(())
+    }
+}
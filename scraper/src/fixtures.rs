@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use crate::fetch::*;
+use crate::imports::*;
+use crate::scraper::*;
+use crate::stop_times::*;
+use crate::types::*;
+
+const FIXTURE_FILENAME: &str = "fixture.json";
+const EXPECTED_FILENAME: &str = "expected.json";
+
+/// A route's schedule pages saved from the BC Ferries site, so that we notice if they stop parsing the same way
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScheduleFixture {
+    pub terminal_pair: TerminalPair,
+    /// Date the pages were recorded, which decides which of their schedules are still current
+    pub today: Date,
+    /// URL of each page in the order returned by `schedule_page_urls`; the page at index N is saved as 'N.html'
+    pub source_urls: Vec<String>,
+}
+
+fn default_fixtures_dir() -> PathBuf {
+    PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/schedules"))
+}
+
+fn page_filename(index: usize) -> String {
+    format!("{}.html", index)
+}
+
+// Going through a JSON value sorts the object keys, so that re-recording a fixture doesn't reorder hash maps
+fn schedules_json(schedules: &[Schedule]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&serde_json::to_value(schedules)?)? + "\n")
+}
+
+impl ScheduleFixture {
+    fn read(fixture_dir: &Path) -> Result<ScheduleFixture> {
+        let path = fixture_dir.join(FIXTURE_FILENAME);
+        let inner = || Ok(serde_json::from_str::<ScheduleFixture>(&fs::read_to_string(&path)?)?) as Result<_>;
+        inner().with_context(|| format!("Failed to read fixture: {:?}", path))
+    }
+
+    /// Parse the pages the same way as a live scrape would
+    fn parse(&self, fixture_dir: &Path) -> Result<Vec<Schedule>> {
+        let inner = || {
+            let mut pages = Vec::new();
+            for (index, source_url) in self.source_urls.iter().enumerate() {
+                let html = fs::read_to_string(fixture_dir.join(page_filename(index)))?;
+                pages.push((source_url.clone(), Html::parse_document(&html)));
+            }
+            let mut schedules = parse_route_schedules(self.terminal_pair, &pages, self.today, None)?;
+            fill_stop_times(&mut schedules);
+            // The refresh time is the only part of the result that depends on when the fixture is parsed
+            schedules.iter_mut().for_each(|s| s.refreshed_at = OffsetDateTime::UNIX_EPOCH);
+            Ok(schedules) as Result<_>
+        };
+        inner().with_context(|| format!("Failed to parse fixture: {:?}", fixture_dir))
+    }
+
+    fn write_expected(&self, fixture_dir: &Path) -> Result<usize> {
+        let schedules = self.parse(fixture_dir)?;
+        fs::write(fixture_dir.join(EXPECTED_FILENAME), schedules_json(&schedules)?)?;
+        Ok(schedules.len())
+    }
+}
+
+fn record_pages(pages_dir: &Path, terminal_pair: TerminalPair, fixture_dir: &Path) -> Result<ScheduleFixture> {
+    ensure!(!fixture_dir.exists(), "Fixture already exists");
+    let base_url = route_schedules_base_url(terminal_pair);
+    let base_html = read_saved_page(pages_dir, &base_url)?;
+    let source_urls = schedule_page_urls(&base_url, &Html::parse_document(&base_html))?;
+    fs::create_dir_all(fixture_dir)?;
+    for (index, source_url) in source_urls.iter().enumerate() {
+        let html = if index == 0 { base_html.clone() } else { read_saved_page(pages_dir, source_url)? };
+        fs::write(fixture_dir.join(page_filename(index)), html)?;
+    }
+    let fixture = ScheduleFixture { terminal_pair, today: today_vancouver(), source_urls };
+    fs::write(fixture_dir.join(FIXTURE_FILENAME), serde_json::to_string_pretty(&fixture)? + "\n")?;
+    Ok(fixture)
+}
+
+/// Record a fixture from the pages saved in `pages_dir` (the cache directory, or a directory of recorded pages)
+pub fn run_record_fixture(options: &RecordFixtureOptions, pages_dir: &Path) -> Result<()> {
+    let terminal_pair = options.terminal_pair;
+    let name = options.name.clone().unwrap_or_else(|| {
+        format!("{}_{}", terminal_pair.to_schedule_code_pair(), format_iso8601_date(today_vancouver()))
+    });
+    let fixture_dir = options.fixtures_dir.clone().unwrap_or_else(default_fixtures_dir).join(name);
+    let inner = || {
+        let fixture = if options.update {
+            let fixture = ScheduleFixture::read(&fixture_dir)?;
+            ensure!(fixture.terminal_pair == terminal_pair, "Fixture is for {}", fixture.terminal_pair);
+            fixture
+        } else {
+            record_pages(pages_dir, terminal_pair, &fixture_dir)?
+        };
+        // A fixture for a new page layout usually won't parse until the scraper is fixed, so keep the pages regardless
+        let schedule_count = fixture.write_expected(&fixture_dir).context(
+            "Recorded the pages, but could not parse them; after fixing the parser, run again with '--update'",
+        )?;
+        info!("Recorded fixture with {} schedule(s): {:?}", schedule_count, fixture_dir);
+        Ok(()) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to record fixture for {} to: {:?}", terminal_pair, fixture_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::*;
+
+    #[test]
+    fn test_schedule_fixtures() -> Result<()> {
+        let fixture_dirs =
+            fs::read_dir(default_fixtures_dir())?.map(|e| e.map(|e| e.path())).collect::<io::Result<Vec<_>>>()?;
+        assert!(!fixture_dirs.is_empty(), "Expect schedule fixtures");
+        for fixture_dir in fixture_dirs.into_iter().sorted() {
+            let schedules = ScheduleFixture::read(&fixture_dir)?.parse(&fixture_dir)?;
+            let expected: Vec<Schedule> =
+                serde_json::from_str(&fs::read_to_string(fixture_dir.join(EXPECTED_FILENAME))?)?;
+            let changes = diff_schedules(&expected, &schedules);
+            assert!(changes.is_empty(), "Fixture {:?} parses differently:\n{}", fixture_dir, changes.iter().join("\n"));
+            // Also catch differences that the diff doesn't report, such as alerts and source URLs
+            assert_eq!(schedules_json(&schedules)?, schedules_json(&expected)?, "Fixture {:?}", fixture_dir);
+        }
+        Ok(())
+    }
+}
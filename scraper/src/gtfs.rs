@@ -0,0 +1,294 @@
+use crate::constants::*;
+use crate::imports::*;
+use crate::types::*;
+
+const GTFS_AGENCY_ID: &str = "BCF";
+const GTFS_AGENCY_NAME: &str = "BC Ferries";
+const GTFS_TIMEZONE: &str = "America/Vancouver";
+const GTFS_FERRY_ROUTE_TYPE: &str = "4";
+const GTFS_DIRECTORY: &str = "gtfs";
+
+struct CsvTable {
+    header: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+#[derive(Default)]
+struct GtfsFeed {
+    routes: Vec<Vec<String>>,
+    trips: Vec<Vec<String>>,
+    stop_times: Vec<Vec<String>>,
+    calendar: Vec<Vec<String>>,
+    calendar_dates: Vec<Vec<String>>,
+}
+
+fn csv_field(field: &str) -> Cow<str> {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        Cow::from(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::from(field)
+    }
+}
+
+fn format_gtfs_date(date: Date) -> String {
+    date.format(format_description!("[year][month][day]")).expect("Expect GTFS date to format")
+}
+
+fn format_gtfs_time(time: Time, day_offset: u8) -> String {
+    format!("{:02}:{:02}:{:02}", time.hour() as u32 + 24 * day_offset as u32, time.minute(), time.second())
+}
+
+fn format_gtfs_coordinate(coordinate: f64) -> String {
+    format!("{:.4}", coordinate)
+}
+
+fn gtfs_route_id(terminal_pair: TerminalPair) -> String {
+    terminal_pair.to_schedule_code_pair()
+}
+
+fn gtfs_service_id(schedule: &Schedule, item_index: usize) -> String {
+    format!("{}_{}_{}", gtfs_route_id(schedule.terminal_pair), format_gtfs_date(schedule.date_range.from), item_index)
+}
+
+impl CsvTable {
+    fn into_output_file(self, filename: &str) -> OutputFile {
+        let mut contents = self.header.join(",");
+        contents.push_str("\r\n");
+        for row in self.rows {
+            contents.push_str(&row.iter().map(|f| csv_field(f)).join(","));
+            contents.push_str("\r\n");
+        }
+        OutputFile {
+            path: format!("{}/{}", GTFS_DIRECTORY, filename),
+            content_type: "text/csv",
+            contents: contents.into_bytes(),
+        }
+    }
+}
+
+impl GtfsFeed {
+    fn add_calendar(&mut self, service_id: &str, schedule: &Schedule, item: &ScheduleItem) {
+        let mut calendar_row = vec![service_id.to_string()];
+        let mut added_dates: Vec<Date> = Vec::new();
+        let mut removed_dates: Vec<Date> = Vec::new();
+        for weekday in WEEKDAYS {
+            let runs_all_weekdays = match item.weekdays.get(&weekday) {
+                None => false,
+                Some(DateRestriction::All) => true,
+                Some(DateRestriction::Except(dates)) => {
+                    removed_dates.extend(dates.iter().filter(|d| schedule.date_range.includes_date_inclusive(*d)));
+                    true
+                }
+                Some(DateRestriction::Only(dates)) => {
+                    added_dates.extend(dates.iter().filter(|d| schedule.date_range.includes_date_inclusive(*d)));
+                    false
+                }
+            };
+            calendar_row.push(if runs_all_weekdays { "1" } else { "0" }.to_string());
+        }
+        calendar_row.push(format_gtfs_date(schedule.date_range.from));
+        calendar_row.push(format_gtfs_date(schedule.date_range.to));
+        self.calendar.push(calendar_row);
+        added_dates.sort_unstable();
+        removed_dates.sort_unstable();
+        for (dates, exception_type) in [(added_dates, "1"), (removed_dates, "2")] {
+            for date in dates {
+                self.calendar_dates.push(vec![
+                    service_id.to_string(),
+                    format_gtfs_date(date),
+                    exception_type.to_string(),
+                ]);
+            }
+        }
+    }
+
+    fn add_trip(&mut self, service_id: &str, schedule: &Schedule, item: &ScheduleItem) {
+        let sailing = &item.sailing;
+        let trip_id = format!(
+            "{}_{}",
+            service_id,
+            sailing.depart_time.format(format_description!("[hour][minute]")).expect("Expect trip time to format")
+        );
+        self.trips.push(vec![gtfs_route_id(schedule.terminal_pair), service_id.to_string(), trip_id.clone()]);
+        let depart_time = format_gtfs_time(sailing.depart_time, sailing.depart_day_offset);
+        let arrive_time = format_gtfs_time(sailing.arrive_time, sailing.arrive_day_offset);
+        let mut stop_times = vec![(schedule.terminal_pair.from, depart_time.clone(), depart_time)];
+        // Leave intermediate stop times blank where they aren't known, for consumers to interpolate
+        let format_stop_time = |time: Option<Time>| {
+            time.map(|t| format_gtfs_time(t, sailing.depart_day_offset + u8::from(t < sailing.depart_time)))
+                .unwrap_or_default()
+        };
+        stop_times.extend(
+            sailing
+                .stops
+                .iter()
+                .map(|s| (s.terminal, format_stop_time(s.arrive_time), format_stop_time(s.depart_time))),
+        );
+        stop_times.push((schedule.terminal_pair.to, arrive_time.clone(), arrive_time));
+        for (index, (terminal, arrival, departure)) in stop_times.into_iter().enumerate() {
+            let timepoint = if arrival.is_empty() { "0" } else { "1" };
+            self.stop_times.push(vec![
+                trip_id.clone(),
+                arrival,
+                departure,
+                terminal.to_string(),
+                (index + 1).to_string(),
+                timepoint.to_string(),
+            ]);
+        }
+    }
+
+    fn add_schedule(&mut self, schedule: &Schedule) {
+        for (item_index, item) in schedule.items.iter().enumerate() {
+            let service_id = gtfs_service_id(schedule, item_index);
+            self.add_calendar(&service_id, schedule, item);
+            self.add_trip(&service_id, schedule, item);
+        }
+    }
+
+    fn into_output_files(self, terminals: Vec<Terminal>) -> Vec<OutputFile> {
+        let agency = CsvTable {
+            header: &["agency_id", "agency_name", "agency_url", "agency_timezone", "agency_lang"],
+            rows: vec![vec![
+                GTFS_AGENCY_ID.to_string(),
+                GTFS_AGENCY_NAME.to_string(),
+                BCFERRIES_HOME_URL.to_string(),
+                GTFS_TIMEZONE.to_string(),
+                "en".to_string(),
+            ]],
+        };
+        let stops = CsvTable {
+            header: &["stop_id", "stop_name", "stop_lat", "stop_lon"],
+            rows: terminals
+                .into_iter()
+                .map(|t| {
+                    let (lat, lon) = t.coordinates();
+                    vec![t.to_string(), t.name().to_string(), format_gtfs_coordinate(lat), format_gtfs_coordinate(lon)]
+                })
+                .collect(),
+        };
+        let routes = CsvTable {
+            header: &["route_id", "agency_id", "route_short_name", "route_long_name", "route_type"],
+            rows: self.routes,
+        };
+        let trips = CsvTable { header: &["route_id", "service_id", "trip_id"], rows: self.trips };
+        let stop_times = CsvTable {
+            header: &["trip_id", "arrival_time", "departure_time", "stop_id", "stop_sequence", "timepoint"],
+            rows: self.stop_times,
+        };
+        let calendar = CsvTable {
+            header: &[
+                "service_id",
+                "monday",
+                "tuesday",
+                "wednesday",
+                "thursday",
+                "friday",
+                "saturday",
+                "sunday",
+                "start_date",
+                "end_date",
+            ],
+            rows: self.calendar,
+        };
+        let calendar_dates = CsvTable { header: &["service_id", "date", "exception_type"], rows: self.calendar_dates };
+        vec![
+            agency.into_output_file("agency.txt"),
+            stops.into_output_file("stops.txt"),
+            routes.into_output_file("routes.txt"),
+            trips.into_output_file("trips.txt"),
+            stop_times.into_output_file("stop_times.txt"),
+            calendar.into_output_file("calendar.txt"),
+            calendar_dates.into_output_file("calendar_dates.txt"),
+        ]
+    }
+}
+
+pub fn gtfs_output_files(schedules: &[Schedule]) -> Vec<OutputFile> {
+    let mut schedules: Vec<_> = schedules.iter().collect();
+    schedules.sort_unstable_by_key(|s| (s.terminal_pair, s.date_range.from));
+    let mut feed = GtfsFeed::default();
+    let terminal_pairs: Vec<_> = schedules.iter().map(|s| s.terminal_pair).sorted().dedup().collect();
+    for terminal_pair in &terminal_pairs {
+        feed.routes.push(vec![
+            gtfs_route_id(*terminal_pair),
+            GTFS_AGENCY_ID.to_string(),
+            String::new(),
+            format!("{} - {}", terminal_pair.from.name(), terminal_pair.to.name()),
+            GTFS_FERRY_ROUTE_TYPE.to_string(),
+        ]);
+    }
+    for schedule in schedules.iter() {
+        feed.add_schedule(schedule);
+    }
+    let terminals = schedules
+        .iter()
+        .flat_map(|s| {
+            iter::once(s.terminal_pair.from)
+                .chain(iter::once(s.terminal_pair.to))
+                .chain(s.items.iter().flat_map(|i| i.sailing.stops.iter().map(|s| s.terminal)))
+        })
+        .sorted()
+        .dedup()
+        .collect();
+    feed.into_output_files(terminals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrysched_shared::date_set::DateSet;
+    use ferrysched_shared::test_support::*;
+
+    fn output_file_text<'a>(output_files: &'a [OutputFile], filename: &str) -> Result<&'a str> {
+        let path = format!("{}/{}", GTFS_DIRECTORY, filename);
+        let output_file = output_files.iter().find(|f| f.path == path).with_context(|| format!("Missing {}", path))?;
+        Ok(std::str::from_utf8(&output_file.contents)?)
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Swartz Bay"), "Swartz Bay");
+        assert_eq!(csv_field("Foot passengers, only"), "\"Foot passengers, only\"");
+        assert_eq!(csv_field("The \"Queen\""), "\"The \"\"Queen\"\"\"");
+    }
+
+    #[test]
+    fn test_format_gtfs_time() {
+        assert_eq!(format_gtfs_time(time!(7:05), 0), "07:05:00");
+        assert_eq!(format_gtfs_time(time!(0:40), 1), "24:40:00");
+    }
+
+    #[test]
+    fn test_calendar_dates() -> Result<()> {
+        let terminal_pair = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") };
+        // Dates outside the schedule are left out
+        let except_dates = DateSet::from_iter([date!(2023 - 06 - 12), date!(2023 - 06 - 26), date!(2023 - 07 - 03)]);
+        let only_dates = DateSet::from_iter([date!(2023 - 06 - 17), date!(2023 - 07 - 01)]);
+        let schedule = ScheduleBuilder::new(terminal_pair)
+            .item(
+                ScheduleItemBuilder::new(time!(07:00), time!(08:35))
+                    .weekdays(&[Weekday::Monday, Weekday::Friday])
+                    .weekday(Weekday::Monday, DateRestriction::Except(except_dates))
+                    .weekday(Weekday::Saturday, DateRestriction::Only(only_dates))
+                    .build(),
+            )
+            .item(ScheduleItemBuilder::new(time!(09:00), time!(10:35)).weekdays(&[Weekday::Sunday]).build())
+            .build();
+        let output_files = gtfs_output_files(&[schedule]);
+        assert_eq!(
+            output_file_text(&output_files, "calendar.txt")?,
+            "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\r\n\
+             TSA-SWB_20230601_0,1,0,0,0,1,0,0,20230601,20230630\r\n\
+             TSA-SWB_20230601_1,0,0,0,0,0,0,1,20230601,20230630\r\n"
+        );
+        assert_eq!(
+            output_file_text(&output_files, "calendar_dates.txt")?,
+            "service_id,date,exception_type\r\n\
+             TSA-SWB_20230601_0,20230617,1\r\n\
+             TSA-SWB_20230601_0,20230612,2\r\n\
+             TSA-SWB_20230601_0,20230626,2\r\n"
+        );
+        Ok(())
+    }
+}
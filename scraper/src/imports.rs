@@ -6,6 +6,9 @@ pub use std::io;
 pub use std::path::PathBuf;
 
 pub use ferrysched_shared::constants::*;
+pub use ferrysched_shared::fares::*;
+pub use ferrysched_shared::holidays::*;
 pub use ferrysched_shared::imports::*;
+pub use ferrysched_shared::registry::*;
 pub use ferrysched_shared::types::*;
 pub use ferrysched_shared::utils::*;
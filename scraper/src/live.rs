@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use crate::cache::*;
+use crate::constants::*;
+use crate::imports::*;
+use crate::macros::*;
+use crate::output::*;
+use crate::types::*;
+use crate::utils::*;
+use ferrysched_shared::live::*;
+
+// The all-terminals page lists the major routes, while the terminal pages also list their smaller routes
+const DEPARTURES_URLS: [&str; 3] = [ALL_DEPARTURES_URL, SWB_DEPARTURES_URL, TSA_DEPARTURES_URL];
+
+// Headings name the terminals, such as "Swartz Bay to Galiano Island (Sturdies Bay)"
+fn parse_route_name(text: &str) -> Result<TerminalPair> {
+    let inner = || {
+        let (from_text, to_text) = text.split_once(" to ").ok_or_else(|| anyhow!("Expect ' to ' in route name"))?;
+        let find_terminal = |name_text: &str| {
+            let name_text = name_text.to_lowercase();
+            Registry::get()
+                .terminals
+                .iter()
+                .find(|t| name_text.contains(&t.name.to_lowercase()))
+                .map(|t| t.terminal)
+                .ok_or_else(|| anyhow!("Unrecognized terminal: {:?}", name_text))
+        };
+        Ok(TerminalPair { from: find_terminal(from_text)?, to: find_terminal(to_text)? }) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to parse route name: {:?}", text))
+}
+
+// Reads a time such as "7:04 am", or "ETA: 10:58 am" for an estimate; returns the time and whether it is an estimate
+fn parse_live_time(text: &str) -> Result<Option<(Time, bool)>> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let captures = regex!(r"(?i)^(?:(ETA|ETD):?\s*)?(.+)$").captures(text).expect("Expect live time regex to match");
+    Ok(Some((parse_schedule_time(&captures[2])?, captures.get(1).is_some())))
+}
+
+fn parse_deck_space(text: &str) -> Result<Option<u8>> {
+    if text.is_empty() {
+        Ok(None)
+    } else if text.eq_ignore_ascii_case("full") {
+        Ok(Some(0))
+    } else {
+        let captures =
+            regex!(r"^(\d{1,3})%").captures(text).ok_or_else(|| anyhow!("Invalid deck space: {:?}", text))?;
+        Ok(Some(captures[1].parse()?))
+    }
+}
+
+fn parse_departure_row(row_elem: ElementRef, terminal_pair: TerminalPair, depart_date: Date) -> Result<LiveSailing> {
+    let cell_text = |class: &str| {
+        let selector = Selector::parse(&format!("td.departure-{}", class)).expect("Expect cell selector to parse");
+        row_elem.select(&selector).next().map(|e| element_text(&e)).unwrap_or_default()
+    };
+    let scheduled_text = cell_text("scheduled");
+    let inner = || {
+        let status = cell_text("status").to_lowercase();
+        let depart = parse_live_time(&cell_text("actual"))?;
+        let arrive = parse_live_time(&cell_text("arrival"))?;
+        let vessel = cell_text("vessel");
+        Ok(LiveSailing {
+            terminal_pair,
+            depart_date,
+            scheduled_depart_time: parse_schedule_time(&scheduled_text)?,
+            depart_time: depart.map(|(time, _)| time),
+            departed: depart.map(|(_, is_estimate)| !is_estimate).unwrap_or(false) || status == "departed",
+            arrive_time: arrive.map(|(time, _)| time),
+            arrived: arrive.map(|(_, is_estimate)| !is_estimate).unwrap_or(false) || status == "arrived",
+            cancelled: status.contains("cancel"),
+            vessel: (!vessel.is_empty()).then(|| vessel),
+            deck_space_percent: parse_deck_space(&cell_text("deck-space"))?,
+        }) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to parse departure row for {} at {:?}", terminal_pair, scheduled_text))
+}
+
+fn parse_departures_document(document: &Html) -> Result<Vec<LiveSailing>> {
+    let date_text = element_text(
+        &document.select(selector!("div.departures-date")).next().context("Missing departures date element")?,
+    );
+    let date = Date::parse(&date_text, format_description!("[weekday], [month repr:long] [day padding:none], [year]"))
+        .with_context(|| format!("Failed to parse departures date: {:?}", date_text))?;
+    let mut sailings = Vec::new();
+    for route_elem in document.select(selector!("section.departures-route")) {
+        let name_elem = route_elem.select(selector!(".departures-route-name")).next().context("Missing route name")?;
+        let terminal_pair = parse_route_name(&element_text(&name_elem))?;
+        let mut depart_date = date;
+        let mut previous_time = None;
+        for row_elem in route_elem.select(selector!("tr.departure-row")) {
+            // One odd row shouldn't lose the rest of the page
+            let mut sailing = match parse_departure_row(row_elem, terminal_pair, depart_date) {
+                Ok(sailing) => sailing,
+                Err(err) => {
+                    warn!("Skipping departure row: {:?}", err);
+                    continue;
+                }
+            };
+            // Rows are in departure order, so an earlier time than the row before is just after midnight
+            if previous_time.map(|t| sailing.scheduled_depart_time < t).unwrap_or(false) {
+                depart_date = depart_date.next_day().context("Expect next day")?;
+                sailing.depart_date = depart_date;
+            }
+            previous_time = Some(sailing.scheduled_depart_time);
+            sailings.push(sailing);
+        }
+    }
+    Ok(sailings)
+}
+
+// The pages list some of the same sailings, so fill in what one page has that another doesn't
+fn merge_live_sailings<I: IntoIterator<Item = LiveSailing>>(sailings: I) -> Vec<LiveSailing> {
+    let mut merged: BTreeMap<_, LiveSailing> = BTreeMap::new();
+    for sailing in sailings {
+        let key = (sailing.terminal_pair, sailing.depart_date, sailing.scheduled_depart_time);
+        match merged.get_mut(&key) {
+            Some(existing) => {
+                existing.depart_time = existing.depart_time.or(sailing.depart_time);
+                existing.departed |= sailing.departed;
+                existing.arrive_time = existing.arrive_time.or(sailing.arrive_time);
+                existing.arrived |= sailing.arrived;
+                existing.cancelled |= sailing.cancelled;
+                existing.vessel = existing.vessel.take().or(sailing.vessel);
+                existing.deck_space_percent = existing.deck_space_percent.or(sailing.deck_space_percent);
+            }
+            None => {
+                merged.insert(key, sailing);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+async fn scrape_departures_page(cache: &Cache, url: &str) -> Result<Vec<LiveSailing>> {
+    let inner = async {
+        let document = cache.get_html(url, &HTML_ERROR_REGEX).await?;
+        parse_departures_document(&document)
+    };
+    inner.await.with_context(|| format!("Failed to scrape departures from: {:?}", url))
+}
+
+/// Scrape the departures pages; a page that fails only leaves out its sailings, unless they all fail
+pub async fn scrape_live_sailings(cache: &Cache) -> Result<Vec<LiveSailing>> {
+    let mut sailings = Vec::new();
+    let mut last_err = None;
+    for url in DEPARTURES_URLS {
+        match scrape_departures_page(cache, url).await {
+            Ok(page_sailings) => sailings.extend(page_sailings),
+            Err(err) => {
+                warn!("{:?}", err);
+                last_err = Some(err);
+            }
+        }
+    }
+    match last_err {
+        Some(err) if sailings.is_empty() => Err(err.context("Failed to scrape any departures")),
+        _ => Ok(merge_live_sailings(sailings)),
+    }
+}
+
+pub async fn run_live(options: &Options, live_options: &LiveOptions, cache: &Cache) -> Result<()> {
+    loop {
+        let result = async {
+            let sailings = scrape_live_sailings(cache).await?;
+            info!("Scraped live status of {} sailing(s)", sailings.len());
+            write_live_output(options, &LiveData::new(sailings, now_utc())).await
+        }
+        .await;
+        let poll_interval = match live_options.poll_interval {
+            Some(poll_interval) => poll_interval,
+            None => return result,
+        };
+        // Keep polling, since the next poll may well succeed
+        if let Err(err) = result {
+            error!("{:?}", err);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(poll_interval)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_departures_fixtures() -> Result<()> {
+        let mut sailings = Vec::new();
+        for page in ["ALL", "SWB"] {
+            let path = format!("{}/fixtures/departures/{}.html", env!("CARGO_MANIFEST_DIR"), page);
+            sailings.extend(parse_departures_document(&Html::parse_document(&fs::read_to_string(&path)?))?);
+        }
+        let sailings = merge_live_sailings(sailings);
+        let swb_tsa = TerminalPair { from: Terminal::new("SWB"), to: Terminal::new("TSA") };
+        let find = |terminal_pair, scheduled_depart_time| {
+            sailings
+                .iter()
+                .find(|s| s.terminal_pair == terminal_pair && s.scheduled_depart_time == scheduled_depart_time)
+                .with_context(|| format!("Expect live sailing for {} at {}", terminal_pair, scheduled_depart_time))
+        };
+        assert_eq!(sailings.len(), 7);
+        assert!(sailings.iter().all(|s| s.depart_date == date!(2026 - 10 - 15)));
+        let arrived = find(swb_tsa, time!(07:00))?;
+        assert!(arrived.departed && arrived.arrived);
+        assert_eq!((arrived.depart_time, arrived.arrive_time), (Some(time!(07:04)), Some(time!(08:38))));
+        let departed = find(swb_tsa, time!(09:00))?;
+        assert!(departed.departed && !departed.arrived);
+        assert_eq!(departed.delay(), Some(Duration::minutes(21)));
+        // The deck space is only on the terminal's page
+        let delayed = find(swb_tsa, time!(11:00))?;
+        assert!(!delayed.departed);
+        assert_eq!((delayed.depart_time, delayed.deck_space_percent), (Some(time!(11:15)), Some(42)));
+        assert_eq!(find(swb_tsa, time!(13:00))?.deck_space_percent, Some(0));
+        assert!(find(swb_tsa, time!(15:00))?.cancelled);
+        let sgi = find(TerminalPair { from: Terminal::new("SWB"), to: Terminal::new("PSB") }, time!(10:20))?;
+        assert_eq!(sgi.vessel.as_deref(), Some("Queen of Cumberland"));
+        let hsb_nan = find(TerminalPair { from: Terminal::new("HSB"), to: Terminal::new("NAN") }, time!(10:40))?;
+        assert_eq!(hsb_nan.arrive_time, Some(time!(12:20)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_bad_departure_row() -> Result<()> {
+        let document = Html::parse_document(
+            r#"<div class="departures-date">Thursday, October 15, 2026</div>
+            <section class="departures-route">
+                <h2 class="departures-route-name">Swartz Bay to Tsawwassen</h2>
+                <table><tbody>
+                    <tr class="departure-row"><td class="departure-scheduled">Soon</td></tr>
+                    <tr class="departure-row">
+                        <td class="departure-scheduled">9:00 am</td>
+                        <td class="departure-deck-space">Lots</td>
+                    </tr>
+                    <tr class="departure-row"><td class="departure-scheduled">11:00 am</td></tr>
+                </tbody></table>
+            </section>"#,
+        );
+        let sailings = parse_departures_document(&document)?;
+        assert_eq!(sailings.iter().map(|s| s.scheduled_depart_time).collect::<Vec<_>>(), vec![time!(11:00)]);
+        Ok(())
+    }
+}
@@ -1,11 +1,21 @@
+mod annotation_grammar;
 mod annotations;
 mod cache;
 mod constants;
 mod depart_time_and_row_annotations;
+mod diff;
+mod fallback;
+mod fares;
+mod fetch;
+mod fixtures;
+mod gtfs;
 mod imports;
+mod live;
 mod macros;
 mod output;
 mod scraper;
+mod service_notices;
+mod stop_times;
 mod types;
 mod utils;
 
@@ -14,11 +24,20 @@ use directories::ProjectDirs;
 use std::env;
 use std::process;
 
-use crate::cache::Cache;
+use crate::cache::{run_cache_command, Cache};
+use crate::diff::run_diff;
+use crate::fallback::{resolve_route_schedules, DegradedRoutesError};
+use crate::fares::scrape_fares;
+use crate::fixtures::run_record_fixture;
 use crate::imports::*;
+use crate::live::run_live;
 use crate::output::write_output;
 use crate::scraper::scrape_schedules;
-use crate::types::Options;
+use crate::service_notices::{attach_service_notices, scrape_service_notices};
+use crate::types::{Command, Options};
+
+/// Exit status when some routes failed to scrape, but the output was written using their last known good schedules
+const DEGRADED_EXIT_CODE: i32 = 2;
 
 #[derive(Parser, Debug)]
 pub struct CliArgs {
@@ -28,6 +47,9 @@ pub struct CliArgs {
 
     #[clap(flatten)]
     options: Options,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
 #[tokio::main]
@@ -44,15 +66,55 @@ async fn main() {
             .init();
     }
     let inner = async {
+        if let Some(registry_file) = &cli_args.options.registry_file {
+            let json = fs::read_to_string(registry_file)
+                .with_context(|| format!("Failed to read registry file: {:?}", registry_file))?;
+            Registry::set_override(Registry::from_json_str(&json)?)?;
+        }
+        // Terminal codes in the arguments can only be checked once the registry is settled
+        if let Some(terminal_pair) = cli_args.options.terminals {
+            terminal_pair.ensure_known()?;
+        }
+        if let Some(Command::RecordFixture(record_fixture_options)) = &cli_args.command {
+            record_fixture_options.terminal_pair.ensure_known()?;
+        }
+        if let Some(Command::Diff(diff_options)) = &cli_args.command {
+            return run_diff(diff_options);
+        }
         let project_dirs = ProjectDirs::from("io", "borsboom", env!("CARGO_PKG_NAME"))
             .ok_or_else(|| anyhow!("Could not get project directories"))?;
-        let cache = Cache::new(Duration::hours(cli_args.options.max_cache_age), &project_dirs);
-        let schedules = scrape_schedules(&cli_args.options, &cache).await?;
-        write_output(&cli_args.options, &schedules).await?;
+        if let Some(Command::RecordFixture(record_fixture_options)) = &cli_args.command {
+            let pages_dir = cli_args.options.replay_dir.as_deref().unwrap_or_else(|| project_dirs.cache_dir());
+            return run_record_fixture(record_fixture_options, pages_dir);
+        }
+        if let Some(Command::Cache(cache_options)) = &cli_args.command {
+            return run_cache_command(cache_options, project_dirs.cache_dir());
+        }
+        let cache = Cache::from_options(&cli_args.options, project_dirs.cache_dir().to_path_buf());
+        if let Some(Command::Live(live_options)) = &cli_args.command {
+            // Always ask whether the departures pages changed, since they are only useful while current
+            return run_live(&cli_args.options, live_options, &cache.with_max_cache_age(Duration::ZERO)).await;
+        }
+        let route_results = scrape_schedules(&cli_args.options, &cache).await;
+        let (mut schedules, failed_terminal_pairs) =
+            resolve_route_schedules(&cli_args.options, &cache, route_results).await?;
+        // Schedules are still worth publishing without notices, so this doesn't fail the run
+        match scrape_service_notices(&cache, today_vancouver()).await {
+            Ok(notices) => attach_service_notices(&mut schedules, &notices),
+            Err(err) => warn!("{:?}", err),
+        }
+        let fares = if cli_args.options.fares { scrape_fares(&cli_args.options, &cache).await } else { vec![] };
+        cache.log_changed_pages();
+        write_output(&cli_args.options, schedules, fares).await?;
+        if !failed_terminal_pairs.is_empty() {
+            return Err(DegradedRoutesError { terminal_pairs: failed_terminal_pairs }.into());
+        }
         Ok(()) as Result<()>
     };
     if let Err(error) = inner.await {
         error!("{:?}", error);
-        process::exit(1);
+        // The output was still written, so let scheduled runs tell this apart from a failure that wrote nothing
+        let exit_code = if error.downcast_ref::<DegradedRoutesError>().is_some() { DEGRADED_EXIT_CODE } else { 1 };
+        process::exit(exit_code);
     }
 }
@@ -1,49 +1,76 @@
+use crate::gtfs::*;
 use crate::imports::*;
 use crate::types::*;
 
+use ferrysched_shared::data_format::*;
+use ferrysched_shared::ical::*;
+use ferrysched_shared::live::*;
+
+use std::path::Path;
 use tempfile::NamedTempFile;
 
 static S3_CACHE_MAX_AGE: Lazy<Duration> = Lazy::new(|| Duration::hours(12));
+static S3_LIVE_CACHE_MAX_AGE: Lazy<Duration> = Lazy::new(|| Duration::minutes(1));
 
 async fn upload_to_s3(
     aws_config: &aws_types::SdkConfig,
     bucket: &str,
     key: &str,
-    schedules: &[Schedule],
+    content_type: &str,
+    cache_max_age: Duration,
+    contents: Vec<u8>,
 ) -> Result<()> {
-    info!("Uploading schedules JSON to: s3://{}/{}", bucket, key);
+    info!("Uploading to: s3://{}/{}", bucket, key);
     let s3_client = aws_sdk_s3::Client::new(aws_config);
     s3_client
         .put_object()
         .bucket(bucket)
         .key(key)
-        .content_type("application/json")
+        .content_type(content_type)
         .acl(aws_sdk_s3::model::ObjectCannedAcl::PublicRead)
-        .cache_control(format!("max-age={},public", S3_CACHE_MAX_AGE.whole_seconds()))
-        .body(aws_sdk_s3::types::ByteStream::from(
-            serde_json::to_vec(schedules).expect("Expect schedules to serialize to JSON"),
-        ))
+        .cache_control(format!("max-age={},public", cache_max_age.whole_seconds()))
+        .body(aws_sdk_s3::types::ByteStream::from(contents))
         .send()
         .await
-        .with_context(|| format!("Failed to upload schedules JSON to S3: s3://{}/{}", bucket, key))?;
+        .with_context(|| format!("Failed to upload to S3: s3://{}/{}", bucket, key))?;
     Ok(())
 }
 
+// Returns `None` if there is no such object
+async fn download_from_s3(aws_config: &aws_types::SdkConfig, bucket: &str, key: &str) -> Result<Option<Vec<u8>>> {
+    info!("Downloading from: s3://{}/{}", bucket, key);
+    let s3_client = aws_sdk_s3::Client::new(aws_config);
+    let inner = async {
+        let output = match s3_client.get_object().bucket(bucket).key(key).send().await {
+            Ok(output) => output,
+            Err(aws_sdk_s3::types::SdkError::ServiceError { err, .. }) if err.is_no_such_key() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(output.body.collect().await?.into_bytes().to_vec())) as Result<_>
+    };
+    inner.await.with_context(|| format!("Failed to download from S3: s3://{}/{}", bucket, key))
+}
+
 async fn invalidate_cloudfront_distribution(
     aws_config: &aws_types::SdkConfig,
     distribution_id: &str,
-    s3_key: &str,
+    s3_keys: &[String],
 ) -> Result<()> {
     let cloudfront_client = aws_sdk_cloudfront::Client::new(aws_config);
-    let path = format!("/{}", s3_key);
-    info!("Invalidating CloudFront distribution {:?} for path: {:?}", distribution_id, path);
+    let paths: Vec<_> = s3_keys.iter().map(|k| format!("/{}", k)).collect();
+    info!("Invalidating CloudFront distribution {:?} for paths: {:?}", distribution_id, paths);
     cloudfront_client
         .create_invalidation()
         .distribution_id(distribution_id)
         .invalidation_batch(
             aws_sdk_cloudfront::model::InvalidationBatch::builder()
                 .caller_reference(OffsetDateTime::now_utc().unix_timestamp_nanos().to_string())
-                .paths(aws_sdk_cloudfront::model::Paths::builder().quantity(1).items(path).build())
+                .paths(
+                    aws_sdk_cloudfront::model::Paths::builder()
+                        .quantity(paths.len() as i32)
+                        .set_items(Some(paths))
+                        .build(),
+                )
                 .build(),
         )
         .send()
@@ -54,31 +81,172 @@ async fn invalidate_cloudfront_distribution(
     Ok(())
 }
 
-pub async fn write_output(options: &Options, schedules: &[Schedule]) -> Result<()> {
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let inner = || {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => std::env::current_dir().context("Failed to get current directory")?,
+        };
+        fs::create_dir_all(&parent).with_context(|| format!("Failed to create directory: {:?}", parent))?;
+        let mut temp_file = NamedTempFile::new_in(&parent).context("Failed to create temporary file")?;
+        io::Write::write_all(&mut temp_file, contents).context("Failed to write temporary file")?;
+        temp_file.persist(path).context("Failed to persist temporary file")?;
+        Ok(()) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to write file: {:?}", path))
+}
+
+fn sibling_s3_key(s3_key: &str, relative_path: &str) -> String {
+    match s3_key.rsplit_once('/') {
+        Some((prefix, _)) => format!("{}/{}", prefix, relative_path),
+        None => relative_path.to_string(),
+    }
+}
+
+fn ical_output_files(schedules: &[Schedule]) -> Vec<OutputFile> {
+    let today = today_vancouver();
+    let terminal_pairs: Vec<_> = schedules.iter().map(|s| s.terminal_pair).sorted().dedup().collect();
+    let mut output_files = Vec::new();
+    for terminal_pair in terminal_pairs {
+        let max_date = schedules.iter().filter(|s| s.terminal_pair == terminal_pair).map(|s| s.date_range.to).max();
+        let date_range = DateRange { from: today, to: max(today, max_date.unwrap_or(today)) };
+        let events = terminal_pair_ical_events(schedules, terminal_pair, date_range);
+        let calendar_name = format!("Ferry {} to {}", terminal_pair.from.name(), terminal_pair.to.name());
+        output_files.push(OutputFile {
+            path: format!("ical/{}.ics", terminal_pair),
+            content_type: "text/calendar",
+            contents: format_ical(&calendar_name, &events).into_bytes(),
+        });
+    }
+    output_files
+}
+
+fn route_output_files(schedules_data: &SchedulesData) -> Vec<OutputFile> {
+    let terminal_pairs: Vec<_> = schedules_data.schedules.iter().map(|s| s.terminal_pair).sorted().dedup().collect();
+    let mut output_files = Vec::new();
+    let mut index_entries = Vec::new();
+    for terminal_pair in terminal_pairs {
+        let schedules: Vec<_> =
+            schedules_data.schedules.iter().filter(|s| s.terminal_pair == terminal_pair).cloned().collect();
+        let date_ranges = schedules.iter().map(|s| s.date_range).collect();
+        let route_data = SchedulesData::new(schedules, schedules_data.generated_at);
+        let contents = serde_json::to_vec(&route_data).expect("Expect route schedules data to serialize to JSON");
+        let path = format!("{}.json", terminal_pair);
+        index_entries.push(RouteIndexEntry {
+            terminal_pair,
+            path: path.clone(),
+            date_ranges,
+            hash: content_hash(&contents),
+        });
+        output_files.push(OutputFile {
+            path: format!("{}/{}", ROUTES_DIRECTORY, path),
+            content_type: "application/json",
+            contents,
+        });
+    }
+    let index = RouteIndex::new(index_entries, schedules_data.generated_at);
+    output_files.push(OutputFile {
+        path: format!("{}/{}", ROUTES_DIRECTORY, ROUTE_INDEX_FILENAME),
+        content_type: "application/json",
+        contents: serde_json::to_vec(&index).expect("Expect route index to serialize to JSON"),
+    });
+    output_files
+}
+
+fn fares_output_file(fares: Vec<Fare>) -> OutputFile {
+    let fares_data = FaresData::new(fares, now_utc());
+    OutputFile {
+        path: FARES_FILENAME.to_string(),
+        content_type: "application/json",
+        contents: serde_json::to_vec(&fares_data).expect("Expect fares data to serialize to JSON"),
+    }
+}
+
+fn extra_output_files(options: &Options, schedules: &[Schedule], fares: Vec<Fare>) -> Vec<OutputFile> {
+    let mut output_files = Vec::new();
+    if options.gtfs {
+        output_files.extend(gtfs_output_files(schedules));
+    }
+    if options.ical {
+        output_files.extend(ical_output_files(schedules));
+    }
+    if options.fares {
+        output_files.push(fares_output_file(fares));
+    }
+    output_files
+}
+
+/// The schedules from the output of the previous run, from the output file or else the S3 bucket, if there is one
+pub async fn read_published_schedules(options: &Options) -> Result<Option<Vec<Schedule>>> {
     let inner = async {
+        let json = match (&options.output_file, &options.output_s3_bucket) {
+            (Some(output_file_path), _) if output_file_path.exists() => {
+                info!("Reading published schedules from: {:?}", output_file_path);
+                fs::read(output_file_path).with_context(|| format!("Failed to read file: {:?}", output_file_path))?
+            }
+            (_, Some(bucket)) => {
+                let aws_config = aws_config::from_env().load().await;
+                match download_from_s3(&aws_config, bucket, &options.output_s3_key).await? {
+                    Some(json) => json,
+                    None => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(SchedulesData::from_json_str(&String::from_utf8(json)?)?.schedules)) as Result<_>
+    };
+    inner.await.context("Failed to read published schedules")
+}
+
+pub async fn write_output(options: &Options, schedules: Vec<Schedule>, fares: Vec<Fare>) -> Result<()> {
+    let inner = async {
+        let mut extra_files = extra_output_files(options, &schedules, fares);
+        let schedules_data = SchedulesData::new(schedules, now_utc());
         if let (None, None) = (options.output_file.as_ref(), options.output_s3_bucket.as_ref()) {
-            serde_json::to_writer_pretty(io::stdout(), &schedules)
+            ensure!(extra_files.is_empty(), "Additional output formats require an output file or S3 bucket");
+            serde_json::to_writer_pretty(io::stdout(), &schedules_data)
                 .context("Failed to write schedules JSON to standard output")?;
         } else {
+            let schedules_json =
+                serde_json::to_vec(&schedules_data).expect("Expect schedules data to serialize to JSON");
+            extra_files.extend(route_output_files(&schedules_data));
             if let Some(output_file_path) = &options.output_file {
                 info!("Writing schedules JSON to: {:?}", output_file_path);
-                let mut temp_file = NamedTempFile::new_in(
-                    output_file_path
-                        .parent()
-                        .unwrap_or(&std::env::current_dir().context("Failed to get current directory")?),
-                )
-                .with_context(|| format!("Failed to create schedules JSON output file: {:?}", output_file_path))?;
-                serde_json::to_writer(&mut temp_file, &schedules)
-                    .with_context(|| format!("Failed to write schedules JSON to file: {:?}", output_file_path))?;
-                temp_file
-                    .persist(output_file_path)
-                    .context(format!("Failed to persist temporary file to: {:?}", output_file_path))?;
+                write_file(output_file_path, &schedules_json)?;
+                let output_dir = output_file_path.parent().unwrap_or_else(|| Path::new(""));
+                for extra_file in &extra_files {
+                    let extra_file_path = output_dir.join(&extra_file.path);
+                    debug!("Writing output file: {:?}", extra_file_path);
+                    write_file(&extra_file_path, &extra_file.contents)?;
+                }
             }
             if let Some(bucket) = &options.output_s3_bucket {
                 let aws_config = aws_config::from_env().load().await;
-                upload_to_s3(&aws_config, bucket, &options.output_s3_key, schedules).await?;
+                let mut s3_keys = vec![options.output_s3_key.clone()];
+                upload_to_s3(
+                    &aws_config,
+                    bucket,
+                    &options.output_s3_key,
+                    "application/json",
+                    *S3_CACHE_MAX_AGE,
+                    schedules_json,
+                )
+                .await?;
+                for extra_file in extra_files {
+                    let s3_key = sibling_s3_key(&options.output_s3_key, &extra_file.path);
+                    upload_to_s3(
+                        &aws_config,
+                        bucket,
+                        &s3_key,
+                        extra_file.content_type,
+                        *S3_CACHE_MAX_AGE,
+                        extra_file.contents,
+                    )
+                    .await?;
+                    s3_keys.push(s3_key);
+                }
                 if let Some(distribution_id) = &options.invalidate_cloudfront_distribution_id {
-                    invalidate_cloudfront_distribution(&aws_config, distribution_id, &options.output_s3_key).await?;
+                    invalidate_cloudfront_distribution(&aws_config, distribution_id, &s3_keys).await?;
                 }
             }
         }
@@ -86,3 +254,29 @@ pub async fn write_output(options: &Options, schedules: &[Schedule]) -> Result<(
     };
     inner.await.context("Failed to write output")
 }
+
+/// Write the live departures JSON alongside the schedules JSON (or to standard output, if there is neither an output
+/// file nor an S3 bucket)
+pub async fn write_live_output(options: &Options, live_data: &LiveData) -> Result<()> {
+    let inner = async {
+        if let (None, None) = (options.output_file.as_ref(), options.output_s3_bucket.as_ref()) {
+            serde_json::to_writer_pretty(io::stdout(), live_data)
+                .context("Failed to write live departures JSON to standard output")?;
+            return Ok(());
+        }
+        let live_json = serde_json::to_vec(live_data).expect("Expect live departures data to serialize to JSON");
+        if let Some(output_file_path) = &options.output_file {
+            let live_file_path = output_file_path.parent().unwrap_or_else(|| Path::new("")).join(LIVE_FILENAME);
+            info!("Writing live departures JSON to: {:?}", live_file_path);
+            write_file(&live_file_path, &live_json)?;
+        }
+        // Not invalidating CloudFront, since the short cache max age is enough for a file that changes every poll
+        if let Some(bucket) = &options.output_s3_bucket {
+            let aws_config = aws_config::from_env().load().await;
+            let s3_key = sibling_s3_key(&options.output_s3_key, LIVE_FILENAME);
+            upload_to_s3(&aws_config, bucket, &s3_key, "application/json", *S3_LIVE_CACHE_MAX_AGE, live_json).await?;
+        }
+        Ok(()) as Result<_>
+    };
+    inner.await.context("Failed to write live departures output")
+}
@@ -1,3 +1,6 @@
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::collections::BTreeSet;
+
 use crate::annotations::*;
 use crate::cache::*;
 use crate::constants::*;
@@ -53,26 +56,103 @@ fn parse_depart_times_and_annotations(
 
 fn parse_stops(stops_texts: Vec<String>) -> Result<Vec<Stop>> {
     let inner = || {
-        let stops_chunks = stops_texts.chunks(2);
-        let stops = parse_schedule_stops(stops_chunks.map(|items| items.join(" ")))?;
+        // Where stop times are published, they follow the text of the stop they belong to
+        let mut stop_texts_with_times: Vec<(&str, Vec<&str>)> = Vec::new();
+        for text in &stops_texts {
+            match stop_texts_with_times.last_mut() {
+                Some((_, times)) if is_stop_time_text(text) => times.push(text),
+                _ => stop_texts_with_times.push((text, Vec::new())),
+            }
+        }
+        let stops_chunks = stop_texts_with_times.chunks(2);
+        let stops = parse_schedule_stops(stops_chunks.map(|items| {
+            items
+                .iter()
+                .map(|(text, _)| *text)
+                .chain(items.iter().flat_map(|(_, times)| times.iter().cloned()))
+                .join(" ")
+        }))?;
         Ok(stops) as Result<_>
     };
     inner().with_context(|| format!("Failed to parse stops: {:?}", stops_texts))
 }
 
-fn parse_table(table_elem: ElementRef, date_range: &DateRange) -> Result<Vec<ScheduleItem>> {
+// Column positions in a sailing row, which vary between routes' schedule tables
+#[derive(Debug, Eq, PartialEq)]
+struct TableRowLayout {
+    depart: usize,
+    arrive: usize,
+    stops: Option<usize>,
+    vessel: Option<usize>,
+    cell_count: usize,
+}
+
+impl TableRowLayout {
+    // Tables without a column header row have always had six cells in each sailing row
+    const WITHOUT_HEADER: TableRowLayout =
+        TableRowLayout { depart: 1, arrive: 2, stops: Some(4), vessel: None, cell_count: 6 };
+
+    // The header names the columns, so a reordered or unfamiliar table fails here rather than being misread
+    fn from_header_texts(header_texts: &[String]) -> Result<TableRowLayout> {
+        let inner = || {
+            let find_column = |names: &[&str]| {
+                let mut indices = header_texts
+                    .iter()
+                    .positions(|text| names.iter().any(|name| text.to_lowercase().starts_with(name)));
+                let index = indices.next();
+                ensure!(indices.next().is_none(), "Expect at most one column for {:?}", names);
+                Ok(index) as Result<Option<usize>>
+            };
+            let layout = TableRowLayout {
+                depart: find_column(&["depart"])?.context("Missing depart column")?,
+                arrive: find_column(&["arrive"])?.context("Missing arrive column")?,
+                stops: find_column(&["stops", "transfers"])?,
+                vessel: find_column(&["vessel"])?,
+                cell_count: header_texts.len(),
+            };
+            for (index, text) in header_texts.iter().enumerate() {
+                ensure!(
+                    text.is_empty()
+                        || [Some(layout.depart), Some(layout.arrive), layout.stops, layout.vessel]
+                            .contains(&Some(index))
+                        || ["duration", "details"].iter().any(|name| text.to_lowercase().starts_with(name)),
+                    "Unrecognized column: {:?}",
+                    text
+                );
+            }
+            Ok(layout) as Result<_>
+        };
+        inner().with_context(|| format!("Unexpected schedule table header: {:?}", header_texts))
+    }
+
+    fn from_table(table_elem: ElementRef) -> Result<TableRowLayout> {
+        match table_elem.select(selector!("thead tr:not([data-schedule-day])")).next() {
+            Some(header_row_elem) => {
+                let header_texts: Vec<_> = header_row_elem.select(selector!("th")).map(|e| element_text(&e)).collect();
+                TableRowLayout::from_header_texts(&header_texts)
+            }
+            None => Ok(TableRowLayout::WITHOUT_HEADER),
+        }
+    }
+}
+
+// Returns the schedule items, and any notes that weren't recognized
+fn parse_table(table_elem: ElementRef, date_range: &DateRange) -> Result<(Vec<ScheduleItem>, BTreeSet<String>)> {
     static DATE_RANGE_TO_SKIP: DateRange = DateRange { from: date!(2026 - 11 - 25), to: date!(2027 - 05 - 06) };
     static DEPART_TIME_TO_SKIP: Lazy<DepartTimeAndRowAnnotations> = Lazy::new(|| DepartTimeAndRowAnnotations {
         time: time!(14:30),
         row_dates: AnnotationDates {
             only: HashSet::from([date!(2026 - 12 - 29), date!(2026 - 12 - 22)]),
             except: HashSet::new(),
+            has_only_clause: true,
         },
         row_notes: AnnotationNotes { map: HashMap::new() },
     });
     let inner = || {
         let mut items = Vec::new();
-        for day_row_elem in table_elem.select(selector!("thead tr")) {
+        let mut notes_to_review = BTreeSet::new();
+        let layout = TableRowLayout::from_table(table_elem)?;
+        for day_row_elem in table_elem.select(selector!("thead tr[data-schedule-day]")) {
             let weekday_text = day_row_elem
                 .value()
                 .attr("data-schedule-day")
@@ -85,15 +165,17 @@ fn parse_table(table_elem: ElementRef, date_range: &DateRange) -> Result<Vec<Sch
             for sailing_row_elem in weekday_sailings_tbody_elem.select(selector!("tr.schedule-table-row")) {
                 let cell_elems: Vec<_> = sailing_row_elem.select(selector!("td")).collect();
                 ensure!(
-                    cell_elems.len() == 6,
-                    "Row should have six cells: {:?}",
+                    cell_elems.len() == layout.cell_count,
+                    "Row should have {} cells to match the table layout: {:?}",
+                    layout.cell_count,
                     cell_elems.iter().map(element_text).collect::<Vec<_>>()
                 );
                 let (annotations, depart_times_texts) =
-                    match parse_annotations(element_texts(&cell_elems[1]), date_range)? {
+                    match parse_annotations(element_texts(&cell_elems[layout.depart]), date_range)? {
                         None => continue,
                         Some(result) => result,
                     };
+                notes_to_review.extend(annotations.notes_to_review.iter().cloned());
                 let depart_times = parse_depart_times_and_annotations(depart_times_texts, &annotations)?;
                 ensure!(depart_times.len() == 1, "Expect exactly one depart time in row");
                 let depart_time = depart_times.into_iter().next().expect("Expect at least one depart time in row");
@@ -105,63 +187,73 @@ fn parse_table(table_elem: ElementRef, date_range: &DateRange) -> Result<Vec<Sch
                     // This is a workaround for an error in BC Ferries' schedule
                     continue;
                 }
-                let arrive_time = parse_arrive_time_or_duration(depart_time.time, &element_text(&cell_elems[2]))?;
+                let (arrive_time, arrive_day_offset) =
+                    parse_arrive_time_or_duration(depart_time.time, &element_text(&cell_elems[layout.arrive]))?;
+                let depart_day_offset = annotations.is_after_midnight.into();
                 if arrive_time != depart_time.time {
-                    let stops = parse_stops(element_texts(&cell_elems[4]))?;
+                    let stops = match layout.stops {
+                        Some(stops_index) => parse_stops(element_texts(&cell_elems[stops_index]))?,
+                        None => vec![],
+                    };
+                    let vessel = layout
+                        .vessel
+                        .map(|vessel_index| element_text(&cell_elems[vessel_index]))
+                        .filter(|text| !text.is_empty());
                     let date_restriction = depart_time.row_dates.into_date_restriction_by_weekday(weekday);
                     let notes = annotation_notes_date_restictions(depart_time.row_notes, weekday, &date_restriction);
                     items.push(ScheduleItem {
-                        sailing: Sailing { depart_time: depart_time.time, arrive_time, stops: stops.clone() },
+                        sailing: Sailing {
+                            depart_time: depart_time.time,
+                            arrive_time,
+                            stops: stops.clone(),
+                            depart_day_offset,
+                            arrive_day_offset: depart_day_offset + arrive_day_offset,
+                            vessel,
+                        },
                         weekdays: HashMap::from_iter([(weekday, date_restriction)]),
                         notes,
+                        cancellations: HashMap::new(),
                     });
                 }
             }
         }
-        ScheduleItem::merge_items(items)
+        Ok((ScheduleItem::merge_items(items)?, notes_to_review)) as Result<_>
     };
     inner().context("Failed to parse route schedule table")
 }
 
-async fn scrape_schedule(
-    options: &Options,
+fn parse_schedule_date_range(document: &Html) -> Result<DateRange> {
+    let date_range_text = element_text(
+        &document
+            .select(selector!("div.schedule-custom-calendar a"))
+            .next()
+            .context("Missing schedule calendar header link element")?,
+    );
+    DateRange::parse(
+        &date_range_text,
+        format_description!("[month repr:short case_sensitive:false] [day], [year]"),
+        " - ",
+    )
+    .with_context(|| format!("Failed to parse date range: {:?}", date_range_text))
+}
+
+fn parse_schedule_page(
     source_url: &str,
     document: &Html,
     terminal_pair: TerminalPair,
     index: usize,
     today: Date,
+    restrict_date: Option<Date>,
 ) -> Result<Option<Schedule>> {
-    let inner = async {
-        let date_range_text = element_text(
-            &document
-                .select(selector!("div.schedule-custom-calendar a"))
-                .next()
-                .context("Missing schedule calendar header link element")?,
-        );
-        let date_range = DateRange::parse(
-            &date_range_text,
-            format_description!("[month repr:short case_sensitive:false] [day], [year]"),
-            " - ",
-        )
-        .with_context(|| format!("Failed to parse date range: {:?}", date_range_text))?;
-        if !should_scrape_schedule_date(date_range, today, options.date) {
+    let inner = || {
+        let date_range = parse_schedule_date_range(document)?;
+        if !should_scrape_schedule_date(date_range, today, restrict_date) {
             return Ok(None);
         }
-        if DISABLED_TERMINAL_PAIRS.contains(&terminal_pair) {
-            info!("Skipping parsing disabled schedule for {}, {}", terminal_pair, date_range);
-            return Ok(Some(Schedule {
-                terminal_pair,
-                date_range,
-                items: vec![],
-                source_url: source_url.to_string(),
-                refreshed_at: now_vancouver(),
-                alerts: vec![Alert {message: "THIS SCHEDULE IS CURRENTLY UNAVAILABLE!  BC Ferries has update the schedule format on their website and the scraper needs to be updated to understand it.".to_string(), level: AlertLevel::Danger}],
-            }));
-        }
         info!("Parsing schedule for {}, {}", terminal_pair, date_range);
         let opt_table_elem = document.select(selector!("div.seasonal-schedule-wrapper table")).next();
         if let Some(table_elem) = opt_table_elem {
-            let items = parse_table(table_elem, &date_range)?;
+            let (items, notes_to_review) = parse_table(table_elem, &date_range)?;
             Ok(Some(Schedule {
                 terminal_pair,
                 date_range,
@@ -169,6 +261,7 @@ async fn scrape_schedule(
                 source_url: source_url.to_string(),
                 refreshed_at: now_vancouver(),
                 alerts: vec![],
+                notes_to_review: notes_to_review.into_iter().collect(),
             })) as Result<_>
         } else if index == 0 {
             for elem in document.select(selector!("div.seasonalSchedulesContainer div.text-center")) {
@@ -183,59 +276,252 @@ async fn scrape_schedule(
             Ok(None)
         }
     };
-    inner.await.with_context(|| format!("Failed to scrape route schedule for {} from: {:?}", terminal_pair, source_url))
+    inner().with_context(|| format!("Failed to scrape route schedule for {} from: {:?}", terminal_pair, source_url))
+}
+
+pub fn route_schedules_base_url(terminal_pair: TerminalPair) -> String {
+    format!("{}/{}", OTHER_ROUTE_SCHEDULES_BASE_URL, terminal_pair.to_schedule_code_pair())
+}
+
+/// URLs of the route's schedule pages linked from its base page, starting with the base page itself
+pub fn schedule_page_urls(base_url: &str, base_document: &Html) -> Result<Vec<String>> {
+    let date_range_modal_elem =
+        base_document.select(selector!("div#dateRangeModal")).next().context("Missing date range modal")?;
+    let mut urls = Vec::new();
+    for (index, schedule_path_query_elem) in date_range_modal_elem.select(selector!("div#dateRangeModal a")).enumerate()
+    {
+        let schedule_path_query_text = schedule_path_query_elem.value().attr("href").ok_or_else(|| {
+            anyhow!("Missing schedule path/query in date range link element: {}", schedule_path_query_elem.html())
+        })?;
+        urls.push(if index == 0 {
+            base_url.to_string()
+        } else {
+            format!("{}{}", BCFERRIES_BASE_URL, schedule_path_query_text)
+        });
+    }
+    Ok(urls)
+}
+
+/// Parse a route's schedule pages (as returned by `schedule_page_urls`, along with their documents)
+pub fn parse_route_schedules(
+    terminal_pair: TerminalPair,
+    pages: &[(String, Html)],
+    today: Date,
+    restrict_date: Option<Date>,
+) -> Result<Vec<Schedule>> {
+    let mut schedules = Vec::new();
+    for (index, (source_url, document)) in pages.iter().enumerate() {
+        let opt_schedule = parse_schedule_page(source_url, document, terminal_pair, index, today, restrict_date)?;
+        opt_schedule.iter().for_each(|s| debug!("Parsed schedule: {:#?}", s));
+        schedules.extend(opt_schedule);
+    }
+    ensure!(!schedules.is_empty(), "Failed to find any schedule elements");
+    Ok(schedules)
 }
 
 pub async fn scrape_route_schedules(
     options: &Options,
-    cache: &Cache<'_>,
+    cache: &Cache,
     terminal_pair: TerminalPair,
     today: Date,
 ) -> Result<Vec<Schedule>> {
     if options.terminals.is_some() && options.terminals != Some(terminal_pair) {
         return Ok(vec![]);
     }
-    let base_url = format!("{}/{}", OTHER_ROUTE_SCHEDULES_BASE_URL, terminal_pair.to_schedule_code_pair());
+    let base_url = route_schedules_base_url(terminal_pair);
     let inner = async {
         let base_document = cache
             .get_html(&base_url, &HTML_ERROR_REGEX)
             .await
             .with_context(|| format!("Failed to download base schedule HTML from: {:?}", base_url))?;
-        let date_range_modal_elem =
-            base_document.select(selector!("div#dateRangeModal")).next().context("Missing date range modal")?;
-        let schedule_path_query_elems = date_range_modal_elem.select(selector!("div#dateRangeModal a"));
-        let mut schedules = Vec::new();
-        for (index, schedule_path_query_elem) in schedule_path_query_elems.enumerate() {
-            let schedule_path_query_text = schedule_path_query_elem.value().attr("href").ok_or_else(|| {
-                anyhow!("Missing schedule path/query in date range link element: {}", schedule_path_query_elem.html())
-            })?;
-            let opt_schedule = if index == 0 {
-                scrape_schedule(options, &base_url, &base_document, terminal_pair, index, today).await?
-            } else {
-                let source_url = format!("{}{}", BCFERRIES_BASE_URL, schedule_path_query_text);
+        let source_urls = schedule_page_urls(&base_url, &base_document)?;
+        let mut pages: Vec<_> = stream::iter(source_urls.iter().skip(1))
+            .map(|source_url| async move {
                 let document = cache
-                    .get_html(&source_url, &HTML_ERROR_REGEX)
+                    .get_html(source_url, &HTML_ERROR_REGEX)
                     .await
                     .with_context(|| format!("Failed to download schedule HTML from: {:?}", source_url))?;
-                scrape_schedule(options, &source_url, &document, terminal_pair, index, today).await?
-            };
-            opt_schedule.iter().for_each(|s| debug!("Parsed schedule: {:#?}", s));
-            schedules.extend(opt_schedule);
+                Ok((source_url.clone(), document)) as Result<_>
+            })
+            .buffered(options.parallelism.get())
+            .try_collect()
+            .await?;
+        if !source_urls.is_empty() {
+            pages.insert(0, (base_url.clone(), base_document));
         }
-        ensure!(!schedules.is_empty(), "Failed to find any schedule elements");
-        Ok(schedules) as Result<_>
+        parse_route_schedules(terminal_pair, &pages, today, options.date)
     };
     inner.await.with_context(|| format!("Failed to scrape route schedule for {} from: {:?}", terminal_pair, base_url))
 }
 
-pub async fn scrape_schedules(options: &Options, cache: &Cache<'_>) -> Result<Vec<Schedule>> {
-    let inner = async {
-        let today = today_vancouver();
-        let mut result = Vec::new();
-        for &terminal_pair in ALL_TERMINAL_PAIRS.iter() {
-            result.extend(scrape_route_schedules(options, cache, terminal_pair, today).await?);
+/// Scrape every route's schedules, with the result for each route separate so that one failing doesn't affect others
+pub async fn scrape_schedules(options: &Options, cache: &Cache) -> Vec<(TerminalPair, Result<Vec<Schedule>>)> {
+    let today = today_vancouver();
+    // Routes are scraped concurrently, but their results are kept in route order
+    stream::iter(ALL_TERMINAL_PAIRS.iter().sorted())
+        .map(|&terminal_pair| async move {
+            (terminal_pair, scrape_route_schedules(options, cache, terminal_pair, today).await)
+        })
+        .buffered(options.parallelism.get())
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrysched_shared::date_set::DateSet;
+    use ferrysched_shared::validation::*;
+
+    fn parse_fixture(code_pair: &str) -> Result<(DateRange, Vec<ScheduleItem>)> {
+        let path = format!("{}/fixtures/schedules/{}/0.html", env!("CARGO_MANIFEST_DIR"), code_pair);
+        let document = Html::parse_document(&fs::read_to_string(&path)?);
+        let date_range = parse_schedule_date_range(&document)?;
+        let table_elem = document
+            .select(selector!("div.seasonal-schedule-wrapper table"))
+            .next()
+            .context("Missing table element in fixture")?;
+        let (items, _) = parse_table(table_elem, &date_range)?;
+        Ok((date_range, items))
+    }
+
+    fn find_item(items: &[ScheduleItem], depart_time: Time) -> Option<&ScheduleItem> {
+        items.iter().find(|i| i.sailing.depart_time == depart_time)
+    }
+
+    #[test]
+    fn test_parse_route_fixtures() -> Result<()> {
+        for code_pair in ["HSB-NAN", "HSB-LNG", "TSA-DUK", "CMX-PWR", "CAM-QDR", "HRB-COR", "NAH-DES"] {
+            let terminal_pair = TerminalPair::parse_schedule_code_pair(code_pair)?;
+            assert!(ALL_TERMINAL_PAIRS.contains(&terminal_pair));
+            let (date_range, items) = parse_fixture(code_pair)?;
+            assert_eq!(date_range, DateRange { from: date!(2026 - 06 - 25), to: date!(2026 - 09 - 08) });
+            assert!(!items.is_empty(), "Expect sailings for {}", terminal_pair);
+            assert!(items.iter().all(|i| i.sailing.stops.is_empty()));
+            let schedule = Schedule {
+                terminal_pair,
+                date_range,
+                items,
+                source_url: String::new(),
+                refreshed_at: OffsetDateTime::UNIX_EPOCH,
+                alerts: vec![],
+                notes_to_review: vec![],
+            };
+            let findings = validate_schedules(&[schedule]);
+            assert!(
+                findings.iter().all(|f| f.severity() != Severity::Error),
+                "Expect valid {}: {:?}",
+                terminal_pair,
+                findings
+            );
         }
-        Ok(result) as Result<_>
-    };
-    inner.await.context("Failed to scrape schedules")
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_route_fixture_variants() -> Result<()> {
+        // Five cell rows with an annotation
+        let (_, items) = parse_fixture("HSB-NAN")?;
+        let item = find_item(&items, time!(15:10)).context("Missing 3:10 pm sailing")?;
+        assert_eq!(item.sailing.arrive_time, time!(16:50));
+        assert_eq!(item.sailing.vessel.as_deref(), Some("Queen of Oak Bay"));
+        assert_eq!(
+            item.weekdays.get(&Weekday::Wednesday),
+            Some(&DateRestriction::Except(DateSet::from_iter([date!(2026 - 07 - 01)])))
+        );
+        // Six cell rows with the duration in place of the arrive time
+        let (_, items) = parse_fixture("HSB-LNG")?;
+        let item = find_item(&items, time!(07:20)).context("Missing 7:20 am sailing")?;
+        assert_eq!(item.sailing.arrive_time, time!(08:00));
+        assert_eq!(item.weekdays.len(), 7);
+        // Three cell rows, with a sailing that doesn't run every day
+        let (_, items) = parse_fixture("NAH-DES")?;
+        let item = find_item(&items, time!(07:20)).context("Missing 7:20 am sailing")?;
+        assert_eq!(item.weekdays.len(), 5);
+        assert_eq!(item.sailing.vessel, None);
+        assert!(item.notes.contains_key("Foot passengers only"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_row_layout_from_header() -> Result<()> {
+        let header_texts = |texts: &[&str]| texts.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            TableRowLayout::from_header_texts(&header_texts(&["", "Depart", "Arrive", "Duration", "Stops", "Vessel"]))?,
+            TableRowLayout { depart: 1, arrive: 2, stops: Some(4), vessel: Some(5), cell_count: 6 }
+        );
+        assert_eq!(
+            TableRowLayout::from_header_texts(&header_texts(&["Depart", "Arrive", "Details"]))?,
+            TableRowLayout { depart: 0, arrive: 1, stops: None, vessel: None, cell_count: 3 }
+        );
+        assert!(TableRowLayout::from_header_texts(&header_texts(&["", "Arrive", "Duration", "Vessel"])).is_err());
+        assert!(TableRowLayout::from_header_texts(&header_texts(&["", "Depart", "Arrive", "Fare"])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_table_without_header() -> Result<()> {
+        // Laid out the way the schedule tables were before they had a column header row
+        let document = Html::parse_document(
+            r#"<table>
+                <thead><tr data-schedule-day="MONDAY"><th colspan="6">Monday</th></tr></thead>
+                <tbody><tr class="schedule-table-row">
+                    <td></td><td>7:00 am</td><td>8:35 am</td><td>1h 35m</td><td>Non-stop</td><td></td>
+                </tr></tbody>
+                <thead><tr data-schedule-day="TUESDAY"><th colspan="6">Tuesday</th></tr></thead>
+                <tbody><tr class="schedule-table-row">
+                    <td></td><td>7:00 am</td><td>9:35 am</td><td>2h 35m</td>
+                    <td><p>Stop</p><p>Galiano Island (Sturdies Bay)</p></td><td></td>
+                </tr></tbody>
+            </table>"#,
+        );
+        let table_elem = document.select(selector!("table")).next().context("Missing table element")?;
+        assert_eq!(TableRowLayout::from_table(table_elem)?, TableRowLayout::WITHOUT_HEADER);
+        let date_range = DateRange { from: date!(2026 - 06 - 01), to: date!(2026 - 06 - 30) };
+        let (items, _) = parse_table(table_elem, &date_range)?;
+        assert_eq!(
+            items.iter().map(|i| (i.sailing.arrive_time, i.sailing.stops.len())).collect::<Vec<_>>(),
+            vec![(time!(08:35), 0), (time!(09:35), 1)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_vessel_column_from_header() -> Result<()> {
+        let document = Html::parse_document(
+            r#"<table>
+                <thead><tr><th></th><th>Depart</th><th>Arrive</th><th>Vessel</th><th>Stops</th></tr></thead>
+                <thead><tr data-schedule-day="MONDAY"><th colspan="5">Monday</th></tr></thead>
+                <tbody><tr class="schedule-table-row">
+                    <td></td><td>7:00 am</td><td>8:35 am</td><td>Spirit of Vancouver Island</td><td>Non-stop</td>
+                </tr></tbody>
+            </table>"#,
+        );
+        let table_elem = document.select(selector!("table")).next().context("Missing table element")?;
+        let date_range = DateRange { from: date!(2026 - 06 - 01), to: date!(2026 - 06 - 30) };
+        let (items, _) = parse_table(table_elem, &date_range)?;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].sailing.vessel.as_deref(), Some("Spirit of Vancouver Island"));
+        assert!(items[0].sailing.stops.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_stops_with_times() -> Result<()> {
+        let texts = ["Stop", "Galiano Island (Sturdies Bay)", "Arrive 8:05 am", "Depart 8:15 am", "Transfer", "Mayne"];
+        let stops = parse_stops(texts.iter().map(|t| t.to_string()).collect())?;
+        assert_eq!(
+            stops,
+            vec![
+                Stop {
+                    type_: StopType::Stop,
+                    terminal: Terminal::new("PSB"),
+                    arrive_time: Some(time!(08:05)),
+                    depart_time: Some(time!(08:15)),
+                },
+                Stop::new(StopType::Transfer, Terminal::new("PVB")),
+            ]
+        );
+        Ok(())
+    }
 }
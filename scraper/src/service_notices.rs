@@ -0,0 +1,401 @@
+use crate::cache::*;
+use crate::constants::*;
+use crate::imports::*;
+use crate::macros::*;
+use crate::utils::*;
+use ferrysched_shared::date_set::*;
+
+const SERVICE_NOTICE_ALERT_PREFIX: &str = "Service notice: ";
+
+/// Sailings that a notice cancels, such as "the 3:15 pm from Swartz Bay is cancelled on Oct 20"
+#[derive(Clone, Debug)]
+pub struct SailingCancellation {
+    pub depart_times: Vec<Time>,
+    /// Terminal the sailings depart from, if the notice says
+    pub from: Option<Terminal>,
+    pub dates: DateSet,
+}
+
+/// A notice from the service notices page, classified by the routes and dates it applies to
+#[derive(Clone, Debug)]
+pub struct ServiceNotice {
+    pub terminal_pairs: HashSet<TerminalPair>,
+    /// `None` if the notice has no dates, which means it applies until it is taken down
+    pub date_range: Option<DateRange>,
+    pub level: AlertLevel,
+    /// Title and body of the notice
+    pub text: String,
+    pub cancellations: Vec<SailingCancellation>,
+}
+
+impl ServiceNotice {
+    fn alert(&self) -> Alert {
+        Alert {
+            message: format!("{}{}", SERVICE_NOTICE_ALERT_PREFIX, self.text),
+            level: self.level.clone(),
+            date_range: self.date_range,
+        }
+    }
+
+    fn applies_to(&self, schedule: &Schedule) -> bool {
+        self.terminal_pairs.contains(&schedule.terminal_pair)
+            && self
+                .date_range
+                .map(|r| r.from <= schedule.date_range.to && r.to >= schedule.date_range.from)
+                .unwrap_or(true)
+    }
+}
+
+// Notices name routes by number (e.g. "Route 1 - Tsawwassen/Swartz Bay"), or else by their terminals
+fn classify_terminal_pairs(title: &str) -> HashSet<TerminalPair> {
+    let registry = Registry::get();
+    let route_numbers: Vec<u32> = regex!(r"(?i)\broutes?\s+(\d+(?:(?:,\s*|,?\s+(?:and|&)\s+)\d+)*)")
+        .captures_iter(title)
+        .flat_map(|captures| {
+            let numbers_text = captures[1].to_string();
+            regex!(r"\d+").find_iter(&numbers_text).filter_map(|m| m.as_str().parse().ok()).collect::<Vec<_>>()
+        })
+        .collect();
+    if !route_numbers.is_empty() {
+        return route_numbers
+            .into_iter()
+            .filter_map(|number| registry.route(number))
+            .flat_map(|route| Terminal::combinations(&route.terminals))
+            .collect();
+    }
+    let lowercase_title = title.to_lowercase();
+    let terminals: HashSet<_> = registry
+        .terminals
+        .iter()
+        .filter(|t| lowercase_title.contains(&t.name.to_lowercase()))
+        .map(|t| t.terminal)
+        .collect();
+    ALL_TERMINAL_PAIRS
+        .iter()
+        .copied()
+        .filter(|tp| match terminals.len() {
+            0 => false,
+            1 => terminals.contains(&tp.from) || terminals.contains(&tp.to),
+            _ => terminals.contains(&tp.from) && terminals.contains(&tp.to),
+        })
+        .collect()
+}
+
+fn classify_level(text: &str) -> AlertLevel {
+    if regex!(r"(?i)\b(cancel(led|lation|lations|s)?|suspended|out of service)\b").is_match(text) {
+        AlertLevel::Danger
+    } else if regex!(r"(?i)\b(delay(ed|s)?|reduced|modified|changed?|late|disruption|substitut(e|ed|ion))\b")
+        .is_match(text)
+    {
+        AlertLevel::Warning
+    } else {
+        AlertLevel::Info
+    }
+}
+
+// Reads either a single date such as "Oct 24, 2026" or a range such as "Oct 24, 2026 - Oct 25, 2026"
+fn parse_notice_dates(text: &str) -> Result<DateRange> {
+    const DATE_FORMAT: &TimeFormat =
+        format_description!("[month repr:short case_sensitive:false] [day padding:none], [year]");
+    if text.contains(" - ") {
+        DateRange::parse(text, DATE_FORMAT, " - ")
+    } else {
+        let date =
+            Date::parse(text, DATE_FORMAT).with_context(|| format!("Failed to parse notice date: {:?}", text))?;
+        Ok(DateRange { from: date, to: date })
+    }
+}
+
+// Reads the sentences of a notice that say sailings at particular times are cancelled, such as "The 7:00 am and 9:00
+// am sailings from Tsawwassen are cancelled". Sailings are cancelled on the dates the sentence names, or else on every
+// date of the notice. Other sentences that mention cancelling (e.g. "the 10:40 am will not be cancelled") are only
+// shown in the notice's alert.
+fn parse_cancellations(body: &str, date_range: Option<DateRange>, today: Date) -> Vec<SailingCancellation> {
+    let registry = Registry::get();
+    // Times such as "3:15 p.m." would otherwise end a sentence
+    let body = regex!(r"(?i)\b(\d{1,2}:\d{2})\s*([ap])\.?m\b\.?").replace_all(body, "$1 ${2}m");
+    let within_range = date_range.unwrap_or(DateRange { from: today, to: today + Duration::days(365) });
+    let mut cancellations = Vec::new();
+    for sentence in regex!(r"[.!?](\s+|$)").split(&body).filter(|s| regex!(r"(?i)\bcancel").is_match(s)) {
+        // The times are followed by what they are (and perhaps where from), but no other times, then the verb
+        let opt_captures = regex!(
+            r"(?ix)
+            \b(\d{1,2}:\d{2}\ [ap]m (?: (?:,\s*|,?\s+and\s+) \d{1,2}:\d{2}\ [ap]m )*)\b
+            [^:]*?
+            \b(?:is|are|has\ been|have\ been|will\ be) \s+ cancell?ed\b"
+        )
+        .captures(sentence);
+        let captures = match opt_captures {
+            Some(captures) => captures,
+            None => {
+                if regex!(r"\d{1,2}:\d{2}").is_match(sentence) {
+                    info!("Not applying unclear cancellation in service notice: {:?}", sentence);
+                }
+                continue;
+            }
+        };
+        let depart_times: Vec<_> = regex!(r"(?i)\b\d{1,2}:\d{2} [ap]m\b")
+            .find_iter(&captures[1])
+            .filter_map(|m| parse_schedule_time(m.as_str()).ok())
+            .collect();
+        let lowercase_sentence = sentence.to_lowercase();
+        let from = registry
+            .terminals
+            .iter()
+            .find(|t| lowercase_sentence.contains(&format!("from {}", t.name.to_lowercase())))
+            .map(|t| t.terminal);
+        let named_dates: Vec<_> =
+            regex!(r"(?i)\b(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.? (\d{1,2})\b")
+                .captures_iter(sentence)
+                .filter_map(|captures| {
+                    within_range.parse_date_within(&format!("{} {}", &captures[1], &captures[2])).ok().flatten()
+                })
+                .collect();
+        let dates: DateSet = match (named_dates.is_empty(), date_range) {
+            (false, _) => named_dates.into_iter().collect(),
+            (true, Some(date_range)) => date_range.iter_days().collect(),
+            (true, None) => continue,
+        };
+        cancellations.push(SailingCancellation { depart_times, from, dates });
+    }
+    cancellations
+}
+
+fn parse_service_notice(notice_elem: ElementRef, today: Date) -> Result<ServiceNotice> {
+    let title_elem = notice_elem.select(selector!(".service-notice-title")).next().context("Missing notice title")?;
+    let title = element_text(&title_elem);
+    let inner = || {
+        let date_range = match notice_elem.select(selector!(".service-notice-dates")).next() {
+            Some(dates_elem) => Some(parse_notice_dates(&element_text(&dates_elem))?),
+            None => None,
+        };
+        let body = notice_elem.select(selector!(".service-notice-body")).next().map(|e| element_text(&e));
+        let body = body.unwrap_or_default();
+        let text = if body.is_empty() { title.clone() } else { format!("{}. {}", title, body) };
+        Ok(ServiceNotice {
+            terminal_pairs: classify_terminal_pairs(&title),
+            date_range,
+            level: classify_level(&text),
+            cancellations: parse_cancellations(&body, date_range, today),
+            text,
+        }) as Result<_>
+    };
+    inner().with_context(|| format!("Failed to parse service notice: {:?}", title))
+}
+
+// Notices that have already ended, or that aren't for any route, are left out
+fn parse_service_notices_document(document: &Html, today: Date) -> Result<Vec<ServiceNotice>> {
+    // Without the container, a changed page would look the same as there being no notices
+    let container_elem =
+        document.select(selector!("div.service-notices")).next().context("Missing service notices container")?;
+    let mut notices = Vec::new();
+    for notice_elem in container_elem.select(selector!("article.service-notice")) {
+        match parse_service_notice(notice_elem, today) {
+            Ok(notice) if notice.terminal_pairs.is_empty() => {
+                debug!("Ignoring service notice that isn't for any route: {:?}", notice.text);
+            }
+            Ok(notice) if notice.date_range.map(|r| r.to < today).unwrap_or(false) => {
+                debug!("Ignoring service notice that has ended: {:?}", notice.text);
+            }
+            Ok(notice) => notices.push(notice),
+            Err(err) => warn!("{:?}", err),
+        }
+    }
+    Ok(notices)
+}
+
+pub async fn scrape_service_notices(cache: &Cache, today: Date) -> Result<Vec<ServiceNotice>> {
+    let inner = async {
+        let document = cache.get_html(ALL_SERVICE_NOTICES_URL, &HTML_ERROR_REGEX).await?;
+        info!("Parsing service notices");
+        parse_service_notices_document(&document, today)
+    };
+    inner.await.with_context(|| format!("Failed to scrape service notices from: {:?}", ALL_SERVICE_NOTICES_URL))
+}
+
+// Returns the number of sailings cancelled
+fn apply_cancellations(schedule: &mut Schedule, notice: &ServiceNotice) -> Result<usize> {
+    let mut cancelled_count = 0;
+    for cancellation in &notice.cancellations {
+        if cancellation.from.map(|from| from != schedule.terminal_pair.from).unwrap_or(false) {
+            continue;
+        }
+        let dates: DateSet =
+            cancellation.dates.iter().filter(|d| schedule.date_range.includes_date_inclusive(*d)).collect();
+        for item in &mut schedule.items {
+            if cancellation.depart_times.contains(&item.sailing.depart_time) && item.cancel(&notice.text, &dates)? {
+                cancelled_count += 1;
+            }
+        }
+    }
+    Ok(cancelled_count)
+}
+
+/// Add an alert for each notice to the schedules it applies to, and cancel the sailings the notices cancel. The
+/// alerts and cancellations from an earlier run (such as on last known good schedules) are replaced, so that those for
+/// a notice that has been taken down go away.
+pub fn attach_service_notices(schedules: &mut [Schedule], notices: &[ServiceNotice]) {
+    for schedule in schedules {
+        schedule.alerts.retain(|alert| !alert.message.starts_with(SERVICE_NOTICE_ALERT_PREFIX));
+        schedule.items.iter_mut().for_each(|item| item.uncancel_all());
+        let schedule_notices: Vec<_> = notices.iter().filter(|n| n.applies_to(schedule)).collect();
+        for notice in schedule_notices {
+            schedule.alerts.push(notice.alert());
+            match apply_cancellations(schedule, notice) {
+                Ok(0) => {}
+                Ok(count) => info!("Cancelled {} sailing(s) for {}: {:?}", count, schedule.terminal_pair, notice.text),
+                Err(err) => warn!("{:?}", err.context(format!("Failed to apply cancellations: {:?}", notice.text))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrysched_shared::test_support::*;
+
+    fn fixture_notices() -> Result<Vec<ServiceNotice>> {
+        let path = format!("{}/fixtures/service-notices/all.html", env!("CARGO_MANIFEST_DIR"));
+        let document = Html::parse_document(&fs::read_to_string(&path)?);
+        parse_service_notices_document(&document, date!(2026 - 10 - 17))
+    }
+
+    fn test_schedule(from: Terminal, to: Terminal, date_range: DateRange, depart_times: &[Time]) -> Schedule {
+        ScheduleBuilder::new(TerminalPair { from, to })
+            .date_range(date_range)
+            .items(depart_times.iter().map(|&t| ScheduleItemBuilder::new(t, t + Duration::minutes(95)).build()))
+            .build()
+    }
+
+    #[test]
+    fn test_parse_service_notices_fixture() -> Result<()> {
+        let notices = fixture_notices()?;
+        assert_eq!(notices.len(), 4);
+        let fall = DateRange { from: date!(2026 - 10 - 13), to: date!(2027 - 01 - 05) };
+        let winter = DateRange { from: date!(2027 - 01 - 06), to: date!(2027 - 03 - 31) };
+        let mut schedules = vec![
+            test_schedule(Terminal::new("TSA"), Terminal::new("SWB"), fall, &[]),
+            test_schedule(Terminal::new("NAN"), Terminal::new("HSB"), fall, &[]),
+            test_schedule(Terminal::new("TSA"), Terminal::new("PSB"), fall, &[]),
+            test_schedule(Terminal::new("TSA"), Terminal::new("DUK"), fall, &[]),
+            test_schedule(Terminal::new("SWB"), Terminal::new("TSA"), winter, &[]),
+        ];
+        attach_service_notices(&mut schedules, &notices);
+        attach_service_notices(&mut schedules, &notices);
+        let alerts: Vec<Vec<_>> = schedules
+            .iter()
+            .map(|s| s.alerts.iter().map(|a| (a.level.clone(), a.date_range.map(|r| (r.from, r.to)))).collect())
+            .collect();
+        assert_eq!(
+            alerts,
+            vec![
+                vec![(AlertLevel::Danger, Some((date!(2026 - 10 - 24), date!(2026 - 10 - 25))))],
+                vec![(AlertLevel::Info, None)],
+                vec![(AlertLevel::Warning, Some((date!(2026 - 10 - 20), date!(2026 - 10 - 20))))],
+                vec![],
+                vec![],
+            ]
+        );
+        assert!(schedules[0].alerts[0].applies_on(date!(2026 - 10 - 25)));
+        assert!(!schedules[0].alerts[0].applies_on(date!(2026 - 10 - 26)));
+        assert_eq!(
+            schedules[0].alerts[0].message,
+            "Service notice: Route 1 - Tsawwassen/Swartz Bay: Cancelled sailings due to vessel maintenance. The 7:00 am \
+             and 9:00 am sailings from Tsawwassen are cancelled."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cancellations() -> Result<()> {
+        let date_range = Some(DateRange { from: date!(2026 - 10 - 20), to: date!(2026 - 10 - 20) });
+        let depart_times = |body: &str| {
+            parse_cancellations(body, date_range, date!(2026 - 10 - 17))
+                .into_iter()
+                .flat_map(|c| c.depart_times)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            depart_times("The 10:40 am, 12:50 pm and 3:00 p.m. sailings from Nanaimo have been cancelled."),
+            vec![time!(10:40), time!(12:50), time!(15:00)]
+        );
+        assert_eq!(depart_times("The 10:40 am sailing will not be cancelled."), vec![]);
+        assert_eq!(depart_times("See our cancellation policy before booking the 10:40 am sailing."), vec![]);
+        assert_eq!(depart_times("The 10:40 am is delayed, and the 12:50 pm is cancelled."), vec![time!(12:50)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_cancellations() -> Result<()> {
+        let notices = fixture_notices()?;
+        let fall = DateRange { from: date!(2026 - 10 - 13), to: date!(2027 - 01 - 05) };
+        let mut schedules = vec![
+            test_schedule(
+                Terminal::new("TSA"),
+                Terminal::new("SWB"),
+                fall,
+                &[time!(07:00), time!(09:00), time!(11:00)],
+            ),
+            test_schedule(Terminal::new("SWB"), Terminal::new("TSA"), fall, &[time!(07:00), time!(09:00)]),
+            test_schedule(Terminal::new("SWB"), Terminal::new("FUL"), fall, &[time!(15:15), time!(17:00)]),
+            test_schedule(Terminal::new("FUL"), Terminal::new("SWB"), fall, &[time!(15:15)]),
+        ];
+        attach_service_notices(&mut schedules, &notices);
+        let cancelled_dates: Vec<Vec<_>> = schedules
+            .iter()
+            .map(|s| {
+                s.items
+                    .iter()
+                    .flat_map(|i| fall.iter_days().filter(|d| !i.includes_date(*d)).map(|d| (i.sailing.depart_time, d)))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            cancelled_dates,
+            vec![
+                vec![
+                    (time!(07:00), date!(2026 - 10 - 24)),
+                    (time!(07:00), date!(2026 - 10 - 25)),
+                    (time!(09:00), date!(2026 - 10 - 24)),
+                    (time!(09:00), date!(2026 - 10 - 25)),
+                ],
+                vec![],
+                vec![(time!(15:15), date!(2026 - 10 - 20))],
+                vec![],
+            ]
+        );
+        assert_eq!(
+            schedules[2].items[0].cancellations_for_date(date!(2026 - 10 - 20)).collect::<Vec<_>>(),
+            vec![
+                "Route 4 - Fulford Harbour/Swartz Bay: Sailing cancellation. Due to crew availability, the 3:15 p.m. from \
+                 Swartz Bay is cancelled on Oct 20. We apologize for any inconvenience."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_withdrawn_notice_cancellations() -> Result<()> {
+        let notices = fixture_notices()?;
+        let fall = DateRange { from: date!(2026 - 10 - 13), to: date!(2027 - 01 - 05) };
+        let mut schedules = vec![test_schedule(Terminal::new("SWB"), Terminal::new("FUL"), fall, &[time!(15:15)])];
+        attach_service_notices(&mut schedules, &notices);
+        assert!(!schedules[0].items[0].includes_date(date!(2026 - 10 - 20)));
+        // The next run (e.g. falling back to the published schedules) no longer finds the notice
+        let schedules_json = serde_json::to_string(&schedules)?;
+        let mut schedules: Vec<Schedule> = serde_json::from_str(&schedules_json)?;
+        attach_service_notices(&mut schedules, &[]);
+        let item = &schedules[0].items[0];
+        assert!(item.cancellations.is_empty() && schedules[0].alerts.is_empty());
+        assert!(item.weekdays.values().all(|dr| *dr == DateRestriction::All));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_service_notices_container() -> Result<()> {
+        let document = Html::parse_document("<html><body><p>We're updating our site</p></body></html>");
+        assert!(parse_service_notices_document(&document, date!(2026 - 10 - 17)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::imports::*;
+
+// A sailing in a schedule that may cover the same trip as a sailing in another schedule
+struct SailingRef<'a> {
+    date_range: &'a DateRange,
+    item: &'a ScheduleItem,
+}
+
+fn stop_terminals(stops: &[Stop]) -> Vec<Terminal> {
+    stops.iter().map(|s| s.terminal).collect()
+}
+
+fn date_ranges_overlap(a: &DateRange, b: &DateRange) -> bool {
+    a.from <= b.to && b.from <= a.to
+}
+
+fn shares_weekday(a: &ScheduleItem, b: &ScheduleItem) -> bool {
+    a.weekdays.keys().any(|w| b.weekdays.contains_key(w))
+}
+
+// Finds a sailing in a sibling schedule that is the same trip as `item`, using `is_same_trip` to compare sailings
+fn find_same_trip<'a>(
+    index: &HashMap<TerminalPair, Vec<SailingRef<'a>>>,
+    terminal_pair: TerminalPair,
+    date_range: &DateRange,
+    item: &ScheduleItem,
+    is_same_trip: impl Fn(&Sailing) -> bool,
+) -> Option<&'a Sailing> {
+    index.get(&terminal_pair)?.iter().find_map(|r| {
+        (date_ranges_overlap(r.date_range, date_range) && shares_weekday(r.item, item) && is_same_trip(&r.item.sailing))
+            .then_some(&r.item.sailing)
+    })
+}
+
+/// Fill in the arrive and depart times at intermediate stops that the schedule pages don't publish, by finding the
+/// same trip in the schedules between the origin and the stop (for the arrive time) and between the stop and the
+/// destination (for the depart time)
+pub fn fill_stop_times(schedules: &mut [Schedule]) {
+    let mut filled = Vec::new();
+    {
+        let mut index: HashMap<TerminalPair, Vec<SailingRef>> = HashMap::new();
+        for schedule in schedules.iter() {
+            for item in &schedule.items {
+                index
+                    .entry(schedule.terminal_pair)
+                    .or_default()
+                    .push(SailingRef { date_range: &schedule.date_range, item });
+            }
+        }
+        for (schedule_index, schedule) in schedules.iter().enumerate() {
+            let TerminalPair { from, to } = schedule.terminal_pair;
+            for (item_index, item) in schedule.items.iter().enumerate() {
+                let sailing = &item.sailing;
+                for (stop_index, stop) in sailing.stops.iter().enumerate() {
+                    let arrive_time = stop.arrive_time.or_else(|| {
+                        let stops_before = stop_terminals(&sailing.stops[..stop_index]);
+                        let terminal_pair = TerminalPair { from, to: stop.terminal };
+                        find_same_trip(&index, terminal_pair, &schedule.date_range, item, |s| {
+                            s.depart_service_time() == sailing.depart_service_time()
+                                && stop_terminals(&s.stops) == stops_before
+                        })
+                        .map(|s| s.arrive_time)
+                    });
+                    let depart_time = stop.depart_time.or_else(|| {
+                        let stops_after = stop_terminals(&sailing.stops[stop_index + 1..]);
+                        let terminal_pair = TerminalPair { from: stop.terminal, to };
+                        find_same_trip(&index, terminal_pair, &schedule.date_range, item, |s| {
+                            s.arrive_time == sailing.arrive_time && stop_terminals(&s.stops) == stops_after
+                        })
+                        .map(|s| s.depart_time)
+                    });
+                    if arrive_time != stop.arrive_time || depart_time != stop.depart_time {
+                        filled.push((schedule_index, item_index, stop_index, arrive_time, depart_time));
+                    }
+                }
+            }
+        }
+    }
+    for (schedule_index, item_index, stop_index, arrive_time, depart_time) in filled {
+        let stop = &mut schedules[schedule_index].items[item_index].sailing.stops[stop_index];
+        stop.arrive_time = arrive_time;
+        stop.depart_time = depart_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrysched_shared::test_support::*;
+
+    fn schedule(from: &str, to: &str, item: ScheduleItem) -> Schedule {
+        ScheduleBuilder::new(TerminalPair { from: Terminal::new(from), to: Terminal::new(to) }).item(item).build()
+    }
+
+    #[test]
+    fn test_fill_stop_times() -> Result<()> {
+        let mut schedules = vec![
+            schedule(
+                "TSA",
+                "PSB",
+                ScheduleItemBuilder::new(time!(10:20), time!(13:05))
+                    .stops(vec![Stop::new(StopType::Stop, Terminal::new("PLH"))])
+                    .build(),
+            ),
+            schedule("TSA", "PLH", ScheduleItemBuilder::new(time!(10:20), time!(12:00)).build()),
+            schedule("PLH", "PSB", ScheduleItemBuilder::new(time!(12:25), time!(13:05)).build()),
+        ];
+        fill_stop_times(&mut schedules);
+        let stop = &schedules[0].items[0].sailing.stops[0];
+        assert_eq!(stop.arrive_time, Some(time!(12:00)));
+        assert_eq!(stop.depart_time, Some(time!(12:25)));
+        Ok(())
+    }
+}
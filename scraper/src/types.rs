@@ -1,13 +1,35 @@
-use clap::Args;
+use clap::{Args, Subcommand};
+use std::num::NonZeroUsize;
 
 use crate::imports::*;
 
 #[derive(Args, Debug)]
 pub struct Options {
-    /// Maximum time to cache source schedule HTML
+    /// Maximum time to use cached source HTML before asking the site whether it changed
     #[clap(short = 'a', long, value_name = "HOURS", default_value = "12")]
     pub max_cache_age: i64,
 
+    /// Maximum number of pages to download at once
+    #[clap(short = 'p', long, value_name = "COUNT", default_value = "4")]
+    pub parallelism: NonZeroUsize,
+
+    /// Minimum time between starting downloads from the same host
+    #[clap(long, value_name = "MILLISECONDS", default_value = "250")]
+    pub min_request_interval: u64,
+
+    /// Don't use the network; read pages only from the cache, however old they are
+    #[clap(long, conflicts_with_all = &["replay-dir", "record-dir"])]
+    pub offline: bool,
+
+    /// Don't use the network; read pages only from this directory, recorded by an earlier run with '--record-dir'
+    /// (or copied from its cache directory)
+    #[clap(long, value_name = "PATH", conflicts_with = "record-dir")]
+    pub replay_dir: Option<PathBuf>,
+
+    /// Save every page fetched to this directory (bypassing the cache), so that the run can be replayed exactly
+    #[clap(long, value_name = "PATH")]
+    pub record_dir: Option<PathBuf>,
+
     /// Only process schedules for specified terminal pair
     #[clap(short, long, value_name = "FROM-TO")]
     pub terminals: Option<TerminalPair>,
@@ -31,4 +53,105 @@ pub struct Options {
     /// After uploading schedules JSON, invalidate this CloudFront distribution
     #[clap(short = 'c', long, value_name = "DISTRIBUTION ID")]
     pub invalidate_cloudfront_distribution_id: Option<String>,
+
+    /// Also write a GTFS static feed to a 'gtfs' directory alongside the schedules JSON
+    #[clap(short = 'g', long)]
+    pub gtfs: bool,
+
+    /// Also write iCalendar subscription files for each route to an 'ical' directory alongside the schedules JSON
+    #[clap(short = 'i', long)]
+    pub ical: bool,
+
+    /// Also scrape fares and write them to 'fares.json' alongside the schedules JSON
+    #[clap(short = 'f', long)]
+    pub fares: bool,
+
+    /// Use this route and terminal registry JSON file instead of the embedded one
+    #[clap(short = 'r', long = "registry", value_name = "PATH")]
+    pub registry_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Compare two schedules JSON files and report what changed
+    Diff(DiffOptions),
+    /// Record a route's cached schedule pages as a parser regression fixture, along with how they parse now
+    RecordFixture(RecordFixtureOptions),
+    /// Inspect or clean up the cache of downloaded pages
+    Cache(CacheOptions),
+    /// Scrape the current departures pages and write the live status of today's sailings to 'live.json' alongside
+    /// the schedules JSON
+    Live(LiveOptions),
+}
+
+#[derive(Args, Debug)]
+pub struct DiffOptions {
+    /// Previous schedules JSON file
+    #[clap(value_name = "OLD")]
+    pub old_file: PathBuf,
+
+    /// Updated schedules JSON file
+    #[clap(value_name = "NEW")]
+    pub new_file: PathBuf,
+
+    /// Write changes as JSON instead of human-readable text
+    #[clap(short, long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RecordFixtureOptions {
+    /// Route whose schedule pages to record, from the cache (or the directory given by '--replay-dir')
+    #[clap(value_name = "FROM-TO")]
+    pub terminal_pair: TerminalPair,
+
+    /// Name of the fixture directory [default: route and today's date]
+    #[clap(short, long, value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Directory to write the fixture to [default: the scraper's 'fixtures/schedules' directory]
+    #[clap(short, long, value_name = "PATH")]
+    pub fixtures_dir: Option<PathBuf>,
+
+    /// Re-parse an existing fixture's pages and rewrite its expected schedules (e.g. after fixing the parser for them)
+    #[clap(short, long)]
+    pub update: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CacheOptions {
+    #[clap(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// List the cached pages, with when each was last checked and last changed
+    List,
+    /// Remove pages that haven't been checked recently, and files that aren't in the cache index
+    Prune(PruneCacheOptions),
+    /// Remove all cached pages, but keep the last known good data used in case a route fails to scrape
+    Clear,
+}
+
+#[derive(Args, Debug)]
+pub struct PruneCacheOptions {
+    /// Remove pages not checked for this long
+    #[clap(long, value_name = "HOURS", default_value = "168")]
+    pub max_age: i64,
+}
+
+#[derive(Args, Debug)]
+pub struct LiveOptions {
+    /// Keep polling the departures pages, waiting this long between polls [default: poll once]
+    #[clap(long, value_name = "SECONDS")]
+    pub poll_interval: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct OutputFile {
+    /// Path relative to the directory containing the schedules JSON
+    pub path: String,
+    pub content_type: &'static str,
+    pub contents: Vec<u8>,
 }
@@ -1,6 +1,4 @@
 use scraper::ElementRef;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
 
 use crate::imports::*;
 use crate::macros::*;
@@ -13,12 +11,6 @@ pub fn element_texts(elem: &ElementRef) -> Vec<String> {
     elem.text().map(|s| regex!(r"\s+").replace_all(s, " ").trim().to_string()).filter(|s| !s.is_empty()).collect()
 }
 
-pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
-    let mut s = DefaultHasher::new();
-    t.hash(&mut s);
-    s.finish()
-}
-
 pub fn parse_schedule_time(text: &str) -> Result<Time> {
     const SCHEDULE_TIME_FORMATS: &[&TimeFormat] = &[
         format_description!("[hour repr:12 padding:none]:[minute] [period case:lower case_sensitive:false]"),
@@ -54,43 +46,68 @@ pub fn parse_duration(duration_text: &str) -> Result<Duration> {
     inner().with_context(|| format!("Failed to parse duration: {:?}", duration_text))
 }
 
-pub fn parse_arrive_time_or_duration(depart_time: Time, text: &str) -> Result<Time> {
-    parse_schedule_time(text)
-        .or_else(|time_err| parse_duration(text).map(|dur| depart_time + dur).context(time_err))
-        .with_context(|| format!("Failed to parse arrive time or duration: {:?}", text))
+/// Returns the arrive time, and the number of days after the depart time's day that it falls on
+pub fn parse_arrive_time_or_duration(depart_time: Time, text: &str) -> Result<(Time, u8)> {
+    let inner = || match parse_schedule_time(text) {
+        Ok(arrive_time) => Ok((arrive_time, (arrive_time < depart_time).into())),
+        Err(time_err) => {
+            let duration = parse_duration(text).context(time_err)?;
+            let arrive = (depart_time - Time::MIDNIGHT) + duration;
+            let day_offset = u8::try_from(arrive.whole_days()).context("Duration is too long")?;
+            Ok((depart_time + duration, day_offset)) as Result<_>
+        }
+    };
+    inner().with_context(|| format!("Failed to parse arrive time or duration: {:?}", text))
 }
 
 fn terminal_from_schedule_stop_text(stop_text: &str) -> Result<Terminal> {
-    let stop_text = stop_text.to_lowercase();
-    match &stop_text[..] {
-        "chemainus" => Ok(Terminal::CHM),
-        "galiano" | "galiano island (sturdies bay)" => Ok(Terminal::PSB),
-        "mayne" | "mayne island (village bay)" | "mayne island (village bay" | "mayne island {village bay)" => {
-            Ok(Terminal::PVB)
+    Registry::get()
+        .terminal_from_stop_name(stop_text)
+        .ok_or_else(|| anyhow!("Unknown schedule stop name: {:?}", stop_text.to_lowercase()))
+}
+
+// Splits times off the end of stop text such as "Stop at Mayne Island (Village Bay) Arrive 8:05 am Depart 8:10 am";
+// a time without a label is both the arrive and depart time
+fn split_stop_times(stop_text: &str) -> Result<(&str, Option<Time>, Option<Time>)> {
+    let mut stop_text = stop_text;
+    let mut arrive_time = None;
+    let mut depart_time = None;
+    while let Some(captures) =
+        regex!(r"(?i)^(.*?)\s+(?:(arrives?|arr\.?|departs?|dep\.?):?\s+)?(\d{1,2}:\d{2} [ap]m)$").captures(stop_text)
+    {
+        let time = parse_schedule_time(&captures[3])?;
+        match captures.get(2).map(|m| m.as_str().to_lowercase()) {
+            Some(label) if label.starts_with("arr") => arrive_time = Some(time),
+            Some(_) => depart_time = Some(time),
+            None => {
+                arrive_time = arrive_time.or(Some(time));
+                depart_time = depart_time.or(Some(time));
+            }
         }
-        "pender" | "pender island (otter bay)" => Ok(Terminal::POB),
-        "penelakut island (telegraph harbour)" | "enelakut island (telegraph harbour)" => Ok(Terminal::PEN),
-        "salt spring" | "salt spring island (long harbour)" => Ok(Terminal::PLH),
-        "saturna" | "saturna island (lyall harbour)" | "saturna island (lyall harbour" => Ok(Terminal::PST),
-        "thetis island (preedy harbour)" => Ok(Terminal::THT),
-        "swartz bay" | "victoria (swartz bay)" | "victoria (swartz bay" => Ok(Terminal::SWB),
-        _ => Err(anyhow!("Unknown schedule stop name: {:?}", stop_text)),
+        stop_text = captures.get(1).expect("Expect stop text capture").as_str();
     }
+    Ok((stop_text, arrive_time, depart_time))
+}
+
+pub fn is_stop_time_text(text: &str) -> bool {
+    regex!(r"(?i)^((arrives?|arr\.?|departs?|dep\.?):?\s+)?\d{1,2}:\d{2} [ap]m$").is_match(text)
 }
 
 fn parse_stop_schedule_text(stop_text: &str) -> Result<Stop> {
     let inner = || {
-        if let Some(captures) = regex!(r"(?i)^(Transfer )?transfer( at)? (.*)$").captures(stop_text) {
-            Ok(Stop { type_: StopType::Transfer, terminal: terminal_from_schedule_stop_text(&captures[3])? })
-                as Result<_>
-        } else if let Some(captures) = regex!(r"(?i)^thru fare( at)? (.*)$").captures(stop_text) {
-            Ok(Stop { type_: StopType::Thrufare, terminal: terminal_from_schedule_stop_text(&captures[2])? })
-                as Result<_>
-        } else {
-            let stop_text =
-                &regex!(r"(?i)^(Stop )?(stop( at)? )?(.*)$").captures(stop_text).expect("Expect stop text to match")[4];
-            Ok(Stop { type_: StopType::Stop, terminal: terminal_from_schedule_stop_text(stop_text)? })
-        }
+        let (stop_text, arrive_time, depart_time) = split_stop_times(stop_text)?;
+        let (type_, terminal_text) =
+            if let Some(captures) = regex!(r"(?i)^(Transfer )?transfer( at)? (.*)$").captures(stop_text) {
+                (StopType::Transfer, captures.get(3))
+            } else if let Some(captures) = regex!(r"(?i)^thru fare( at)? (.*)$").captures(stop_text) {
+                (StopType::Thrufare, captures.get(2))
+            } else {
+                let captures = regex!(r"(?i)^(Stop )?(stop( at)? )?(.*)$").captures(stop_text);
+                (StopType::Stop, captures.expect("Expect stop text to match").get(4))
+            };
+        let terminal_text = terminal_text.expect("Expect stop terminal capture").as_str();
+        let terminal = terminal_from_schedule_stop_text(terminal_text)?;
+        Ok(Stop { type_, terminal, arrive_time, depart_time }) as Result<_>
     };
     inner().with_context(|| format!("Failed to parse schedule stop: {:?}", stop_text))
 }
@@ -1,4 +1,5 @@
 use crate::imports::*;
+use crate::registry::*;
 use crate::types::*;
 use crate::utils::*;
 
@@ -18,31 +19,20 @@ pub const SWB_SGI_SERVICE_NOTICES_URL: &str =
     concatcp!(ALL_SERVICE_NOTICES_URL, "#Vancouver%20Island%20-%20Southern%20Gulf%20Islands");
 pub const TSA_SGI_SERVICE_NOTICES_URL: &str =
     concatcp!(ALL_SERVICE_NOTICES_URL, "#Metro%20Vancouver%20-%20Southern%20Gulf%20Islands");
+pub const FARES_BASE_URL: &str = concatcp!(BCFERRIES_BASE_URL, "/routes-fares/ferry-fares/departure-terminal");
 pub const THRU_FARE_INFORMATION_URL: &str = concatcp!(BCFERRIES_BASE_URL, "/routes-fares/ferry-fares/thru-fare");
 
-pub static ROUTE_5_AND_9_GULF_ISLAND_TERMINALS: Lazy<HashSet<Terminal>> =
-    Lazy::new(|| HashSet::from_iter([Terminal::PLH, Terminal::POB, Terminal::PSB, Terminal::PST, Terminal::PVB]));
-
-pub static ALL_TERMINAL_PAIRS: Lazy<HashSet<TerminalPair>> = Lazy::new(|| {
-    let routes = [
-        // Route 1 (Tsawwassen/Swartz Bay)
-        vec![Terminal::TSA, Terminal::SWB],
-        // Route 4 (Fulford Harbour/Swartz Bay)
-        vec![Terminal::FUL, Terminal::SWB],
-        // Route 5 (Swartz Bay/Southern Gulf Islands)
-        vec![Terminal::SWB, Terminal::PLH, Terminal::POB, Terminal::PSB, Terminal::PST, Terminal::PVB],
-        // Route 6 (Vesuvius/Crofton)
-        vec![Terminal::VES, Terminal::CFT],
-        // Route 9 (Tsawwassen/Southern Gulf Islands)
-        vec![Terminal::TSA, Terminal::PLH, Terminal::POB, Terminal::PSB, Terminal::PST, Terminal::PVB],
-        // Route 12 (Brentwood/Mill Bay)
-        vec![Terminal::BTW, Terminal::MIL],
-        // Route 20 (Chemainus/Thetis/Penelakut)
-        vec![Terminal::CHM, Terminal::THT, Terminal::PEN],
-    ];
-    routes.iter().flat_map(|terminals| Terminal::combinations(terminals)).collect()
+pub static ROUTE_5_AND_9_GULF_ISLAND_TERMINALS: Lazy<HashSet<Terminal>> = Lazy::new(|| {
+    let registry = Registry::get();
+    let route_terminals = |number| {
+        registry.route(number).map(|r| r.terminals.iter().cloned().collect::<HashSet<_>>()).unwrap_or_default()
+    };
+    &route_terminals(5) & &route_terminals(9)
 });
 
+pub static ALL_TERMINAL_PAIRS: Lazy<HashSet<TerminalPair>> =
+    Lazy::new(|| Registry::get().routes.iter().flat_map(|r| Terminal::combinations(&r.terminals)).collect());
+
 pub static ALL_AREA_PAIRS: Lazy<HashSet<AreaPair>> =
     Lazy::new(|| HashSet::from_iter(ALL_TERMINAL_PAIRS.iter().map(|tp| tp.area_pair())));
 
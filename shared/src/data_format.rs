@@ -0,0 +1,265 @@
+use crate::imports::*;
+use crate::types::*;
+
+use serde_json::{Map, Value};
+
+/// Version of the schedules JSON format written by the scraper; bump this and add a migration whenever a reader of the
+/// previous version would misread the new data. New optional fields that older readers can safely ignore, such as stop
+/// times, vessels, cancellation notices and notes to review, don't need a bump.
+pub const SCHEDULES_FORMAT_VERSION: u32 = 3;
+
+/// Directory alongside the schedules JSON containing the per-route schedules data files and their index
+pub const ROUTES_DIRECTORY: &str = "routes";
+pub const ROUTE_INDEX_FILENAME: &str = "index.json";
+
+// Each migration converts the JSON for version N (its index) to version N + 1
+const MIGRATIONS: [fn(Value) -> Result<Value>; SCHEDULES_FORMAT_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SchedulesData {
+    pub format_version: u32,
+    pub generated_at: OffsetDateTime,
+    pub schedules: Vec<Schedule>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RouteIndexEntry {
+    pub terminal_pair: TerminalPair,
+    /// Path of the route's schedules data file, relative to the index
+    pub path: String,
+    pub date_ranges: Vec<DateRange>,
+    /// Hash of the route's schedules data file, which changes whenever its contents do
+    pub hash: String,
+}
+
+/// Manifest of the per-route schedules data files, so that clients can fetch only the routes they need
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RouteIndex {
+    pub format_version: u32,
+    pub generated_at: OffsetDateTime,
+    pub routes: Vec<RouteIndexEntry>,
+}
+
+/// A data file was written by a newer scraper than this build understands, so it needs updating
+#[derive(Debug)]
+pub struct NewerFormatVersionError {
+    /// What the data file contains, such as "Schedules"
+    pub data_name: &'static str,
+    pub format_version: u32,
+    pub supported_format_version: u32,
+}
+
+impl fmt::Display for NewerFormatVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} format version {} is newer than supported version {}",
+            self.data_name, self.format_version, self.supported_format_version
+        )
+    }
+}
+
+impl std::error::Error for NewerFormatVersionError {}
+
+// Version 0 was a bare array of schedules with no envelope
+fn migrate_v0_to_v1(value: Value) -> Result<Value> {
+    let schedules = match value {
+        Value::Array(schedules) => schedules,
+        _ => bail!("Expect version 0 schedules to be an array"),
+    };
+    let generated_at = schedules
+        .iter()
+        .filter_map(|s| s.get("refreshed_at").and_then(|r| serde_json::from_value::<OffsetDateTime>(r.clone()).ok()))
+        .max()
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    let mut envelope = Map::new();
+    envelope.insert("format_version".to_string(), Value::from(1));
+    envelope.insert(
+        "generated_at".to_string(),
+        serde_json::to_value(generated_at).context("Failed to serialize generated-at timestamp")?,
+    );
+    envelope.insert("schedules".to_string(), Value::Array(schedules));
+    Ok(Value::Object(envelope))
+}
+
+// Version 2 allows date spans in date restrictions, which version 1 readers can't parse; version 1's lists of dates
+// are still valid
+fn migrate_v1_to_v2(mut value: Value) -> Result<Value> {
+    let envelope = value.as_object_mut().ok_or_else(|| anyhow!("Expect version 1 schedules to be an object"))?;
+    envelope.insert("format_version".to_string(), Value::from(2));
+    Ok(value)
+}
+
+// Version 3 puts sailings after midnight on the previous operating day with day offsets, which version 2 readers would
+// ignore and show them on the wrong day. Version 2 had no offsets, so a sailing that arrives at an earlier clock time
+// than it departs arrives the next day.
+fn migrate_v2_to_v3(mut value: Value) -> Result<Value> {
+    let envelope = value.as_object_mut().ok_or_else(|| anyhow!("Expect version 2 schedules to be an object"))?;
+    let items = envelope
+        .get_mut("schedules")
+        .and_then(|s| s.as_array_mut())
+        .ok_or_else(|| anyhow!("Expect version 2 schedules to have a 'schedules' array"))?
+        .iter_mut()
+        .filter_map(|s| s.get_mut("items").and_then(|i| i.as_array_mut()))
+        .flatten();
+    for item in items {
+        let sailing = item
+            .get_mut("sailing")
+            .and_then(|s| s.as_object_mut())
+            .ok_or_else(|| anyhow!("Expect version 2 schedule item to have a 'sailing' object"))?;
+        let time = |name: &str| {
+            sailing
+                .get(name)
+                .and_then(|t| serde_json::from_value::<Time>(t.clone()).ok())
+                .ok_or_else(|| anyhow!("Expect version 2 sailing to have '{}' time", name))
+        };
+        if time("arrive_time")? < time("depart_time")? {
+            sailing.insert("arrive_day_offset".to_string(), Value::from(1));
+        }
+    }
+    envelope.insert("format_version".to_string(), Value::from(3));
+    Ok(value)
+}
+
+fn format_version(value: &Value) -> Result<u32> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(map) => {
+            let version = map
+                .get("format_version")
+                .and_then(|v| v.as_u64())
+                .ok_or_else(|| anyhow!("Expect data object to have numeric 'format_version'"))?;
+            u32::try_from(version).context("Format version out of range")
+        }
+        _ => bail!("Expect data JSON to be an object or array"),
+    }
+}
+
+/// Format version of a data file's JSON, failing with `NewerFormatVersionError` if it is newer than the supported one
+pub fn check_format_version(value: &Value, data_name: &'static str, supported_format_version: u32) -> Result<u32> {
+    let format_version = format_version(value)?;
+    if format_version > supported_format_version {
+        return Err(anyhow!(NewerFormatVersionError { data_name, format_version, supported_format_version }));
+    }
+    Ok(format_version)
+}
+
+impl SchedulesData {
+    pub fn new(schedules: Vec<Schedule>, generated_at: OffsetDateTime) -> SchedulesData {
+        SchedulesData { format_version: SCHEDULES_FORMAT_VERSION, generated_at, schedules }
+    }
+
+    pub fn from_value(mut value: Value) -> Result<SchedulesData> {
+        let inner = || {
+            let mut version = check_format_version(&value, "Schedules", SCHEDULES_FORMAT_VERSION)?;
+            while version < SCHEDULES_FORMAT_VERSION {
+                value = MIGRATIONS[version as usize](value)
+                    .with_context(|| format!("Failed to migrate from format version {}", version))?;
+                version += 1;
+            }
+            let data: SchedulesData = serde_json::from_value(value).context("Failed to deserialize schedules")?;
+            for schedule in &data.schedules {
+                schedule.terminal_pair.ensure_known()?;
+                for stop in schedule.items.iter().flat_map(|i| &i.sailing.stops) {
+                    ensure!(stop.terminal.is_known(), "Unknown stop terminal: {}", stop.terminal);
+                }
+            }
+            Ok(data) as Result<_>
+        };
+        inner().context("Failed to read schedules data")
+    }
+
+    pub fn from_json_str(json: &str) -> Result<SchedulesData> {
+        let value = serde_json::from_str(json).context("Failed to parse schedules JSON")?;
+        SchedulesData::from_value(value)
+    }
+}
+
+impl RouteIndex {
+    pub fn new(routes: Vec<RouteIndexEntry>, generated_at: OffsetDateTime) -> RouteIndex {
+        RouteIndex { format_version: SCHEDULES_FORMAT_VERSION, generated_at, routes }
+    }
+
+    pub fn from_json_str(json: &str) -> Result<RouteIndex> {
+        let inner = || {
+            let value = serde_json::from_str(json).context("Failed to parse route index JSON")?;
+            check_format_version(&value, "Route index", SCHEDULES_FORMAT_VERSION)?;
+            let index: RouteIndex = serde_json::from_value(value).context("Failed to deserialize route index")?;
+            for entry in &index.routes {
+                entry.terminal_pair.ensure_known()?;
+            }
+            Ok(index) as Result<_>
+        };
+        inner().context("Failed to read route index")
+    }
+
+    pub fn get(&self, terminal_pair: TerminalPair) -> Option<&RouteIndexEntry> {
+        self.routes.iter().find(|r| r.terminal_pair == terminal_pair)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_unversioned_schedules() -> Result<()> {
+        let json = r#"[{
+            "terminal_pair": {"from": "TSA", "to": "SWB"},
+            "date_range": {"from": "2023-06-01", "to": "2023-09-04"},
+            "items": [],
+            "source_url": "",
+            "refreshed_at": "2023-05-20 12:00:00.0 +00:00:00"
+        }]"#;
+        let data = SchedulesData::from_json_str(json)?;
+        assert_eq!(data.format_version, SCHEDULES_FORMAT_VERSION);
+        assert_eq!(data.schedules.len(), 1);
+        assert_eq!(data.generated_at, data.schedules[0].refreshed_at);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_v2_arrive_next_day() -> Result<()> {
+        let json = r#"{
+            "format_version": 2,
+            "generated_at": "2023-05-20 12:00:00.0 +00:00:00",
+            "schedules": [{
+                "terminal_pair": {"from": "TSA", "to": "SWB"},
+                "date_range": {"from": "2023-06-01", "to": "2023-09-04"},
+                "items": [
+                    {"sailing": {"depart_time": "21:00:00.0", "arrive_time": "22:35:00.0", "stops": []}, "weekdays": {}},
+                    {"sailing": {"depart_time": "23:00:00.0", "arrive_time": "00:35:00.0", "stops": []}, "weekdays": {}}
+                ],
+                "source_url": "",
+                "refreshed_at": "2023-05-20 12:00:00.0 +00:00:00"
+            }]
+        }"#;
+        let data = SchedulesData::from_json_str(json)?;
+        assert_eq!(data.schedules[0].items.iter().map(|i| i.sailing.arrive_day_offset).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(data.schedules[0].items[1].sailing.duration(date!(2023 - 06 - 01)), Duration::minutes(95));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_newer_format_version() -> Result<()> {
+        let json = format!(r#"{{"format_version": {}, "schedules": []}}"#, SCHEDULES_FORMAT_VERSION + 1);
+        let err = SchedulesData::from_json_str(&json).expect_err("Expect newer format version to fail");
+        assert!(err.downcast_ref::<NewerFormatVersionError>().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unknown_terminal() -> Result<()> {
+        let json = r#"[{
+            "terminal_pair": {"from": "TSA", "to": "XYZ"},
+            "date_range": {"from": "2023-06-01", "to": "2023-09-04"},
+            "items": [],
+            "source_url": "",
+            "refreshed_at": "2023-05-20 12:00:00.0 +00:00:00"
+        }]"#;
+        let err = SchedulesData::from_json_str(json).expect_err("Expect unknown terminal to fail");
+        assert!(format!("{:#}", err).contains("Unknown terminal: XYZ"));
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use crate::imports::*;
+use crate::utils::*;
+
+use std::collections::BTreeSet;
+
+// Runs shorter than this are cheaper to write out as individual dates
+const MIN_SPAN_DATES: usize = 3;
+
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DateStep {
+    Daily,
+    Weekly,
+}
+
+/// Dates from `from` to `to` inclusive, either every day or every week on the same weekday as `from`
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DateSpan {
+    pub from: Date,
+    pub to: Date,
+    pub step: DateStep,
+}
+
+/// Set of dates, kept in a canonical form of daily spans, weekly spans (which may overlap the daily ones) and single
+/// dates, so two sets with the same dates always compare and serialize the same. The form is usually, but not always,
+/// the fewest spans that cover the dates.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DateSet {
+    spans: Vec<DateSpan>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum DateSetEntry {
+    Date(Date),
+    Span {
+        from: Date,
+        to: Date,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        #[serde(default)]
+        weekly: bool,
+    },
+}
+
+impl DateStep {
+    fn days(&self) -> i64 {
+        match self {
+            DateStep::Daily => 1,
+            DateStep::Weekly => 7,
+        }
+    }
+}
+
+impl DateSpan {
+    pub fn contains(&self, date: Date) -> bool {
+        date >= self.from && date <= self.to && (date - self.from).whole_days() % self.step.days() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Date> + '_ {
+        iter::successors(Some(self.from), move |d| d.checked_add(Duration::days(self.step.days())))
+            .take_while(move |d| *d <= self.to)
+    }
+}
+
+impl fmt::Display for DateSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.step {
+            _ if self.from == self.to => f.write_str(&format_iso8601_date(self.from)),
+            DateStep::Daily => write!(f, "{} to {}", format_iso8601_date(self.from), format_iso8601_date(self.to)),
+            DateStep::Weekly => write!(
+                f,
+                "every {} {} to {}",
+                self.from.weekday(),
+                format_iso8601_date(self.from),
+                format_iso8601_date(self.to)
+            ),
+        }
+    }
+}
+
+// Splits dates sorted by the step's order into maximal runs separated by `step`, returning those long enough to be
+// spans
+fn take_runs(dates: &[Date], step: DateStep) -> Vec<DateSpan> {
+    let mut spans = Vec::new();
+    let mut run_start = 0;
+    for index in 1..=dates.len() {
+        if index == dates.len() || (dates[index] - dates[index - 1]).whole_days() != step.days() {
+            if index - run_start >= MIN_SPAN_DATES {
+                spans.push(DateSpan { from: dates[run_start], to: dates[index - 1], step });
+            }
+            run_start = index;
+        }
+    }
+    spans
+}
+
+// Maximal runs of the sorted dates with the step, which for weekly runs means each weekday's dates separately
+fn runs(dates: &[Date], step: DateStep) -> Vec<DateSpan> {
+    match step {
+        DateStep::Daily => take_runs(dates, step),
+        DateStep::Weekly => dates
+            .iter()
+            .copied()
+            .sorted_by_key(|d| (d.weekday().number_from_monday(), *d))
+            .group_by(|d| d.weekday())
+            .into_iter()
+            .flat_map(|(_, weekday_dates)| take_runs(&weekday_dates.collect::<Vec<_>>(), step))
+            .collect(),
+    }
+}
+
+// Covers the sorted dates with the runs of the first step, then the runs of the second step that cover at least two
+// dates the first didn't (one is no better than a single date), then single dates
+fn cover_dates(dates: &[Date], first: DateStep, second: DateStep) -> Vec<DateSpan> {
+    let mut spans = runs(dates, first);
+    let mut covered: HashSet<Date> = spans.iter().flat_map(|s| s.iter()).collect();
+    for span in runs(dates, second) {
+        if span.iter().filter(|d| !covered.contains(d)).count() >= 2 {
+            covered.extend(span.iter());
+            spans.push(span);
+        }
+    }
+    spans.extend(dates.iter().filter(|d| !covered.contains(d)).map(|&d| DateSpan {
+        from: d,
+        to: d,
+        step: DateStep::Daily,
+    }));
+    spans.sort_unstable();
+    spans
+}
+
+impl DateSet {
+    pub fn new() -> DateSet {
+        DateSet { spans: Vec::new() }
+    }
+
+    // Taking weekly runs first finds the short form of a season of weekends, which is a weekly span for each day rather
+    // than a daily span for each weekend, so try both orders
+    fn from_sorted_dates(dates: Vec<Date>) -> DateSet {
+        let daily_first = cover_dates(&dates, DateStep::Daily, DateStep::Weekly);
+        let weekly_first = cover_dates(&dates, DateStep::Weekly, DateStep::Daily);
+        let spans = if weekly_first.len() < daily_first.len() { weekly_first } else { daily_first };
+        DateSet { spans }
+    }
+
+    pub fn spans(&self) -> &[DateSpan] {
+        &self.spans
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    pub fn contains(&self, date: Date) -> bool {
+        self.spans.iter().any(|s| s.contains(date))
+    }
+
+    /// All the dates in the set, in order
+    pub fn iter(&self) -> impl Iterator<Item = Date> + '_ {
+        self.spans.iter().flat_map(|s| s.iter()).collect::<BTreeSet<_>>().into_iter()
+    }
+
+    pub fn extend(&mut self, other: &DateSet) {
+        *self = self.iter().chain(other.iter()).collect();
+    }
+}
+
+impl FromIterator<Date> for DateSet {
+    fn from_iter<I: IntoIterator<Item = Date>>(dates: I) -> DateSet {
+        DateSet::from_sorted_dates(dates.into_iter().collect::<BTreeSet<_>>().into_iter().collect())
+    }
+}
+
+impl fmt::Display for DateSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.spans.iter().join(", "))
+    }
+}
+
+impl Serialize for DateSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.spans.iter().map(|s| {
+            if s.from == s.to {
+                DateSetEntry::Date(s.from)
+            } else {
+                DateSetEntry::Span { from: s.from, to: s.to, weekly: s.step == DateStep::Weekly }
+            }
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for DateSet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<DateSet, D::Error> {
+        let entries = Vec::<DateSetEntry>::deserialize(deserializer)?;
+        let mut dates = BTreeSet::new();
+        for entry in entries {
+            match entry {
+                DateSetEntry::Date(date) => {
+                    dates.insert(date);
+                }
+                DateSetEntry::Span { from, to, weekly } => {
+                    if from > to {
+                        return Err(serde::de::Error::custom(format!("Date span ends before it starts: {}", from)));
+                    }
+                    let step = if weekly { DateStep::Weekly } else { DateStep::Daily };
+                    dates.extend(DateSpan { from, to, step }.iter());
+                }
+            }
+        }
+        Ok(DateSet::from_sorted_dates(dates.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_set_normalize() -> Result<()> {
+        let saturdays = DateSpan { from: date!(2023 - 06 - 03), to: date!(2023 - 09 - 02), step: DateStep::Weekly };
+        let holidays = DateSpan { from: date!(2023 - 12 - 20), to: date!(2024 - 01 - 03), step: DateStep::Daily };
+        let dates: DateSet = saturdays
+            .iter()
+            .chain(holidays.iter())
+            .chain([date!(2023 - 07 - 04), date!(2023 - 07 - 05), date!(2023 - 06 - 03)])
+            .collect();
+        assert_eq!(
+            dates.spans(),
+            &[
+                saturdays,
+                DateSpan { from: date!(2023 - 07 - 04), to: date!(2023 - 07 - 04), step: DateStep::Daily },
+                DateSpan { from: date!(2023 - 07 - 05), to: date!(2023 - 07 - 05), step: DateStep::Daily },
+                holidays,
+            ]
+        );
+        assert!(dates.contains(date!(2023 - 08 - 12)));
+        assert!(!dates.contains(date!(2023 - 08 - 13)));
+        assert!(dates.contains(date!(2024 - 01 - 01)));
+        let json = serde_json::to_string(&dates)?;
+        assert_eq!(
+            json,
+            r#"[{"from":"2023-06-03","to":"2023-09-02","weekly":true},"2023-07-04","2023-07-05",{"from":"2023-12-20","to":"2024-01-03"}]"#
+        );
+        assert_eq!(serde_json::from_str::<DateSet>(&json)?, dates);
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_set_overlapping_spans() -> Result<()> {
+        let saturdays = DateSpan { from: date!(2023 - 06 - 03), to: date!(2023 - 09 - 02), step: DateStep::Weekly };
+        let week = DateSpan { from: date!(2023 - 07 - 01), to: date!(2023 - 07 - 06), step: DateStep::Daily };
+        let dates: DateSet = saturdays.iter().chain(week.iter()).collect();
+        assert_eq!(dates.spans(), &[saturdays, week]);
+        assert_eq!(dates.iter().count(), 14 + 5);
+        let weekends: DateSet =
+            DateSpan { from: date!(2023 - 06 - 02), to: date!(2023 - 09 - 03), step: DateStep::Daily }
+                .iter()
+                .filter(|d| [Weekday::Friday, Weekday::Saturday, Weekday::Sunday].contains(&d.weekday()))
+                .collect();
+        assert_eq!(weekends.spans().len(), 3);
+        assert!(weekends.spans().iter().all(|s| s.step == DateStep::Weekly));
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_set_read_date_list() -> Result<()> {
+        let dates: DateSet = serde_json::from_str(r#"["2023-07-03", "2023-07-01", "2023-07-02"]"#)?;
+        assert_eq!(dates.to_string(), "2023-07-01 to 2023-07-03");
+        Ok(())
+    }
+}
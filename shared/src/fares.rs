@@ -0,0 +1,174 @@
+use crate::data_format::*;
+use crate::imports::*;
+use crate::types::*;
+
+/// Version of the fares JSON format written by the scraper
+pub const FARES_FORMAT_VERSION: u32 = 1;
+
+/// Fares data file, alongside the schedules JSON
+pub const FARES_FILENAME: &str = "fares.json";
+
+/// Amount in Canadian cents
+pub type Cents = u32;
+
+#[derive(Copy, Clone, Debug, Deserialize, Display, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum PassengerType {
+    Adult,
+    Senior,
+    Child,
+    Infant,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VehicleFare {
+    /// Fare for a vehicle up to `standard_max_length_ft` long, not including its driver
+    pub standard: Cents,
+    pub standard_max_length_ft: u32,
+    /// Added for each foot of length over the standard length
+    pub per_extra_foot: Cents,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FareTable {
+    pub passengers: HashMap<PassengerType, Cents>,
+    /// Missing for routes that don't carry vehicles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub vehicle: Option<VehicleFare>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Fare {
+    pub terminal_pair: TerminalPair,
+    pub date_range: DateRange,
+    pub regular: FareTable,
+    /// Fares for thru-fare sailings, which include the connecting sailing at the thru-fare stop
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub thrufare: Option<FareTable>,
+    /// Days on which seniors pay the senior fare; on other days they pay the adult fare
+    pub senior_weekdays: Vec<Weekday>,
+    pub source_url: String,
+}
+
+/// Passengers and vehicle to estimate the fare for
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FareParty {
+    pub passengers: HashMap<PassengerType, u32>,
+    pub vehicle_length_ft: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FaresData {
+    pub format_version: u32,
+    pub generated_at: OffsetDateTime,
+    pub fares: Vec<Fare>,
+}
+
+impl FareTable {
+    fn passenger_fare(&self, passenger_type: PassengerType) -> Option<Cents> {
+        self.passengers.get(&passenger_type).copied()
+    }
+}
+
+impl Fare {
+    /// Estimated total fare for the party on the date, or `None` if the fare tables don't cover it (e.g. a vehicle
+    /// on a foot passenger route)
+    pub fn estimate(&self, party: &FareParty, date: Date, is_thrufare: bool) -> Option<Cents> {
+        let table = match (&self.thrufare, is_thrufare) {
+            (Some(thrufare), true) => thrufare,
+            _ => &self.regular,
+        };
+        let mut total = 0;
+        for (&passenger_type, &count) in &party.passengers {
+            let fare = match passenger_type {
+                PassengerType::Senior if !self.senior_weekdays.contains(&date.weekday()) => {
+                    table.passenger_fare(PassengerType::Adult)?
+                }
+                PassengerType::Infant => table.passenger_fare(passenger_type).unwrap_or(0),
+                _ => table.passenger_fare(passenger_type)?,
+            };
+            total += fare * count;
+        }
+        if let Some(length_ft) = party.vehicle_length_ft {
+            let vehicle = table.vehicle.as_ref()?;
+            total +=
+                vehicle.standard + length_ft.saturating_sub(vehicle.standard_max_length_ft) * vehicle.per_extra_foot;
+        }
+        Some(total)
+    }
+}
+
+pub fn find_fare(fares: &[Fare], terminal_pair: TerminalPair, date: Date) -> Option<&Fare> {
+    fares.iter().find(|f| f.terminal_pair == terminal_pair && f.date_range.includes_date_inclusive(date))
+}
+
+pub fn format_cents(cents: Cents) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+impl FaresData {
+    pub fn new(fares: Vec<Fare>, generated_at: OffsetDateTime) -> FaresData {
+        FaresData { format_version: FARES_FORMAT_VERSION, generated_at, fares }
+    }
+
+    pub fn from_json_str(json: &str) -> Result<FaresData> {
+        let inner = || {
+            let value = serde_json::from_str(json).context("Failed to parse fares JSON")?;
+            check_format_version(&value, "Fares", FARES_FORMAT_VERSION)?;
+            let data: FaresData = serde_json::from_value(value).context("Failed to deserialize fares")?;
+            for fare in &data.fares {
+                fare.terminal_pair.ensure_known()?;
+            }
+            Ok(data) as Result<_>
+        };
+        inner().context("Failed to read fares data")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fare_estimate() -> Result<()> {
+        let table = |adult| FareTable {
+            passengers: HashMap::from([
+                (PassengerType::Adult, adult),
+                (PassengerType::Senior, 0),
+                (PassengerType::Child, adult / 2),
+            ]),
+            vehicle: Some(VehicleFare { standard: 6000, standard_max_length_ft: 20, per_extra_foot: 600 }),
+        };
+        let fare = Fare {
+            terminal_pair: TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("PSB") },
+            date_range: DateRange { from: date!(2023 - 06 - 01), to: date!(2023 - 09 - 04) },
+            regular: table(2000),
+            thrufare: Some(table(2400)),
+            senior_weekdays: vec![Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday],
+            source_url: String::new(),
+        };
+        let party = FareParty {
+            passengers: HashMap::from([
+                (PassengerType::Senior, 2),
+                (PassengerType::Child, 1),
+                (PassengerType::Infant, 1),
+            ]),
+            vehicle_length_ft: Some(22),
+        };
+        // Monday, so seniors travel at the senior fare
+        assert_eq!(fare.estimate(&party, date!(2023 - 07 - 03), false), Some(1000 + 6000 + 1200));
+        // Saturday, so seniors pay the adult fare
+        assert_eq!(fare.estimate(&party, date!(2023 - 07 - 08), true), Some(2 * 2400 + 1200 + 6000 + 1200));
+        assert_eq!(format_cents(8200), "$82.00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_newer_fares_format_version() -> Result<()> {
+        let json = format!(r#"{{"format_version": {}, "fares": []}}"#, FARES_FORMAT_VERSION + 1);
+        let err = FaresData::from_json_str(&json).expect_err("Expect newer format version to fail");
+        assert!(err.downcast_ref::<NewerFormatVersionError>().is_some());
+        Ok(())
+    }
+}
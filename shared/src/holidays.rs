@@ -0,0 +1,201 @@
+use crate::imports::*;
+use crate::sailings::*;
+use crate::types::*;
+
+/// British Columbia statutory holidays
+#[derive(Copy, Clone, Debug, EnumIter, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Holiday {
+    NewYearsDay,
+    FamilyDay,
+    GoodFriday,
+    VictoriaDay,
+    CanadaDay,
+    BcDay,
+    LabourDay,
+    TruthAndReconciliationDay,
+    Thanksgiving,
+    RemembranceDay,
+    ChristmasDay,
+}
+
+// The `nth` (starting at 1) `weekday` in the month
+fn nth_weekday(year: i32, month: Month, weekday: Weekday, nth: u8) -> Date {
+    let first = Date::from_calendar_date(year, month, 1).expect("Expect first of month to be valid");
+    let days_until = (weekday.number_days_from_monday() + 7 - first.weekday().number_days_from_monday()) % 7;
+    first + Duration::days((days_until + 7 * (nth - 1)) as i64)
+}
+
+// Gregorian Easter Sunday, using the anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> Date {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    Date::from_calendar_date(year, Month::try_from(month as u8).expect("Expect Easter month to be valid"), day as u8)
+        .expect("Expect Easter date to be valid")
+}
+
+fn ymd(year: i32, month: Month, day: u8) -> Date {
+    Date::from_calendar_date(year, month, day).expect("Expect holiday date to be valid")
+}
+
+impl Holiday {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Holiday::NewYearsDay => "New Year's Day",
+            Holiday::FamilyDay => "Family Day",
+            Holiday::GoodFriday => "Good Friday",
+            Holiday::VictoriaDay => "Victoria Day",
+            Holiday::CanadaDay => "Canada Day",
+            Holiday::BcDay => "BC Day",
+            Holiday::LabourDay => "Labour Day",
+            Holiday::TruthAndReconciliationDay => "National Day for Truth and Reconciliation",
+            Holiday::Thanksgiving => "Thanksgiving",
+            Holiday::RemembranceDay => "Remembrance Day",
+            Holiday::ChristmasDay => "Christmas Day",
+        }
+    }
+
+    /// Parse the name of a holiday as it appears in schedule notes (e.g. "Family Day" or "Thanksgiving Day")
+    pub fn from_name(text: &str) -> Option<Holiday> {
+        let text = text.trim().trim_end_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        let text = text.strip_suffix(" monday").unwrap_or(&text);
+        Holiday::iter().find(|h| {
+            let name = h.name().to_lowercase();
+            text == name
+                || text == name.replace('\'', "")
+                || text == format!("{} day", name)
+                || (*h == Holiday::TruthAndReconciliationDay && text == "truth and reconciliation day")
+        })
+    }
+
+    /// Date of the holiday in the year, or `None` if it wasn't a BC statutory holiday that year
+    pub fn date(&self, year: i32) -> Option<Date> {
+        match self {
+            Holiday::NewYearsDay => Some(ymd(year, Month::January, 1)),
+            // Second Monday of February from 2013, then the third Monday from 2019
+            Holiday::FamilyDay if year >= 2019 => Some(nth_weekday(year, Month::February, Weekday::Monday, 3)),
+            Holiday::FamilyDay if year >= 2013 => Some(nth_weekday(year, Month::February, Weekday::Monday, 2)),
+            Holiday::FamilyDay => None,
+            Holiday::GoodFriday => Some(easter_sunday(year) - Duration::days(2)),
+            // Last Monday before May 25
+            Holiday::VictoriaDay => {
+                let may_24 = ymd(year, Month::May, 24);
+                Some(may_24 - Duration::days(may_24.weekday().number_days_from_monday() as i64))
+            }
+            // Moves to the following day when July 1st is a Sunday
+            Holiday::CanadaDay => Some(match ymd(year, Month::July, 1) {
+                date if date.weekday() == Weekday::Sunday => ymd(year, Month::July, 2),
+                date => date,
+            }),
+            Holiday::BcDay => Some(nth_weekday(year, Month::August, Weekday::Monday, 1)),
+            Holiday::LabourDay => Some(nth_weekday(year, Month::September, Weekday::Monday, 1)),
+            Holiday::TruthAndReconciliationDay if year >= 2023 => Some(ymd(year, Month::September, 30)),
+            Holiday::TruthAndReconciliationDay => None,
+            Holiday::Thanksgiving => Some(nth_weekday(year, Month::October, Weekday::Monday, 2)),
+            Holiday::RemembranceDay => Some(ymd(year, Month::November, 11)),
+            Holiday::ChristmasDay => Some(ymd(year, Month::December, 25)),
+        }
+    }
+
+    /// The dates of the holiday that fall in the date range
+    pub fn dates_within(&self, date_range: &DateRange) -> Vec<Date> {
+        (date_range.from.year()..=date_range.to.year())
+            .filter_map(|year| self.date(year))
+            .filter(|date| date_range.includes_date_inclusive(*date))
+            .collect()
+    }
+
+    pub fn on_date(date: Date) -> Option<Holiday> {
+        Holiday::iter().find(|h| h.date(date.year()) == Some(date))
+    }
+}
+
+impl fmt::Display for Holiday {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// All the holidays that fall in the date range, in date order
+pub fn holidays_within(date_range: &DateRange) -> Vec<(Date, Holiday)> {
+    Holiday::iter().flat_map(|h| h.dates_within(date_range).into_iter().map(move |d| (d, h))).sorted().collect()
+}
+
+fn area_departures_for_date(
+    area_pair: AreaPair,
+    date: Date,
+    schedules_map: &SchedulesMap,
+) -> Option<Vec<(TerminalPair, Time)>> {
+    area_sailings_for_date(area_pair, date, schedules_map).map(|schedules_sailings| {
+        schedules_sailings
+            .iter()
+            .flat_map(|(schedule, sailings)| sailings.iter().map(|s| (schedule.terminal_pair, s.sailing.depart_time)))
+            .sorted()
+            .collect()
+    })
+}
+
+/// Holidays in the date range whose sailings differ from the same weekday a week before (or after, at the start of a
+/// schedule)
+pub fn holidays_with_different_sailings(
+    area_pair: AreaPair,
+    date_range: DateRange,
+    schedules_map: &SchedulesMap,
+) -> Vec<(Date, Holiday)> {
+    holidays_within(&date_range)
+        .into_iter()
+        .filter(|(date, _)| {
+            let departures = area_departures_for_date(area_pair, *date, schedules_map);
+            let usual_departures = [*date - Duration::weeks(1), *date + Duration::weeks(1)]
+                .into_iter()
+                .filter(|d| Holiday::on_date(*d).is_none())
+                .find_map(|d| area_departures_for_date(area_pair, d, schedules_map));
+            matches!((departures, usual_departures), (Some(a), Some(b)) if a != b)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holiday_dates() -> Result<()> {
+        let date_range = DateRange { from: date!(2024 - 01 - 01), to: date!(2024 - 12 - 31) };
+        assert_eq!(
+            holidays_within(&date_range).into_iter().map(|(d, _)| d).collect::<Vec<_>>(),
+            vec![
+                date!(2024 - 01 - 01),
+                date!(2024 - 02 - 19),
+                date!(2024 - 03 - 29),
+                date!(2024 - 05 - 20),
+                date!(2024 - 07 - 01),
+                date!(2024 - 08 - 05),
+                date!(2024 - 09 - 02),
+                date!(2024 - 09 - 30),
+                date!(2024 - 10 - 14),
+                date!(2024 - 11 - 11),
+                date!(2024 - 12 - 25),
+            ]
+        );
+        assert_eq!(Holiday::VictoriaDay.date(2023), Some(date!(2023 - 05 - 22)));
+        assert_eq!(Holiday::CanadaDay.date(2029), Some(date!(2029 - 07 - 02)));
+        assert_eq!(Holiday::GoodFriday.date(2025), Some(date!(2025 - 04 - 18)));
+        assert_eq!(Holiday::FamilyDay.date(2012), None);
+        assert_eq!(Holiday::from_name("Thanksgiving Day"), Some(Holiday::Thanksgiving));
+        assert_eq!(Holiday::from_name("BC Day Monday"), Some(Holiday::BcDay));
+        assert_eq!(Holiday::from_name("New Years Day"), Some(Holiday::NewYearsDay));
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use crate::constants::*;
+use crate::imports::*;
+use crate::types::*;
+
+const ICAL_MAX_LINE_OCTETS: usize = 75;
+const ICAL_UID_DOMAIN: &str = "ferries.borsboom.io";
+const ICAL_TIMEZONE_ID: &str = "America/Vancouver";
+const ICAL_VTIMEZONE: &[&str] = &[
+    "BEGIN:VTIMEZONE",
+    "TZID:America/Vancouver",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:-0800",
+    "TZOFFSETTO:-0700",
+    "TZNAME:PDT",
+    "DTSTART:19700308T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:-0700",
+    "TZOFFSETTO:-0800",
+    "TZNAME:PST",
+    "DTSTART:19701101T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IcalEvent {
+    pub uid: String,
+    pub stamp: OffsetDateTime,
+    pub start: (Date, Time),
+    pub end: (Date, Time),
+    pub summary: String,
+    pub location: String,
+    pub description: String,
+}
+
+fn escape_ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+fn fold_ical_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > ICAL_MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn format_ical_local_datetime((date, time): (Date, Time)) -> String {
+    format!(
+        "{}T{}",
+        date.format(format_description!("[year][month][day]")).expect("Expect iCalendar date to format"),
+        time.format(format_description!("[hour][minute][second]")).expect("Expect iCalendar time to format")
+    )
+}
+
+fn format_ical_utc_datetime(datetime: OffsetDateTime) -> String {
+    let utc = datetime.to_offset(time::UtcOffset::UTC);
+    format!("{}Z", format_ical_local_datetime((utc.date(), utc.time())))
+}
+
+fn stop_description(stop: &Stop) -> String {
+    let type_text = match stop.type_ {
+        StopType::Stop => "Stop",
+        StopType::Transfer => "Transfer",
+        StopType::Thrufare => "Thru-fare",
+    };
+    let format_time = |time: Time| time.format(format_description!("[hour]:[minute]")).expect("Expect time to format");
+    let times_text = match (stop.arrive_time, stop.depart_time) {
+        (Some(arrive), Some(depart)) if arrive != depart => {
+            format!(" ({}-{})", format_time(arrive), format_time(depart))
+        }
+        (Some(time), _) | (None, Some(time)) => format!(" ({})", format_time(time)),
+        (None, None) => String::new(),
+    };
+    format!("{} {}{}", type_text, stop.terminal.name(), times_text)
+}
+
+impl IcalEvent {
+    pub fn for_sailing<S: AsRef<str>>(
+        terminal_pair: TerminalPair,
+        date: Date,
+        sailing: &Sailing,
+        notes: &[S],
+        source_url: &str,
+        stamp: OffsetDateTime,
+    ) -> IcalEvent {
+        let start = (sailing.depart_date(date), sailing.depart_time);
+        let mut description_lines = Vec::new();
+        if sailing.stops.is_empty() {
+            description_lines.push("Non-stop".to_string());
+        } else {
+            description_lines.push(format!("Stops: {}", sailing.stops.iter().map(stop_description).join(", ")));
+        }
+        if let Some(vessel) = &sailing.vessel {
+            description_lines.push(format!("Vessel: {}", vessel));
+        }
+        description_lines.extend(notes.iter().map(|n| n.as_ref().to_string()));
+        description_lines.push(format!("Confirm with the official schedule: {}", source_url));
+        IcalEvent {
+            uid: format!("{}-{}@{}", format_ical_local_datetime(start), terminal_pair, ICAL_UID_DOMAIN),
+            stamp,
+            start,
+            end: (sailing.arrive_date(date), sailing.arrive_time),
+            summary: format!("Ferry {} to {}", terminal_pair.from.name(), terminal_pair.to.name()),
+            location: format!("{} ferry terminal", terminal_pair.from.name()),
+            description: description_lines.join("\n"),
+        }
+    }
+
+    fn write_lines(&self, lines: &mut Vec<String>) {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", self.uid));
+        lines.push(format!("DTSTAMP:{}", format_ical_utc_datetime(self.stamp)));
+        lines.push(format!("DTSTART;TZID={}:{}", ICAL_TIMEZONE_ID, format_ical_local_datetime(self.start)));
+        lines.push(format!("DTEND;TZID={}:{}", ICAL_TIMEZONE_ID, format_ical_local_datetime(self.end)));
+        lines.push(format!("SUMMARY:{}", escape_ical_text(&self.summary)));
+        lines.push(format!("LOCATION:{}", escape_ical_text(&self.location)));
+        lines.push(format!("DESCRIPTION:{}", escape_ical_text(&self.description)));
+        lines.push("END:VEVENT".to_string());
+    }
+}
+
+pub fn schedule_ical_events(schedule: &Schedule, date_range: DateRange) -> Vec<IcalEvent> {
+    let mut events = Vec::new();
+    for date in date_range.iter_days().filter(|d| schedule.date_range.includes_date_inclusive(*d)) {
+        for item in schedule.items.iter().filter(|i| i.includes_date(date)) {
+            let notes: Vec<_> = item.notes_for_date(date).collect();
+            events.push(IcalEvent::for_sailing(
+                schedule.terminal_pair,
+                date,
+                &item.sailing,
+                &notes,
+                &schedule.source_url,
+                schedule.refreshed_at,
+            ));
+        }
+    }
+    events
+}
+
+pub fn terminal_pair_ical_events<'a, I>(
+    schedules: I,
+    terminal_pair: TerminalPair,
+    date_range: DateRange,
+) -> Vec<IcalEvent>
+where
+    I: IntoIterator<Item = &'a Schedule>,
+{
+    let mut events: Vec<_> = schedules
+        .into_iter()
+        .filter(|s| s.terminal_pair == terminal_pair)
+        .flat_map(|s| schedule_ical_events(s, date_range))
+        .collect();
+    events.sort_unstable_by(|a, b| a.start.cmp(&b.start).then_with(|| a.uid.cmp(&b.uid)));
+    events
+}
+
+pub fn area_pair_ical_events<'a, I>(schedules: I, area_pair: AreaPair, date_range: DateRange) -> Vec<IcalEvent>
+where
+    I: IntoIterator<Item = &'a Schedule>,
+{
+    let terminal_pairs = AREA_PAIR_TERMINAL_PAIRS.get(&area_pair).cloned().unwrap_or_default();
+    let mut events: Vec<_> = schedules
+        .into_iter()
+        .filter(|s| terminal_pairs.contains(&s.terminal_pair))
+        .flat_map(|s| schedule_ical_events(s, date_range))
+        .collect();
+    events.sort_unstable_by(|a, b| a.start.cmp(&b.start).then_with(|| a.uid.cmp(&b.uid)));
+    events
+}
+
+pub fn format_ical(calendar_name: &str, events: &[IcalEvent]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//borsboom.io//Schedules for BC Ferries//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_ical_text(calendar_name)),
+        format!("X-WR-TIMEZONE:{}", ICAL_TIMEZONE_ID),
+    ];
+    lines.extend(ICAL_VTIMEZONE.iter().map(|l| l.to_string()));
+    for event in events {
+        event.write_lines(&mut lines);
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|l| fold_ical_line(l)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_ical_text() -> Result<()> {
+        assert_eq!(escape_ical_text("Stops: A, B; C\nNote"), "Stops: A\\, B\\; C\\nNote");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold_ical_line() -> Result<()> {
+        let line = "DESCRIPTION:".to_string() + &"x".repeat(100);
+        let folded = fold_ical_line(&line);
+        let folded_lines: Vec<_> = folded.split("\r\n").collect();
+        assert_eq!(folded_lines.len(), 3);
+        assert_eq!(folded_lines[0].len(), ICAL_MAX_LINE_OCTETS);
+        assert!(folded_lines[1].starts_with(' '));
+        assert_eq!(folded_lines.concat().replace(' ', ""), line);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ical_event_for_sailing_crossing_midnight() -> Result<()> {
+        let sailing = Sailing {
+            depart_time: time!(23:30),
+            arrive_time: time!(00:45),
+            stops: vec![],
+            depart_day_offset: 0,
+            arrive_day_offset: 1,
+            vessel: Some("Queen of Cowichan".to_string()),
+        };
+        let event = IcalEvent::for_sailing(
+            TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") },
+            date!(2023 - 06 - 30),
+            &sailing,
+            &["Foot passengers only"],
+            ALL_SCHEDULES_URL,
+            datetime!(2023-06-01 12:00 UTC),
+        );
+        assert_eq!(event.uid, "20230630T233000-TSA-SWB@ferries.borsboom.io");
+        assert_eq!(event.end, (date!(2023 - 07 - 01), time!(00:45)));
+        Ok(())
+    }
+}
@@ -0,0 +1,492 @@
+use crate::constants::*;
+use crate::imports::*;
+use crate::registry::*;
+use crate::types::*;
+
+pub const DEFAULT_MIN_CONNECTION_TIME: Duration = Duration::minutes(15);
+pub const DEFAULT_MIN_TERMINAL_CHANGE_TIME: Duration = Duration::minutes(45);
+pub const DEFAULT_MAX_JOURNEY_LEGS: usize = 3;
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JourneyTime {
+    DepartAfter(Time),
+    ArriveBy(Time),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JourneyQuery {
+    pub area_pair: AreaPair,
+    pub date: Date,
+    pub time: JourneyTime,
+    pub min_connection_time: Duration,
+    // Changing terminals within an area (e.g. Long Harbour to Vesuvius Bay on Salt Spring) means driving between them
+    pub min_terminal_change_time: Duration,
+    pub max_legs: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct JourneyLeg {
+    // The route's schedules are shared rather than borrowed, so that planned journeys can be kept
+    schedules: Rc<Vec<Schedule>>,
+    schedule_index: usize,
+    item_index: usize,
+    /// Operating day the sailing belongs to, which is the day before the calendar date it departs for sailings just
+    /// after midnight
+    pub date: Date,
+    depart_minutes: i64,
+    arrive_minutes: i64,
+}
+
+/// Driving to a terminal in a neighbouring area (e.g. from Swartz Bay to Brentwood Bay) at the start or end of a
+/// journey
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct JourneyDrive {
+    pub to: Area,
+    pub duration: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct Journey {
+    pub depart_drive: Option<JourneyDrive>,
+    pub legs: Vec<JourneyLeg>,
+    pub arrive_drive: Option<JourneyDrive>,
+}
+
+struct JourneySearch<'a> {
+    query: &'a JourneyQuery,
+    legs_by_terminal: HashMap<Terminal, Vec<JourneyLeg>>,
+    results: Vec<Journey>,
+}
+
+fn time_minutes(time: Time) -> i64 {
+    time.hour() as i64 * 60 + time.minute() as i64
+}
+
+fn minutes_time(minutes: i64) -> Time {
+    Time::MIDNIGHT + Duration::minutes(minutes.rem_euclid(MINUTES_PER_DAY))
+}
+
+fn drive_minutes(drive: Option<JourneyDrive>) -> i64 {
+    drive.map(|d| d.duration.whole_minutes()).unwrap_or(0)
+}
+
+impl JourneyQuery {
+    pub fn new(area_pair: AreaPair, date: Date) -> JourneyQuery {
+        JourneyQuery {
+            area_pair,
+            date,
+            time: JourneyTime::DepartAfter(time!(00:00)),
+            min_connection_time: DEFAULT_MIN_CONNECTION_TIME,
+            min_terminal_change_time: DEFAULT_MIN_TERMINAL_CHANGE_TIME,
+            max_legs: DEFAULT_MAX_JOURNEY_LEGS,
+        }
+    }
+}
+
+impl JourneyLeg {
+    // `days` is the number of days after the journey date that the sailing departs
+    fn new(
+        schedules: &Rc<Vec<Schedule>>,
+        (schedule_index, item_index): (usize, usize),
+        date: Date,
+        days: i64,
+    ) -> JourneyLeg {
+        let sailing = &schedules[schedule_index].items[item_index].sailing;
+        let depart_minutes = days * MINUTES_PER_DAY + time_minutes(sailing.depart_time);
+        // Connections are planned by the clock, so this is the clock time even if the clocks change on the way
+        let arrive_minutes =
+            depart_minutes + (sailing.arrive_service_time() - sailing.depart_service_time()).whole_minutes();
+        JourneyLeg { schedules: schedules.clone(), schedule_index, item_index, date, depart_minutes, arrive_minutes }
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedules[self.schedule_index]
+    }
+
+    pub fn item(&self) -> &ScheduleItem {
+        &self.schedule().items[self.item_index]
+    }
+
+    pub fn terminal_pair(&self) -> TerminalPair {
+        self.schedule().terminal_pair
+    }
+
+    pub fn sailing(&self) -> &Sailing {
+        &self.item().sailing
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &str> {
+        self.item().notes_for_date(self.date)
+    }
+
+    /// Whether the sailing departs the day after the journey date, for a connection after midnight
+    pub fn departs_next_day(&self) -> bool {
+        self.depart_minutes >= MINUTES_PER_DAY
+    }
+}
+
+impl Journey {
+    pub fn depart_time(&self) -> Time {
+        minutes_time(self.depart_minutes())
+    }
+
+    pub fn arrive_time(&self) -> Time {
+        minutes_time(self.arrive_minutes())
+    }
+
+    pub fn arrives_next_day(&self) -> bool {
+        self.arrive_minutes() >= MINUTES_PER_DAY
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.arrive_minutes() - self.depart_minutes())
+    }
+
+    pub fn is_direct(&self) -> bool {
+        self.legs.len() == 1
+    }
+
+    pub fn connection_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.legs.windows(2).map(|w| Duration::minutes(w[1].depart_minutes - w[0].arrive_minutes))
+    }
+
+    fn depart_minutes(&self) -> i64 {
+        self.legs.first().expect("Expect journey to have legs").depart_minutes - drive_minutes(self.depart_drive)
+    }
+
+    fn arrive_minutes(&self) -> i64 {
+        self.legs.last().expect("Expect journey to have legs").arrive_minutes + drive_minutes(self.arrive_drive)
+    }
+
+    fn dominates(&self, other: &Journey) -> bool {
+        self.depart_minutes() >= other.depart_minutes()
+            && self.arrive_minutes() <= other.arrive_minutes()
+            && self.legs.len() <= other.legs.len()
+    }
+}
+
+impl<'a> JourneySearch<'a> {
+    fn new(query: &'a JourneyQuery, schedules_map: &SchedulesMap) -> JourneySearch<'a> {
+        let mut legs_by_terminal: HashMap<Terminal, Vec<JourneyLeg>> = HashMap::new();
+        // Journeys start on the date, but may connect with sailings after midnight
+        let dates: Vec<_> = iter::once(query.date).chain(query.date.next_day()).enumerate().collect();
+        for (terminal_pair, schedules) in schedules_map {
+            let legs = legs_by_terminal.entry(terminal_pair.from).or_default();
+            for (schedule_index, schedule) in schedules.iter().enumerate() {
+                for (item_index, item) in schedule.items.iter().enumerate() {
+                    for &(days, date) in &dates {
+                        let service_date = item
+                            .service_date_departing_on(date)
+                            .filter(|service_date| schedule.date_range.includes_date_inclusive(*service_date));
+                        if let Some(service_date) = service_date {
+                            legs.push(JourneyLeg::new(
+                                schedules,
+                                (schedule_index, item_index),
+                                service_date,
+                                days as i64,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        for legs in legs_by_terminal.values_mut() {
+            legs.sort_unstable_by_key(|l| (l.depart_minutes, l.arrive_minutes, l.terminal_pair()));
+        }
+        JourneySearch { query, legs_by_terminal, results: Vec::new() }
+    }
+
+    fn connection_time(
+        &self,
+        arrive_terminal: Option<Terminal>,
+        depart_terminal: Terminal,
+        drive_time: Option<Duration>,
+    ) -> i64 {
+        match (arrive_terminal, drive_time) {
+            (None, None) => 0,
+            (None, Some(drive_time)) => drive_time.whole_minutes(),
+            (Some(terminal), None) if terminal == depart_terminal => self.query.min_connection_time.whole_minutes(),
+            (Some(_), None) => self.query.min_terminal_change_time.whole_minutes(),
+            (Some(_), Some(drive_time)) => drive_time.whole_minutes() + self.query.min_connection_time.whole_minutes(),
+        }
+    }
+
+    // The sailings may leave from the area, or from a neighbouring area after driving there
+    fn search_from(
+        &mut self,
+        path: &mut Vec<JourneyLeg>,
+        depart_drive: Option<JourneyDrive>,
+        area: Area,
+        earliest_depart_minutes: i64,
+    ) {
+        let registry = Registry::get();
+        let arrive_terminal = path.last().map(|l| l.terminal_pair().to);
+        let depart_areas = iter::once((area, None)).chain(registry.drive_links_from(area).map(|(a, d)| (a, Some(d))));
+        let mut candidate_legs = Vec::new();
+        for (depart_area, drive_time) in depart_areas {
+            for terminal in AREA_TERMINALS.get(&depart_area).into_iter().flatten().sorted() {
+                let earliest_minutes =
+                    earliest_depart_minutes + self.connection_time(arrive_terminal, *terminal, drive_time);
+                candidate_legs.extend(
+                    self.legs_by_terminal
+                        .get(terminal)
+                        .into_iter()
+                        .flatten()
+                        .filter(|l| l.depart_minutes >= earliest_minutes)
+                        .filter(|l| arrive_terminal.is_some() || !l.departs_next_day())
+                        .map(|l| (l.clone(), drive_time)),
+                );
+            }
+        }
+        for (leg, drive_time) in candidate_legs {
+            let to_area = leg.terminal_pair().to.area();
+            let visits_area_again =
+                to_area == self.query.area_pair.from || path.iter().any(|l| l.terminal_pair().to.area() == to_area);
+            if visits_area_again {
+                continue;
+            }
+            let depart_drive = if path.is_empty() {
+                drive_time.map(|duration| JourneyDrive { to: leg.terminal_pair().from.area(), duration })
+            } else {
+                depart_drive
+            };
+            let arrive_minutes = leg.arrive_minutes;
+            path.push(leg);
+            if to_area == self.query.area_pair.to {
+                self.results.push(Journey { depart_drive, legs: path.clone(), arrive_drive: None });
+            } else {
+                if let Some(duration) = registry.drive_time(to_area, self.query.area_pair.to) {
+                    let arrive_drive = Some(JourneyDrive { to: self.query.area_pair.to, duration });
+                    self.results.push(Journey { depart_drive, legs: path.clone(), arrive_drive });
+                }
+                if path.len() < self.query.max_legs {
+                    self.search_from(path, depart_drive, to_area, arrive_minutes);
+                }
+            }
+            path.pop();
+        }
+    }
+
+    fn into_journeys(mut self) -> Vec<Journey> {
+        let earliest_depart_minutes = match self.query.time {
+            JourneyTime::DepartAfter(time) => time_minutes(time),
+            JourneyTime::ArriveBy(_) => 0,
+        };
+        self.search_from(&mut Vec::new(), None, self.query.area_pair.from, earliest_depart_minutes);
+        if let JourneyTime::ArriveBy(time) = self.query.time {
+            self.results.retain(|j| j.arrive_minutes() <= time_minutes(time));
+        }
+        self.results.sort_by_key(|j| (j.depart_minutes(), j.arrive_minutes(), j.legs.len()));
+        let results = &self.results;
+        // Only keep journeys that no other journey beats by leaving later, arriving earlier, or using fewer sailings
+        let mut journeys: Vec<_> = results
+            .iter()
+            .enumerate()
+            .filter(|(index, journey)| {
+                !results.iter().enumerate().any(|(other_index, other)| {
+                    other_index != *index
+                        && other.dominates(journey)
+                        && (!journey.dominates(other) || other_index < *index)
+                })
+            })
+            .map(|(_, journey)| journey.clone())
+            .collect();
+        journeys.sort_by_key(|j| (j.depart_minutes(), j.arrive_minutes()));
+        journeys
+    }
+}
+
+pub fn plan_journeys(query: &JourneyQuery, schedules_map: &SchedulesMap) -> Vec<Journey> {
+    JourneySearch::new(query, schedules_map).into_journeys()
+}
+
+// Adds the areas a short drive from any of the areas
+fn with_drive_links(mut areas: HashSet<Area>) -> HashSet<Area> {
+    let registry = Registry::get();
+    let linked: Vec<_> = areas.iter().flat_map(|&area| registry.drive_links_from(area).map(|(a, _)| a)).collect();
+    areas.extend(linked);
+    areas
+}
+
+/// Areas that can be reached from the area by taking at least one sailing, but that have no direct sailings
+pub fn connecting_areas(from: Area, max_legs: usize) -> HashSet<Area> {
+    let mut reached = HashSet::from([from]);
+    let mut frontier = with_drive_links(HashSet::from([from]));
+    for _ in 0..max_legs {
+        let arrived: HashSet<_> = ALL_AREA_PAIRS
+            .iter()
+            .filter(|ap| frontier.contains(&ap.from) && !reached.contains(&ap.to))
+            .map(|ap| ap.to)
+            .collect();
+        frontier = with_drive_links(arrived);
+        reached.extend(&frontier);
+    }
+    reached.into_iter().filter(|&to| to != from && !ALL_AREA_PAIRS.contains(&AreaPair { from, to })).collect()
+}
+
+// Number of sailings needed to get from the start to each area, where driving to a neighbouring area is free
+fn area_distances<F: Fn(&AreaPair) -> (Area, Area)>(start: Area, max_legs: usize, f: F) -> HashMap<Area, usize> {
+    let mut distances: HashMap<_, _> = with_drive_links(HashSet::from([start])).into_iter().map(|a| (a, 0)).collect();
+    for distance in 1..=max_legs {
+        let next: HashSet<_> = ALL_AREA_PAIRS
+            .iter()
+            .map(&f)
+            .filter(|(near, far)| distances.get(near) == Some(&(distance - 1)) && !distances.contains_key(far))
+            .map(|(_, far)| far)
+            .collect();
+        for area in with_drive_links(next) {
+            distances.entry(area).or_insert(distance);
+        }
+    }
+    distances
+}
+
+/// All terminal pairs whose sailings could be part of a journey between the areas
+pub fn journey_terminal_pairs(area_pair: AreaPair, max_legs: usize) -> HashSet<TerminalPair> {
+    let from_distances = area_distances(area_pair.from, max_legs, |ap| (ap.from, ap.to));
+    let to_distances = area_distances(area_pair.to, max_legs, |ap| (ap.to, ap.from));
+    ALL_TERMINAL_PAIRS
+        .iter()
+        .filter(|tp| match (from_distances.get(&tp.from.area()), to_distances.get(&tp.to.area())) {
+            (Some(from_distance), Some(to_distance)) => from_distance + 1 + to_distance <= max_legs,
+            _ => false,
+        })
+        .copied()
+        .collect()
+}
+
+pub fn is_reachable_area_pair(area_pair: AreaPair) -> bool {
+    ALL_AREA_PAIRS.contains(&area_pair)
+        || connecting_areas(area_pair.from, DEFAULT_MAX_JOURNEY_LEGS).contains(&area_pair.to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use crate::utils::*;
+
+    fn test_schedule(from: Terminal, to: Terminal, times: &[(Time, Time)]) -> Schedule {
+        ScheduleBuilder::new(TerminalPair { from, to })
+            .date_range(DateRange { from: date!(2023 - 01 - 01), to: date!(2023 - 12 - 31) })
+            .items(
+                times
+                    .iter()
+                    .map(|&(depart_time, arrive_time)| ScheduleItemBuilder::new(depart_time, arrive_time).build()),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_plan_journeys_with_transfer() -> Result<()> {
+        let schedules_map = into_schedules_map([
+            test_schedule(
+                Terminal::new("TSA"),
+                Terminal::new("SWB"),
+                &[(time!(07:00), time!(08:35)), (time!(09:00), time!(10:35))],
+            ),
+            test_schedule(
+                Terminal::new("SWB"),
+                Terminal::new("FUL"),
+                &[(time!(08:45), time!(09:20)), (time!(11:00), time!(11:35))],
+            ),
+        ]);
+        let query = JourneyQuery::new(
+            AreaPair { from: Area::new("Vancouver"), to: Area::new("SaltSpring") },
+            date!(2023 - 06 - 01),
+        );
+        let journeys = plan_journeys(&query, &schedules_map);
+        assert_eq!(
+            journeys.iter().map(|j| (j.depart_time(), j.arrive_time())).collect::<Vec<_>>(),
+            vec![(time!(09:00), time!(11:35))]
+        );
+        let arrive_by_query = JourneyQuery { time: JourneyTime::ArriveBy(time!(11:00)), ..query };
+        assert!(plan_journeys(&arrive_by_query, &schedules_map).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_journeys_after_midnight() -> Result<()> {
+        let schedules_map = into_schedules_map([
+            ScheduleBuilder::new(TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") })
+                .item(ScheduleItemBuilder::new(time!(23:00), time!(00:35)).arrive_day_offset(1).build())
+                .build(),
+            ScheduleBuilder::new(TerminalPair { from: Terminal::new("SWB"), to: Terminal::new("FUL") })
+                .item(ScheduleItemBuilder::new(time!(00:50), time!(01:25)).day_offset(1).build())
+                .build(),
+        ]);
+        let query = JourneyQuery::new(
+            AreaPair { from: Area::new("Vancouver"), to: Area::new("SaltSpring") },
+            date!(2023 - 06 - 10),
+        );
+        let journeys = plan_journeys(&query, &schedules_map);
+        assert_eq!(
+            journeys
+                .iter()
+                .map(|j| (j.depart_time(), j.arrive_time(), j.arrives_next_day(), j.legs[1].departs_next_day()))
+                .collect::<Vec<_>>(),
+            vec![(time!(23:00), time!(01:25), true, true)]
+        );
+        assert_eq!(journeys[0].legs[1].date, date!(2023 - 06 - 10));
+        // A journey can't start with a sailing on the next day
+        let salt_spring_query =
+            JourneyQuery { area_pair: AreaPair { from: Area::new("Victoria"), to: Area::new("SaltSpring") }, ..query };
+        assert_eq!(
+            plan_journeys(&salt_spring_query, &schedules_map).iter().map(|j| j.depart_time()).collect::<Vec<_>>(),
+            vec![time!(00:50)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_connecting_areas() -> Result<()> {
+        let areas = connecting_areas(Area::new("Mayne"), DEFAULT_MAX_JOURNEY_LEGS);
+        assert!(areas.contains(&Area::new("Crofton")));
+        assert!(!areas.contains(&Area::new("Victoria")));
+        // Swartz Bay is a short drive from Brentwood Bay
+        assert!(areas.contains(&Area::new("Brentwood")));
+        let terminal_pairs = journey_terminal_pairs(AreaPair { from: Area::new("Mayne"), to: Area::new("Crofton") }, 2);
+        assert!(terminal_pairs.contains(&TerminalPair { from: Terminal::new("PVB"), to: Terminal::new("PLH") }));
+        assert!(terminal_pairs.contains(&TerminalPair { from: Terminal::new("VES"), to: Terminal::new("CFT") }));
+        assert!(!terminal_pairs.contains(&TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_journeys_with_drive() -> Result<()> {
+        let schedules_map = into_schedules_map([
+            test_schedule(Terminal::new("PVB"), Terminal::new("SWB"), &[(time!(08:00), time!(09:10))]),
+            test_schedule(Terminal::new("SWB"), Terminal::new("PVB"), &[(time!(11:00), time!(12:10))]),
+        ]);
+        let query =
+            JourneyQuery::new(AreaPair { from: Area::new("Mayne"), to: Area::new("Brentwood") }, date!(2023 - 06 - 01));
+        let journeys = plan_journeys(&query, &schedules_map);
+        assert_eq!(
+            journeys.iter().map(|j| (j.depart_time(), j.arrive_time(), j.arrive_drive)).collect::<Vec<_>>(),
+            vec![(
+                time!(08:00),
+                time!(09:35),
+                Some(JourneyDrive { to: Area::new("Brentwood"), duration: Duration::minutes(25) })
+            )]
+        );
+        let return_query = JourneyQuery {
+            area_pair: AreaPair { from: Area::new("Brentwood"), to: Area::new("Mayne") },
+            time: JourneyTime::DepartAfter(time!(10:30)),
+            ..query
+        };
+        let journeys = plan_journeys(&return_query, &schedules_map);
+        assert_eq!(
+            journeys.iter().map(|j| (j.depart_time(), j.arrive_time(), j.depart_drive)).collect::<Vec<_>>(),
+            vec![(
+                time!(10:35),
+                time!(12:10),
+                Some(JourneyDrive { to: Area::new("Victoria"), duration: Duration::minutes(25) })
+            )]
+        );
+        assert!(journey_terminal_pairs(query.area_pair, 1)
+            .contains(&TerminalPair { from: Terminal::new("PVB"), to: Terminal::new("SWB") }));
+        Ok(())
+    }
+}
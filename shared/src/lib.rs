@@ -1,4 +1,16 @@
 pub mod constants;
+pub mod data_format;
+pub mod date_set;
+pub mod fares;
+pub mod holidays;
+pub mod ical;
 pub mod imports;
+pub mod journeys;
+pub mod live;
+pub mod registry;
+pub mod sailings;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 pub mod types;
 pub mod utils;
+pub mod validation;
@@ -0,0 +1,179 @@
+use crate::data_format::*;
+use crate::imports::*;
+use crate::sailings::*;
+use crate::types::*;
+use crate::utils::*;
+
+/// Version of the live departures JSON format written by the scraper
+pub const LIVE_FORMAT_VERSION: u32 = 1;
+
+/// Live departures data file, alongside the schedules JSON
+pub const LIVE_FILENAME: &str = "live.json";
+
+/// Current status of a sailing from the departures pages. The route, departure date and scheduled departure time
+/// identify the scheduled `Sailing` it is for.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LiveSailing {
+    pub terminal_pair: TerminalPair,
+    /// Calendar date the sailing departs (not its operating day, for sailings just after midnight)
+    pub depart_date: Date,
+    pub scheduled_depart_time: Time,
+    /// Actual departure time once the sailing has departed, or else the expected departure time if the page gives one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub depart_time: Option<Time>,
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub departed: bool,
+    /// Actual arrival time once the sailing has arrived, or else the estimated arrival time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub arrive_time: Option<Time>,
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub arrived: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub vessel: Option<String>,
+    /// Percentage of the vehicle deck space that is still available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub deck_space_percent: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LiveData {
+    pub format_version: u32,
+    pub generated_at: OffsetDateTime,
+    pub sailings: Vec<LiveSailing>,
+}
+
+impl LiveSailing {
+    /// How much later than scheduled the sailing departed (or is expected to), if the page gives a time
+    pub fn delay(&self) -> Option<Duration> {
+        self.depart_time.map(|depart_time| {
+            let scheduled = vancouver_datetime(self.depart_date, self.scheduled_depart_time);
+            let delay = vancouver_datetime(self.depart_date, depart_time) - scheduled;
+            // Sailings don't leave hours early, so a much earlier time is a delay past midnight
+            if delay < -Duration::hours(12) {
+                match self.depart_date.next_day() {
+                    Some(next_date) => vancouver_datetime(next_date, depart_time) - scheduled,
+                    None => delay,
+                }
+            } else {
+                delay
+            }
+        })
+    }
+
+    pub fn is_for(&self, sailing: &SailingWithNotes) -> bool {
+        self.terminal_pair == sailing.terminal_pair
+            && self.depart_date == sailing.sailing.depart_date(sailing.service_date)
+            && self.scheduled_depart_time == sailing.sailing.depart_time
+    }
+}
+
+impl LiveData {
+    pub fn new(sailings: Vec<LiveSailing>, generated_at: OffsetDateTime) -> LiveData {
+        LiveData { format_version: LIVE_FORMAT_VERSION, generated_at, sailings }
+    }
+
+    pub fn from_json_str(json: &str) -> Result<LiveData> {
+        let inner = || {
+            let value = serde_json::from_str(json).context("Failed to parse live departures JSON")?;
+            check_format_version(&value, "Live departures", LIVE_FORMAT_VERSION)?;
+            let data: LiveData = serde_json::from_value(value).context("Failed to deserialize live departures")?;
+            for sailing in &data.sailings {
+                sailing.terminal_pair.ensure_known()?;
+            }
+            Ok(data) as Result<_>
+        };
+        inner().context("Failed to read live departures data")
+    }
+
+    pub fn find(&self, sailing: &SailingWithNotes) -> Option<&LiveSailing> {
+        self.sailings.iter().find(|s| s.is_for(sailing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_live_sailing() -> Result<()> {
+        let terminal_pair = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") };
+        let live_sailing = |depart_date, scheduled_depart_time, depart_time| LiveSailing {
+            terminal_pair,
+            depart_date,
+            scheduled_depart_time,
+            depart_time,
+            departed: depart_time.is_some(),
+            arrive_time: None,
+            arrived: false,
+            cancelled: false,
+            vessel: None,
+            deck_space_percent: None,
+        };
+        let live_data = LiveData::from_json_str(&serde_json::to_string(&LiveData::new(
+            vec![
+                live_sailing(date!(2024 - 07 - 02), time!(00:30), None),
+                live_sailing(date!(2024 - 07 - 02), time!(07:00), Some(time!(07:12))),
+            ],
+            OffsetDateTime::UNIX_EPOCH,
+        ))?)?;
+        let sailing = |depart_time, depart_day_offset, service_date| SailingWithNotes {
+            sailing: Sailing {
+                depart_time,
+                arrive_time: depart_time + Duration::minutes(95),
+                stops: vec![],
+                depart_day_offset,
+                arrive_day_offset: depart_day_offset,
+                vessel: None,
+            },
+            notes: vec![],
+            service_date,
+            terminal_pair,
+            cancellations: vec![],
+        };
+        let found = live_data.find(&sailing(time!(07:00), 0, date!(2024 - 07 - 02))).context("Expect live sailing")?;
+        assert_eq!(found.delay(), Some(Duration::minutes(12)));
+        // The sailing after midnight belongs to the previous day's schedule
+        assert!(live_data.find(&sailing(time!(00:30), 1, date!(2024 - 07 - 01))).is_some());
+        assert!(live_data.find(&sailing(time!(00:30), 0, date!(2024 - 07 - 01))).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_delay_past_midnight() -> Result<()> {
+        let live_sailing = |scheduled_depart_time, depart_time| LiveSailing {
+            terminal_pair: TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") },
+            depart_date: date!(2024 - 07 - 02),
+            scheduled_depart_time,
+            depart_time: Some(depart_time),
+            departed: true,
+            arrive_time: None,
+            arrived: false,
+            cancelled: false,
+            vessel: None,
+            deck_space_percent: None,
+        };
+        assert_eq!(live_sailing(time!(23:50), time!(00:15)).delay(), Some(Duration::minutes(25)));
+        assert_eq!(live_sailing(time!(07:00), time!(06:58)).delay(), Some(Duration::minutes(-2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_newer_live_format_version() -> Result<()> {
+        let json = format!(
+            r#"{{"format_version": {}, "generated_at": "2024-07-02T00:00:00Z", "sailings": []}}"#,
+            LIVE_FORMAT_VERSION + 1
+        );
+        let err = LiveData::from_json_str(&json).expect_err("Expect newer format version to fail");
+        assert!(err.downcast_ref::<NewerFormatVersionError>().is_some());
+        Ok(())
+    }
+}
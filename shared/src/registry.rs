@@ -0,0 +1,222 @@
+use crate::imports::*;
+use crate::types::*;
+
+const EMBEDDED_REGISTRY_JSON: &str = include_str!("../data/registry.json");
+
+static REGISTRY: OnceCell<Registry> = OnceCell::new();
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AreaInfo {
+    pub area: Area,
+    pub long_name: String,
+    pub short_name: String,
+    /// Alternative names accepted when parsing an area (e.g. from an old URL query)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TerminalInfo {
+    pub terminal: Terminal,
+    pub name: String,
+    pub area: Area,
+    /// Latitude and longitude
+    pub coordinates: (f64, f64),
+    /// Lowercase names used for the terminal in the stops column of schedule pages
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub stop_names: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RouteInfo {
+    pub number: u32,
+    pub name: String,
+    /// Sailings on the route may go between any two of these terminals
+    pub terminals: Vec<Terminal>,
+    #[serde(default)]
+    pub reservable: bool,
+}
+
+/// Areas whose terminals are a short drive apart, so journeys may change between them
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DriveLink {
+    pub areas: [Area; 2],
+    pub minutes: i64,
+}
+
+/// Metadata about areas, terminals and routes, loaded from `shared/data/registry.json` (embedded at build time)
+/// unless an override is installed before first use
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Registry {
+    pub areas: Vec<AreaInfo>,
+    pub terminals: Vec<TerminalInfo>,
+    pub routes: Vec<RouteInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub drive_links: Vec<DriveLink>,
+}
+
+impl Registry {
+    pub fn get() -> &'static Registry {
+        REGISTRY.get_or_init(|| {
+            Registry::from_json_str(EMBEDDED_REGISTRY_JSON).expect("Expect embedded registry to be valid")
+        })
+    }
+
+    /// Replace the embedded registry; must be called before anything looks up area or terminal metadata
+    pub fn set_override(registry: Registry) -> Result<()> {
+        REGISTRY.set(registry).map_err(|_| anyhow!("Registry has already been initialized"))
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Registry> {
+        let inner = || {
+            let registry: Registry = serde_json::from_str(json)?;
+            registry.validate()?;
+            Ok(registry) as Result<_>
+        };
+        inner().context("Failed to read registry")
+    }
+
+    // Areas and terminals are only names until they're found here, so check that everything refers to known ones
+    fn validate(&self) -> Result<()> {
+        ensure!(!self.areas.is_empty() && !self.terminals.is_empty(), "Expect areas and terminals");
+        ensure!(!self.routes.is_empty(), "Expect routes");
+        let has_area = |area: Area| self.areas.iter().any(|a| a.area == area);
+        let has_terminal = |terminal: Terminal| self.terminals.iter().any(|t| t.terminal == terminal);
+        for area_info in &self.areas {
+            let count = self.areas.iter().filter(|a| a.area == area_info.area).count();
+            ensure!(count == 1, "Expect exactly one entry for area {}, found {}", area_info.area, count);
+            for alias in &area_info.aliases {
+                ensure!(!has_area(Area::new(alias)), "Expect alias {:?} to not be an area name", alias);
+                let count = self.areas.iter().filter(|a| a.aliases.contains(alias)).count();
+                ensure!(count == 1, "Expect alias {:?} to be for only one area", alias);
+            }
+        }
+        for terminal_info in &self.terminals {
+            let terminal = terminal_info.terminal;
+            let count = self.terminals.iter().filter(|t| t.terminal == terminal).count();
+            ensure!(count == 1, "Expect exactly one entry for terminal {}, found {}", terminal, count);
+            ensure!(has_area(terminal_info.area), "Unknown area for terminal {}: {}", terminal, terminal_info.area);
+        }
+        for route in &self.routes {
+            ensure!(route.terminals.len() >= 2, "Expect route {} to have at least two terminals", route.number);
+            if let Some(terminal) = route.terminals.iter().find(|t| !has_terminal(**t)) {
+                bail!("Unknown terminal for route {}: {}", route.number, terminal);
+            }
+            ensure!(
+                self.routes.iter().filter(|r| r.number == route.number).count() == 1,
+                "Expect route {} to appear only once",
+                route.number
+            );
+        }
+        for drive_link in &self.drive_links {
+            if let Some(area) = drive_link.areas.iter().find(|a| !has_area(**a)) {
+                bail!("Unknown area for drive link: {}", area);
+            }
+            ensure!(
+                drive_link.areas[0] != drive_link.areas[1],
+                "Expect drive link to be between different areas: {}",
+                drive_link.areas[0]
+            );
+        }
+        Ok(())
+    }
+
+    pub fn area(&self, area: Area) -> &AreaInfo {
+        self.areas.iter().find(|a| a.area == area).expect("Expect registry to have every area")
+    }
+
+    pub fn terminal(&self, terminal: Terminal) -> &TerminalInfo {
+        self.terminals.iter().find(|t| t.terminal == terminal).expect("Expect registry to have every terminal")
+    }
+
+    pub fn route(&self, number: u32) -> Option<&RouteInfo> {
+        self.routes.iter().find(|r| r.number == number)
+    }
+
+    pub fn area_from_alias(&self, alias: &str) -> Option<Area> {
+        self.areas.iter().find(|a| a.aliases.iter().any(|x| x == alias)).map(|a| a.area)
+    }
+
+    pub fn terminal_from_stop_name(&self, stop_name: &str) -> Option<Terminal> {
+        let stop_name = stop_name.to_lowercase();
+        self.terminals.iter().find(|t| t.stop_names.contains(&stop_name)).map(|t| t.terminal)
+    }
+
+    /// Time to drive between the areas' terminals, if they are linked (in either direction)
+    pub fn drive_time(&self, from: Area, to: Area) -> Option<Duration> {
+        self.drive_links
+            .iter()
+            .find(|l| l.areas == [from, to] || l.areas == [to, from])
+            .map(|l| Duration::minutes(l.minutes))
+    }
+
+    /// Areas linked to the area, with the time to drive to each
+    pub fn drive_links_from(&self, area: Area) -> impl Iterator<Item = (Area, Duration)> + '_ {
+        self.drive_links.iter().filter_map(move |l| match l.areas {
+            [a, b] if a == area => Some((b, Duration::minutes(l.minutes))),
+            [a, b] if b == area => Some((a, Duration::minutes(l.minutes))),
+            _ => None,
+        })
+    }
+
+    pub fn routes_with_terminal(&self, terminal: Terminal) -> impl Iterator<Item = &RouteInfo> {
+        self.routes.iter().filter(move |r| r.terminals.contains(&terminal))
+    }
+
+    pub fn routes_with_terminals(&self, terminal_pair: TerminalPair) -> impl Iterator<Item = &RouteInfo> {
+        self.routes
+            .iter()
+            .filter(move |r| r.terminals.contains(&terminal_pair.from) && r.terminals.contains(&terminal_pair.to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_registry() -> Result<()> {
+        let registry = Registry::from_json_str(EMBEDDED_REGISTRY_JSON)?;
+        assert_eq!(registry.terminal_from_stop_name("Victoria (Swartz Bay)"), Some(Terminal::new("SWB")));
+        assert_eq!(registry.terminal_from_stop_name("Tsawwassen"), None);
+        assert_eq!(registry.area_from_alias("PLH"), Some(Area::new("SaltSpring")));
+        assert!(registry.route(9).map(|r| r.reservable).unwrap_or(false));
+        assert_eq!(registry.drive_time(Area::new("Brentwood"), Area::new("Victoria")), Some(Duration::minutes(25)));
+        assert_eq!(registry.drive_time(Area::new("Brentwood"), Area::new("MillBay")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_incomplete_registry() -> Result<()> {
+        let json = r#"{"areas": [], "terminals": [], "routes": []}"#;
+        assert!(Registry::from_json_str(json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unknown_registry_references() -> Result<()> {
+        let json = r#"{
+            "areas": [{ "area": "Victoria", "long_name": "Victoria", "short_name": "Victoria" }],
+            "terminals": [
+                { "terminal": "SWB", "name": "Swartz Bay", "area": "Victoria", "coordinates": [0, 0] },
+                { "terminal": "TSA", "name": "Tsawwassen", "area": "Vancouver", "coordinates": [0, 0] }
+            ],
+            "routes": [{ "number": 1, "name": "Tsawwassen/Swartz Bay", "terminals": ["TSA", "SWB"] }]
+        }"#;
+        let err = Registry::from_json_str(json).expect_err("Expect unknown area to fail");
+        assert!(format!("{:#}", err).contains("Unknown area for terminal TSA: Vancouver"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_area_alias() -> Result<()> {
+        assert_eq!(Area::new("SWB").resolve(), Some(Area::new("Victoria")));
+        assert_eq!(Area::new("Victoria").resolve(), Some(Area::new("Victoria")));
+        assert_eq!(Area::new("Atlantis").resolve(), None);
+        assert!("Atlantis".parse::<Area>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use crate::constants::*;
+use crate::imports::*;
+use crate::types::*;
+use crate::utils::*;
+
+/// A sailing on a particular operating day, with the notes that apply that day
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SailingWithNotes {
+    pub sailing: Sailing,
+    pub notes: Vec<String>,
+    /// Operating day the sailing belongs to, which differs from the calendar date for sailings just after midnight
+    pub service_date: Date,
+    pub terminal_pair: TerminalPair,
+    /// Text of the notices that cancelled the sailing, if it is cancelled
+    pub cancellations: Vec<String>,
+}
+
+impl SailingWithNotes {
+    pub fn depart_datetime(&self) -> OffsetDateTime {
+        self.sailing.depart_datetime(self.service_date)
+    }
+
+    pub fn arrive_datetime(&self) -> OffsetDateTime {
+        self.sailing.arrive_datetime(self.service_date)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        !self.cancellations.is_empty()
+    }
+}
+
+/// Sailings in the schedule that depart on the calendar date, including those that were cancelled, in no particular
+/// order
+pub fn schedule_sailings_for_date(schedule: &Schedule, date: Date) -> Vec<SailingWithNotes> {
+    let cancelled_items = schedule.items.iter().filter_map(|item| {
+        item.cancelled_service_date_departing_on(date)
+            .filter(|service_date| schedule.date_range.includes_date_inclusive(*service_date))
+            .map(|service_date| (item, service_date))
+    });
+    schedule
+        .items_departing_on(date)
+        .chain(cancelled_items)
+        .map(|(item, service_date)| SailingWithNotes {
+            sailing: item.sailing.clone(),
+            notes: item.notes_for_date(service_date).map(String::from).collect(),
+            service_date,
+            terminal_pair: schedule.terminal_pair,
+            cancellations: item.cancellations_for_date(service_date).map(String::from).collect(),
+        })
+        .collect()
+}
+
+// Sailings just after midnight may belong to the previous day's schedule, so this looks at all the schedules
+fn sailings_departing_on(schedules: &[Schedule], date: Date) -> Vec<SailingWithNotes> {
+    schedules.iter().flat_map(|sched| schedule_sailings_for_date(sched, date)).sorted().collect()
+}
+
+/// The schedule in effect on the date, and the sailings from all the schedules that depart that day in departure
+/// order, or `None` if no schedule covers the date
+pub fn schedules_sailings_for_date(schedules: &[Schedule], date: Date) -> Option<(&Schedule, Vec<SailingWithNotes>)> {
+    schedules
+        .iter()
+        .find(|sched| sched.date_range.includes_date_inclusive(date))
+        .map(|sched| (sched, sailings_departing_on(schedules, date)))
+}
+
+pub fn terminal_pair_sailings_for_date(
+    terminal_pair: TerminalPair,
+    date: Date,
+    schedules_map: &SchedulesMap,
+) -> Option<(&Schedule, Vec<SailingWithNotes>)> {
+    schedules_map.get(&terminal_pair).and_then(|schedules| schedules_sailings_for_date(schedules, date))
+}
+
+/// Sailings on the date for each route between the areas, with the busiest routes first, or `None` if no schedule
+/// covers the date
+pub fn area_sailings_for_date(
+    area_pair: AreaPair,
+    date: Date,
+    schedules_map: &SchedulesMap,
+) -> Option<Vec<(&Schedule, Vec<SailingWithNotes>)>> {
+    let mut area_schedules_vec = AREA_PAIR_TERMINAL_PAIRS
+        .get(&area_pair)
+        .map(|tps| tps.iter().filter_map(|&tp| terminal_pair_sailings_for_date(tp, date, schedules_map)).collect())
+        .unwrap_or_else(Vec::new);
+    (!area_schedules_vec.is_empty()).then(|| {
+        area_schedules_vec.sort_unstable_by(|(sa, va), (sb, vb)| {
+            va.len().cmp(&vb.len()).reverse().then_with(|| sa.terminal_pair.cmp(&sb.terminal_pair))
+        });
+        area_schedules_vec
+            .into_iter()
+            .filter(|(s, v)| !v.is_empty() || s.alerts.iter().any(|a| a.applies_on(date)))
+            .collect()
+    })
+}
+
+// Calendar dates from the date of `from` until the last date any of the schedules has a sailing departing
+fn departure_dates(schedules: &[Schedule], from: OffsetDateTime) -> impl Iterator<Item = Date> {
+    let last_date = schedules
+        .iter()
+        .filter_map(|sched| {
+            let max_day_offset = sched.items.iter().map(|item| item.sailing.depart_day_offset).max()?;
+            Some(sched.date_range.to + Duration::days(max_day_offset.into()))
+        })
+        .max();
+    iter::successors(Some(vancouver_date(from)), |date| date.next_day())
+        .take_while(move |date| last_date.map(|last| *date <= last).unwrap_or(false))
+}
+
+/// The next `count` sailings on the route that aren't cancelled, departing at or after the time, continuing past
+/// midnight into the following days for as long as there are schedules
+pub fn next_sailings(
+    terminal_pair: TerminalPair,
+    after: OffsetDateTime,
+    count: usize,
+    schedules_map: &SchedulesMap,
+) -> Vec<SailingWithNotes> {
+    let schedules = match schedules_map.get(&terminal_pair) {
+        Some(schedules) => schedules,
+        None => return Vec::new(),
+    };
+    departure_dates(schedules, after)
+        .flat_map(|date| sailings_departing_on(schedules, date))
+        .filter(|s| !s.is_cancelled() && s.depart_datetime() >= after)
+        .take(count)
+        .collect()
+}
+
+/// Sailings on any of the routes that aren't cancelled, departing at or after `from` and before `to`, in departure
+/// order
+pub fn sailings_in_window<I: IntoIterator<Item = TerminalPair>>(
+    terminal_pairs: I,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    schedules_map: &SchedulesMap,
+) -> Vec<SailingWithNotes> {
+    let last_date = vancouver_date(to);
+    terminal_pairs
+        .into_iter()
+        .filter_map(|tp| schedules_map.get(&tp))
+        .flat_map(|schedules| {
+            departure_dates(schedules, from)
+                .take_while(|date| *date <= last_date)
+                .flat_map(|date| sailings_departing_on(schedules, date))
+                .filter(|s| !s.is_cancelled() && s.depart_datetime() >= from && s.depart_datetime() < to)
+        })
+        .sorted_by_key(|s| (s.depart_datetime(), s.terminal_pair))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date_set::*;
+    use crate::test_support::*;
+
+    fn depart_datetimes(sailings: &[SailingWithNotes]) -> Vec<(OffsetDateTime, TerminalPair)> {
+        sailings.iter().map(|s| (s.depart_datetime(), s.terminal_pair)).collect()
+    }
+
+    #[test]
+    fn test_sailing_queries() -> Result<()> {
+        let tsa_swb = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") };
+        let hsb_nan = TerminalPair { from: Terminal::new("HSB"), to: Terminal::new("NAN") };
+        let schedules_map = into_schedules_map([
+            ScheduleBuilder::new(tsa_swb)
+                .items([
+                    ScheduleItemBuilder::new(time!(07:00), time!(08:35)).build(),
+                    ScheduleItemBuilder::new(time!(21:00), time!(22:35)).build(),
+                    ScheduleItemBuilder::new(time!(00:30), time!(02:05)).day_offset(1).build(),
+                ])
+                .build(),
+            ScheduleBuilder::new(hsb_nan)
+                .items([
+                    ScheduleItemBuilder::new(time!(06:15), time!(07:55)).build(),
+                    ScheduleItemBuilder::new(time!(22:00), time!(23:40)).build(),
+                ])
+                .build(),
+        ]);
+        let (_, sailings) = terminal_pair_sailings_for_date(tsa_swb, date!(2023 - 06 - 10), &schedules_map)
+            .context("Expect sailings on date")?;
+        assert_eq!(
+            sailings.iter().map(|s| (s.sailing.depart_time, s.service_date)).collect::<Vec<_>>(),
+            vec![
+                (time!(00:30), date!(2023 - 06 - 09)),
+                (time!(07:00), date!(2023 - 06 - 10)),
+                (time!(21:00), date!(2023 - 06 - 10)),
+            ]
+        );
+        assert_eq!(
+            depart_datetimes(&next_sailings(tsa_swb, datetime!(2023 - 06 - 10 20:00 -7), 3, &schedules_map)),
+            vec![
+                (datetime!(2023 - 06 - 10 21:00 -7), tsa_swb),
+                (datetime!(2023 - 06 - 11 00:30 -7), tsa_swb),
+                (datetime!(2023 - 06 - 11 07:00 -7), tsa_swb),
+            ]
+        );
+        // The last sailing belongs to the last day of the schedule, but departs the day after
+        assert_eq!(
+            depart_datetimes(&next_sailings(tsa_swb, datetime!(2023 - 06 - 30 22:00 -7), 3, &schedules_map)),
+            vec![(datetime!(2023 - 07 - 01 00:30 -7), tsa_swb)]
+        );
+        assert_eq!(
+            depart_datetimes(&sailings_in_window(
+                [tsa_swb, hsb_nan],
+                datetime!(2023 - 06 - 10 21:00 -7),
+                datetime!(2023 - 06 - 11 07:00 -7),
+                &schedules_map
+            )),
+            vec![
+                (datetime!(2023 - 06 - 10 21:00 -7), tsa_swb),
+                (datetime!(2023 - 06 - 10 22:00 -7), hsb_nan),
+                (datetime!(2023 - 06 - 11 00:30 -7), tsa_swb),
+                (datetime!(2023 - 06 - 11 06:15 -7), hsb_nan),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancelled_sailings() -> Result<()> {
+        let tsa_swb = TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("SWB") };
+        let mut schedule = ScheduleBuilder::new(tsa_swb)
+            .items([
+                ScheduleItemBuilder::new(time!(07:00), time!(08:35)).build(),
+                ScheduleItemBuilder::new(time!(09:00), time!(10:35)).build(),
+            ])
+            .build();
+        let dates: DateSet = [date!(2023 - 06 - 10), date!(2023 - 07 - 10)].into_iter().collect();
+        assert!(schedule.items[0].cancel("The 7:00 am sailing is cancelled on Jun 10.", &dates)?);
+        // Cancelling again changes nothing, since the sailing no longer runs that day
+        assert!(!schedule.items[0].cancel("The 7:00 am sailing is cancelled on Jun 10.", &dates)?);
+        assert!(!schedule.items[0].includes_date(date!(2023 - 06 - 10)));
+        assert!(schedule.items[0].includes_date(date!(2023 - 06 - 17)));
+        let schedules_map = into_schedules_map([schedule]);
+        let (_, sailings) = terminal_pair_sailings_for_date(tsa_swb, date!(2023 - 06 - 10), &schedules_map)
+            .context("Expect sailings on date")?;
+        assert_eq!(
+            sailings.iter().map(|s| (s.sailing.depart_time, s.cancellations.clone())).collect::<Vec<_>>(),
+            vec![
+                (time!(07:00), vec!["The 7:00 am sailing is cancelled on Jun 10.".to_string()]),
+                (time!(09:00), vec![]),
+            ]
+        );
+        assert_eq!(
+            depart_datetimes(&next_sailings(tsa_swb, datetime!(2023 - 06 - 10 06:00 -7), 2, &schedules_map)),
+            vec![(datetime!(2023 - 06 - 10 09:00 -7), tsa_swb), (datetime!(2023 - 06 - 11 07:00 -7), tsa_swb)]
+        );
+        Ok(())
+    }
+}
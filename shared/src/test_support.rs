@@ -0,0 +1,111 @@
+use crate::imports::*;
+use crate::types::*;
+
+/// Builds a `ScheduleItem` for tests: a sailing with no stops on every day of the week unless told otherwise
+pub struct ScheduleItemBuilder {
+    item: ScheduleItem,
+}
+
+/// Builds a `Schedule` for tests, for June 2023 unless told otherwise
+pub struct ScheduleBuilder {
+    schedule: Schedule,
+}
+
+impl ScheduleItemBuilder {
+    pub fn new(depart_time: Time, arrive_time: Time) -> ScheduleItemBuilder {
+        ScheduleItemBuilder {
+            item: ScheduleItem {
+                sailing: Sailing {
+                    depart_time,
+                    arrive_time,
+                    stops: vec![],
+                    depart_day_offset: 0,
+                    arrive_day_offset: 0,
+                    vessel: None,
+                },
+                weekdays: iter::successors(Some(Weekday::Monday), |w| Some(w.next()))
+                    .take(7)
+                    .map(|w| (w, DateRestriction::All))
+                    .collect(),
+                notes: HashMap::new(),
+                cancellations: HashMap::new(),
+            },
+        }
+    }
+
+    /// Days after the operating day that the sailing departs and arrives
+    pub fn day_offset(mut self, day_offset: u8) -> ScheduleItemBuilder {
+        self.item.sailing.depart_day_offset = day_offset;
+        self.item.sailing.arrive_day_offset = day_offset;
+        self
+    }
+
+    pub fn arrive_day_offset(mut self, arrive_day_offset: u8) -> ScheduleItemBuilder {
+        self.item.sailing.arrive_day_offset = arrive_day_offset;
+        self
+    }
+
+    pub fn stops(mut self, stops: Vec<Stop>) -> ScheduleItemBuilder {
+        self.item.sailing.stops = stops;
+        self
+    }
+
+    pub fn weekdays(mut self, weekdays: &[Weekday]) -> ScheduleItemBuilder {
+        self.item.weekdays = weekdays.iter().map(|w| (*w, DateRestriction::All)).collect();
+        self
+    }
+
+    pub fn weekday(mut self, weekday: Weekday, date_restriction: DateRestriction) -> ScheduleItemBuilder {
+        self.item.weekdays.insert(weekday, date_restriction);
+        self
+    }
+
+    pub fn note(mut self, note: &str, date_restriction: DateRestriction) -> ScheduleItemBuilder {
+        self.item.notes.insert(Cow::from(note.to_string()), date_restriction);
+        self
+    }
+
+    pub fn build(self) -> ScheduleItem {
+        self.item
+    }
+}
+
+impl ScheduleBuilder {
+    pub fn new(terminal_pair: TerminalPair) -> ScheduleBuilder {
+        ScheduleBuilder {
+            schedule: Schedule {
+                terminal_pair,
+                date_range: DateRange { from: date!(2023 - 06 - 01), to: date!(2023 - 06 - 30) },
+                items: vec![],
+                source_url: String::new(),
+                refreshed_at: OffsetDateTime::UNIX_EPOCH,
+                alerts: vec![],
+                notes_to_review: vec![],
+            },
+        }
+    }
+
+    pub fn date_range(mut self, date_range: DateRange) -> ScheduleBuilder {
+        self.schedule.date_range = date_range;
+        self
+    }
+
+    pub fn item(mut self, item: ScheduleItem) -> ScheduleBuilder {
+        self.schedule.items.push(item);
+        self
+    }
+
+    pub fn items<I: IntoIterator<Item = ScheduleItem>>(mut self, items: I) -> ScheduleBuilder {
+        self.schedule.items.extend(items);
+        self
+    }
+
+    pub fn note_to_review(mut self, note: &str) -> ScheduleBuilder {
+        self.schedule.notes_to_review.push(note.to_string());
+        self
+    }
+
+    pub fn build(self) -> Schedule {
+        self.schedule
+    }
+}
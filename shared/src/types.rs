@@ -1,55 +1,19 @@
 use crate::constants::*;
+use crate::date_set::*;
 use crate::imports::*;
+use crate::registry::*;
+use crate::utils::*;
 
 pub type TimeFormat = [time::format_description::FormatItem<'static>];
 
-#[derive(
-    Copy, Clone, Debug, Deserialize, Display, EnumString, Eq, EnumIter, Hash, Ord, PartialEq, PartialOrd, Serialize,
-)]
-pub enum Area {
-    Brentwood,
-    Chemainus,
-    Crofton,
-    // Gulf Islands terminals have aliases for backward compatibility when parsing URL query
-    #[serde(alias = "PSB")]
-    Galiano,
-    #[serde(alias = "PVB")]
-    Mayne,
-    MillBay,
-    #[serde(alias = "POB")]
-    Pender,
-    Penelakut,
-    #[serde(alias = "PLH")]
-    SaltSpring,
-    #[serde(alias = "PST")]
-    Saturna,
-    Thetis,
-    #[serde(alias = "TSA")]
-    Vancouver,
-    #[serde(alias = "SWB")]
-    Victoria,
-}
-
-#[derive(
-    Copy, Clone, Debug, Deserialize, Display, EnumString, Eq, EnumIter, Hash, Ord, PartialEq, PartialOrd, Serialize,
-)]
-pub enum Terminal {
-    BTW, // Brentwood Bay
-    CFT, // Crofton
-    CHM, // Chemainus
-    FUL, // Salt Spring Island (Fulford Harbour)
-    MIL, // Mill Bay
-    PEN, // Penelakut Island (Telegraph Harbour)
-    PLH, // Salt Spring Island (Long Harbour)
-    POB, // Pender Island (Otter Bay)
-    PSB, // Galiano Island (Sturdies Bay)
-    PST, // Saturna Island (Lyall Harbour)
-    PVB, // Mayne Island (Village Bay)
-    SWB, // Victoria (Swartz Bay)
-    THT, // Thetis Island (Preedy Harbour)
-    TSA, // Vancouver (Tsawwassen)
-    VES, // Salt Spring Island (Vesuvius Bay)
-}
+/// Area as named in the registry (e.g. "SaltSpring"). Deserializing doesn't look at the registry, so use
+/// `Area::resolve` for names from outside the data files, which may be aliases.
+#[derive(Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Area(&'static str);
+
+/// Terminal as coded in the registry (e.g. "SWB")
+#[derive(Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Terminal(&'static str);
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct AreaPair {
@@ -74,6 +38,12 @@ pub enum StopType {
 pub struct Stop {
     pub type_: StopType,
     pub terminal: Terminal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub arrive_time: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub depart_time: Option<Time>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -81,6 +51,19 @@ pub struct Sailing {
     pub depart_time: Time,
     pub arrive_time: Time,
     pub stops: Vec<Stop>,
+    /// Days after the operating day that the sailing departs (1 for sailings just after midnight that belong to the
+    /// previous day's schedule)
+    #[serde(skip_serializing_if = "is_zero")]
+    #[serde(default)]
+    pub depart_day_offset: u8,
+    /// Days after the operating day that the sailing arrives
+    #[serde(skip_serializing_if = "is_zero")]
+    #[serde(default)]
+    pub arrive_day_offset: u8,
+    /// Name of the vessel that runs the sailing (e.g. "Queen of Cumberland"), where the schedule lists it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub vessel: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -94,36 +77,45 @@ pub struct DateRange {
     pub to: Date,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum DateRestriction {
     All,
-    Only(HashSet<Date>),
-    Except(HashSet<Date>),
+    Only(DateSet),
+    Except(DateSet),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ScheduleItem {
     pub sailing: Sailing,
     pub weekdays: HashMap<Weekday, DateRestriction>,
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     #[serde(default)]
     pub notes: HashMap<Cow<'static, str>, DateRestriction>,
+    /// Dates the sailing was cancelled by a service notice, keyed by the notice text; these dates are also excluded
+    /// from `weekdays`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default)]
+    pub cancellations: HashMap<String, DateRestriction>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum AlertLevel {
     Info,
     Warning,
     Danger,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Alert {
     pub message: String,
     pub level: AlertLevel,
+    /// Dates the alert applies to, or `None` if it applies to every date of the schedule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub date_range: Option<DateRange>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Schedule {
     pub terminal_pair: TerminalPair,
     pub date_range: DateRange,
@@ -133,43 +125,41 @@ pub struct Schedule {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub alerts: Vec<Alert>,
+    /// Notes on the schedule page that weren't recognized, which are passed through to the sailings as is
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub notes_to_review: Vec<String>,
 }
 
+/// Schedules of each route; they're shared so that adding a route's schedules to a map doesn't copy the others
+pub type SchedulesMap = HashMap<TerminalPair, Rc<Vec<Schedule>>>;
+
 impl Area {
-    pub fn long_name(&self) -> &'static str {
-        match *self {
-            Area::Brentwood => "Brentwood",
-            Area::Chemainus => "Chemainus",
-            Area::Crofton => "Crofton",
-            Area::Galiano => "Galiano Island",
-            Area::Mayne => "Mayne Island",
-            Area::MillBay => "Mill Bay",
-            Area::Pender => "Pender Island",
-            Area::Penelakut => "Penelakut Island",
-            Area::SaltSpring => "Salt Spring Island",
-            Area::Saturna => "Saturna Island",
-            Area::Thetis => "Thetis Island",
-            Area::Vancouver => "Vancouver",
-            Area::Victoria => "Victoria",
+    pub fn new(name: &str) -> Area {
+        Area(intern(name))
+    }
+
+    /// Every area in the registry
+    pub fn iter() -> impl Iterator<Item = Area> {
+        Registry::get().areas.iter().map(|a| a.area)
+    }
+
+    /// The registry's area with this name or alias
+    pub fn resolve(&self) -> Option<Area> {
+        let registry = Registry::get();
+        if registry.areas.iter().any(|a| a.area == *self) {
+            Some(*self)
+        } else {
+            registry.area_from_alias(self.0)
         }
     }
 
+    pub fn long_name(&self) -> &'static str {
+        &Registry::get().area(*self).long_name
+    }
+
     pub fn short_name(&self) -> &'static str {
-        match *self {
-            Area::Brentwood => "Brentwood",
-            Area::Chemainus => "Chemainus",
-            Area::Crofton => "Crofton",
-            Area::Galiano => "Galiano",
-            Area::Mayne => "Mayne",
-            Area::MillBay => "Mill Bay",
-            Area::Pender => "Pender",
-            Area::Penelakut => "Penelakut",
-            Area::SaltSpring => "Salt Spring",
-            Area::Saturna => "Saturna",
-            Area::Thetis => "Thetis",
-            Area::Vancouver => "Vancouver",
-            Area::Victoria => "Victoria",
-        }
+        &Registry::get().area(*self).short_name
     }
 
     pub fn includes_terminal(&self, terminal: Terminal) -> bool {
@@ -182,44 +172,29 @@ impl Area {
 }
 
 impl Terminal {
+    pub fn new(code: &str) -> Terminal {
+        Terminal(intern(code))
+    }
+
+    /// Every terminal in the registry
+    pub fn iter() -> impl Iterator<Item = Terminal> {
+        Registry::get().terminals.iter().map(|t| t.terminal)
+    }
+
+    pub fn is_known(&self) -> bool {
+        Registry::get().terminals.iter().any(|t| t.terminal == *self)
+    }
+
     pub fn name(&self) -> &'static str {
-        match *self {
-            Terminal::BTW => "Brentwood Bay",
-            Terminal::CFT => "Crofton",
-            Terminal::CHM => "Chemainus",
-            Terminal::FUL => "Fulford Harbour",
-            Terminal::MIL => "Mill Bay",
-            Terminal::PEN => "Telegraph Harbour",
-            Terminal::PLH => "Long Harbour",
-            Terminal::POB => "Otter Bay",
-            Terminal::PSB => "Sturdies Bay",
-            Terminal::PST => "Lyall Harbour",
-            Terminal::PVB => "Village Bay",
-            Terminal::SWB => "Swartz Bay",
-            Terminal::THT => "Preedy Harbour",
-            Terminal::TSA => "Tsawwassen",
-            Terminal::VES => "Vesuvius Bay",
-        }
+        &Registry::get().terminal(*self).name
     }
 
     pub fn area(&self) -> Area {
-        match *self {
-            Terminal::BTW => Area::Brentwood,
-            Terminal::CFT => Area::Crofton,
-            Terminal::CHM => Area::Chemainus,
-            Terminal::FUL => Area::SaltSpring,
-            Terminal::MIL => Area::MillBay,
-            Terminal::PEN => Area::Penelakut,
-            Terminal::PLH => Area::SaltSpring,
-            Terminal::POB => Area::Pender,
-            Terminal::PSB => Area::Galiano,
-            Terminal::PST => Area::Saturna,
-            Terminal::PVB => Area::Mayne,
-            Terminal::SWB => Area::Victoria,
-            Terminal::THT => Area::Thetis,
-            Terminal::TSA => Area::Vancouver,
-            Terminal::VES => Area::SaltSpring,
-        }
+        Registry::get().terminal(*self).area
+    }
+
+    pub fn coordinates(&self) -> (f64, f64) {
+        Registry::get().terminal(*self).coordinates
     }
 
     pub fn combinations(terminals: &[Terminal]) -> impl Iterator<Item = TerminalPair> + '_ {
@@ -245,20 +220,27 @@ impl AreaPair {
     }
 
     pub fn is_reservable(&self) -> bool {
-        self.includes_any_terminal(&*ROUTE_5_AND_9_GULF_ISLAND_TERMINALS) && self.includes_terminal(Terminal::TSA)
+        AREA_PAIR_TERMINAL_PAIRS.get(self).map(|tps| tps.iter().any(|tp| tp.is_reservable())).unwrap_or(false)
     }
 }
 
 impl TerminalPair {
+    /// Only reads the codes (e.g. "TSA-SWB"), so that it works before a registry override is installed; use
+    /// `ensure_known` afterwards
     pub fn parse_schedule_code_pair(code_pair: &str) -> Result<TerminalPair> {
         let inner = || {
             let parts: Vec<_> = code_pair.split('-').collect();
             if parts.len() != 2 {
                 bail!("Expect exactly two parts");
             }
-            let from = parts[0].parse().with_context(|| format!("Invalid first part: {:?}", parts[0]))?;
-            let to = parts[1].parse().with_context(|| format!("Invalid second part: {:?}", parts[1]))?;
-            Ok(TerminalPair { from, to })
+            for part in &parts {
+                ensure!(
+                    !part.is_empty() && part.chars().all(|c| c.is_ascii_uppercase()),
+                    "Invalid terminal code: {:?}",
+                    part
+                );
+            }
+            Ok(TerminalPair { from: Terminal::new(parts[0]), to: Terminal::new(parts[1]) })
         };
         inner().with_context(|| format!("Failed to parse terminal code pair: {:?}", code_pair))
     }
@@ -282,6 +264,84 @@ impl TerminalPair {
     pub fn area_pair(&self) -> AreaPair {
         AreaPair { from: self.from.area(), to: self.to.area() }
     }
+
+    pub fn is_reservable(&self) -> bool {
+        Registry::get().routes_with_terminals(*self).any(|r| r.reservable)
+    }
+
+    /// Data files may have been written with a different registry, so check the terminals before looking them up
+    pub fn ensure_known(&self) -> Result<()> {
+        for terminal in [self.from, self.to] {
+            ensure!(terminal.is_known(), "Unknown terminal: {}", terminal);
+        }
+        Ok(())
+    }
+}
+
+impl Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Debug for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl FromStr for Area {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Area> {
+        Area::new(s).resolve().ok_or_else(|| anyhow!("Unknown area: {:?}", s))
+    }
+}
+
+impl Serialize for Area {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+// Only reads the name, since the registry itself contains areas
+impl<'de> Deserialize<'de> for Area {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Area, D::Error> {
+        Ok(Area::new(&String::deserialize(deserializer)?))
+    }
+}
+
+impl Display for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Debug for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl FromStr for Terminal {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Terminal> {
+        let terminal = Terminal::new(s);
+        ensure!(terminal.is_known(), "Unknown terminal: {:?}", s);
+        Ok(terminal)
+    }
+}
+
+impl Serialize for Terminal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+// Only reads the code, since the registry itself contains terminals
+impl<'de> Deserialize<'de> for Terminal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Terminal, D::Error> {
+        Ok(Terminal::new(&String::deserialize(deserializer)?))
+    }
 }
 
 impl Display for TerminalPair {
@@ -297,10 +357,69 @@ impl FromStr for TerminalPair {
     }
 }
 
+impl Stop {
+    pub fn new(type_: StopType, terminal: Terminal) -> Stop {
+        Stop { type_, terminal, arrive_time: None, depart_time: None }
+    }
+}
+
 impl Sailing {
     pub fn is_thrufare(&self) -> bool {
         self.stops.iter().any(|s| s.type_ == StopType::Thrufare)
     }
+
+    /// Time since the start of the operating day that the sailing departs
+    pub fn depart_service_time(&self) -> Duration {
+        Duration::days(self.depart_day_offset.into()) + (self.depart_time - Time::MIDNIGHT)
+    }
+
+    /// Time since the start of the operating day that the sailing arrives
+    pub fn arrive_service_time(&self) -> Duration {
+        Duration::days(self.arrive_day_offset.into()) + (self.arrive_time - Time::MIDNIGHT)
+    }
+
+    /// Time the sailing takes when it runs on the operating day, which differs from the difference between its clock
+    /// times if the clocks change on the way
+    pub fn duration(&self, service_date: Date) -> Duration {
+        self.arrive_datetime(service_date) - self.depart_datetime(service_date)
+    }
+
+    pub fn arrives_next_day(&self) -> bool {
+        self.arrive_day_offset > self.depart_day_offset
+    }
+
+    pub fn depart_date(&self, service_date: Date) -> Date {
+        service_date + Duration::days(self.depart_day_offset.into())
+    }
+
+    pub fn arrive_date(&self, service_date: Date) -> Date {
+        service_date + Duration::days(self.arrive_day_offset.into())
+    }
+
+    pub fn depart_datetime(&self, service_date: Date) -> OffsetDateTime {
+        vancouver_datetime(self.depart_date(service_date), self.depart_time)
+    }
+
+    pub fn arrive_datetime(&self, service_date: Date) -> OffsetDateTime {
+        vancouver_datetime(self.arrive_date(service_date), self.arrive_time)
+    }
+}
+
+impl Schedule {
+    /// Items with a sailing departing on the given calendar date, with the operating day each belongs to
+    pub fn items_departing_on(&self, date: Date) -> impl Iterator<Item = (&ScheduleItem, Date)> {
+        self.items.iter().filter_map(move |item| {
+            item.service_date_departing_on(date)
+                .filter(|service_date| self.date_range.includes_date_inclusive(*service_date))
+                .map(|service_date| (item, service_date))
+        })
+    }
+}
+
+impl Alert {
+    pub fn applies_on(&self, date: Date) -> bool {
+        self.date_range.map(|date_range| date_range.includes_date_inclusive(date)).unwrap_or(true)
+    }
 }
 
 impl DateDaysIterator {
@@ -341,20 +460,20 @@ impl DateRange {
                     format_description!("[month repr:short case_sensitive:false] [day padding:none] [year]"),
                 )
             })?;
-            match Date::from_calendar_date(self.from.year(), parsed_date.month(), parsed_date.day()) {
-                Ok(from_year_date) if self.includes_date_inclusive(from_year_date) => Ok(Some(from_year_date)),
-                _ if self.from.year() == self.to.year() => Ok(None) as Result<_>,
-                _ => match Date::from_calendar_date(self.to.year(), parsed_date.month(), parsed_date.day()) {
-                    Ok(to_year_date) if self.includes_date_inclusive(to_year_date) => {
-                        Ok(Some(to_year_date)) as Result<_>
-                    }
-                    _ => Ok(None) as Result<_>,
-                },
-            }
+            Ok(self.month_day_within(parsed_date.month(), parsed_date.day())) as Result<_>
         };
         inner().with_context(|| format!("Failed to parse date within range {}: {:?}", self, text))
     }
 
+    /// The date in the range with the month and day, or `None` if there isn't one
+    pub fn month_day_within(&self, month: Month, day: u8) -> Option<Date> {
+        [self.from.year(), self.to.year()]
+            .into_iter()
+            .dedup()
+            .filter_map(|year| Date::from_calendar_date(year, month, day).ok())
+            .find(|date| self.includes_date_inclusive(*date))
+    }
+
     pub fn parse(text: &str, date_format: &TimeFormat, separator: &str) -> Result<DateRange> {
         let inner = || {
             let parts: Vec<_> = text.split(separator).collect();
@@ -381,8 +500,8 @@ impl DateRestriction {
     pub fn includes_date(&self, date: Date) -> bool {
         match self {
             DateRestriction::All => true,
-            DateRestriction::Except(dates) => !dates.contains(&date),
-            DateRestriction::Only(dates) => dates.contains(&date),
+            DateRestriction::Except(dates) => !dates.contains(date),
+            DateRestriction::Only(dates) => dates.contains(date),
         }
     }
 
@@ -393,6 +512,32 @@ impl DateRestriction {
             DateRestriction::Only(dates) => dates.is_empty(),
         }
     }
+
+    /// Stop including the dates (e.g. because their sailings were cancelled)
+    pub fn exclude(&mut self, dates: &DateSet) {
+        match self {
+            DateRestriction::All => *self = DateRestriction::Except(dates.clone()),
+            DateRestriction::Except(except_dates) => except_dates.extend(dates),
+            DateRestriction::Only(only_dates) => {
+                *only_dates = only_dates.iter().filter(|d| !dates.contains(*d)).collect()
+            }
+        }
+    }
+
+    /// Include the dates again after `exclude`
+    pub fn include(&mut self, dates: &DateSet) {
+        match self {
+            DateRestriction::All => {}
+            DateRestriction::Except(except_dates) => {
+                *except_dates = except_dates.iter().filter(|d| !dates.contains(*d)).collect();
+                if except_dates.is_empty() {
+                    *self = DateRestriction::All;
+                }
+            }
+            DateRestriction::Only(only_dates) => only_dates.extend(dates),
+        }
+    }
+
     pub fn merge(&mut self, other: &DateRestriction) -> Result<()> {
         match (self, other) {
             (DateRestriction::Except(a), DateRestriction::Except(b)) => a.extend(b),
@@ -423,6 +568,68 @@ impl DateRestriction {
 }
 
 impl ScheduleItem {
+    pub fn includes_date(&self, date: Date) -> bool {
+        self.weekdays.get(&date.weekday()).map(|dr| dr.includes_date(date)).unwrap_or(false)
+    }
+
+    /// Operating day of the sailing if it departs on the given calendar date
+    pub fn service_date_departing_on(&self, date: Date) -> Option<Date> {
+        let service_date = date - Duration::days(self.sailing.depart_day_offset.into());
+        self.includes_date(service_date).then(|| service_date)
+    }
+
+    /// Notes that apply on the operating day, sorted so they come out the same way every time
+    pub fn notes_for_date(&self, date: Date) -> impl Iterator<Item = &str> {
+        self.notes.iter().filter_map(move |(note, dr)| dr.includes_date(date).then(|| note.as_ref())).sorted()
+    }
+
+    /// Notices that cancelled the sailing on the operating day
+    pub fn cancellations_for_date(&self, date: Date) -> impl Iterator<Item = &str> {
+        self.cancellations.iter().filter_map(move |(notice, dr)| dr.includes_date(date).then(|| notice.as_str()))
+    }
+
+    /// Operating day of the sailing if it would have departed on the given calendar date, but was cancelled
+    pub fn cancelled_service_date_departing_on(&self, date: Date) -> Option<Date> {
+        let service_date = date - Duration::days(self.sailing.depart_day_offset.into());
+        self.cancellations_for_date(service_date).next().map(|_| service_date)
+    }
+
+    /// Exclude the dates that the sailing would otherwise run on, recording the notice that cancelled it; returns
+    /// whether it would have run on any of them
+    pub fn cancel(&mut self, notice: &str, dates: &DateSet) -> Result<bool> {
+        let cancelled_dates: DateSet = dates.iter().filter(|d| self.includes_date(*d)).collect();
+        if cancelled_dates.is_empty() {
+            return Ok(false);
+        }
+        for (weekday, date_restriction) in self.weekdays.iter_mut() {
+            let weekday_dates: DateSet = cancelled_dates.iter().filter(|d| d.weekday() == *weekday).collect();
+            if !weekday_dates.is_empty() {
+                date_restriction.exclude(&weekday_dates);
+            }
+        }
+        DateRestriction::merge_map(
+            &mut self.cancellations,
+            HashMap::from([(notice.to_string(), DateRestriction::Only(cancelled_dates))]),
+        )?;
+        Ok(true)
+    }
+
+    /// Undo every `cancel`, e.g. before applying the current notices to schedules from an earlier run
+    pub fn uncancel_all(&mut self) {
+        for (_, cancellation) in self.cancellations.drain() {
+            let dates = match cancellation {
+                DateRestriction::Only(dates) => dates,
+                DateRestriction::All | DateRestriction::Except(_) => continue,
+            };
+            for (weekday, date_restriction) in self.weekdays.iter_mut() {
+                let weekday_dates: DateSet = dates.iter().filter(|d| d.weekday() == *weekday).collect();
+                if !weekday_dates.is_empty() {
+                    date_restriction.include(&weekday_dates);
+                }
+            }
+        }
+    }
+
     pub fn merge_items(items: Vec<ScheduleItem>) -> Result<Vec<ScheduleItem>> {
         let mut map: HashMap<Sailing, ScheduleItem> = HashMap::new();
         for new_item in items {
@@ -431,12 +638,17 @@ impl ScheduleItem {
                     .context("Failed to merge weekdays of schedule items")?;
                 DateRestriction::merge_map(&mut existing_item.notes, new_item.notes)
                     .context("Failed to merge notes of schedule items")?;
+                DateRestriction::merge_map(&mut existing_item.cancellations, new_item.cancellations)
+                    .context("Failed to merge cancellations of schedule items")?;
             } else {
                 map.insert(new_item.sailing.clone(), new_item);
             }
         }
         let mut items: Vec<_> = map.into_values().collect();
-        items.sort_unstable_by(|a, b| a.sailing.depart_time.cmp(&b.sailing.depart_time));
+        // Sailings at the same time on different vessels need a stable order too
+        items.sort_unstable_by(|a, b| {
+            (a.sailing.depart_service_time(), &a.sailing).cmp(&(b.sailing.depart_service_time(), &b.sailing))
+        });
         Ok(items)
     }
 }
@@ -444,6 +656,7 @@ impl ScheduleItem {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::*;
 
     #[test]
     fn test_date_range_make_year_within() -> Result<()> {
@@ -487,16 +700,117 @@ mod tests {
     #[test]
     fn test_terminal_combinations() -> Result<()> {
         assert_eq!(
-            Terminal::combinations(&[Terminal::CHM, Terminal::THT, Terminal::PEN]).collect::<HashSet<_>>(),
+            Terminal::combinations(&[Terminal::new("CHM"), Terminal::new("THT"), Terminal::new("PEN")])
+                .collect::<HashSet<_>>(),
             HashSet::from([
-                TerminalPair { from: Terminal::CHM, to: Terminal::PEN },
-                TerminalPair { from: Terminal::CHM, to: Terminal::THT },
-                TerminalPair { from: Terminal::PEN, to: Terminal::CHM },
-                TerminalPair { from: Terminal::PEN, to: Terminal::THT },
-                TerminalPair { from: Terminal::THT, to: Terminal::CHM },
-                TerminalPair { from: Terminal::THT, to: Terminal::PEN },
+                TerminalPair { from: Terminal::new("CHM"), to: Terminal::new("PEN") },
+                TerminalPair { from: Terminal::new("CHM"), to: Terminal::new("THT") },
+                TerminalPair { from: Terminal::new("PEN"), to: Terminal::new("CHM") },
+                TerminalPair { from: Terminal::new("PEN"), to: Terminal::new("THT") },
+                TerminalPair { from: Terminal::new("THT"), to: Terminal::new("CHM") },
+                TerminalPair { from: Terminal::new("THT"), to: Terminal::new("PEN") },
             ])
         );
         Ok(())
     }
+
+    #[test]
+    fn test_sailing_after_midnight_across_dst_change() -> Result<()> {
+        let item = ScheduleItem {
+            sailing: Sailing {
+                depart_time: time!(01:30),
+                arrive_time: time!(02:30),
+                stops: vec![],
+                depart_day_offset: 1,
+                arrive_day_offset: 1,
+                vessel: None,
+            },
+            weekdays: HashMap::from([(Weekday::Saturday, DateRestriction::All)]),
+            notes: HashMap::new(),
+            cancellations: HashMap::new(),
+        };
+        let schedule = Schedule {
+            terminal_pair: TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("DUK") },
+            date_range: DateRange { from: date!(2024 - 10 - 01), to: date!(2024 - 12 - 31) },
+            items: vec![item],
+            source_url: String::new(),
+            refreshed_at: OffsetDateTime::UNIX_EPOCH,
+            alerts: vec![],
+            notes_to_review: vec![],
+        };
+        assert_eq!(schedule.items_departing_on(date!(2024 - 11 - 02)).count(), 0);
+        let (item, service_date) =
+            schedule.items_departing_on(date!(2024 - 11 - 03)).next().context("Expect sailing after midnight")?;
+        assert_eq!(service_date, date!(2024 - 11 - 02));
+        // The clocks go back an hour between departure and arrival
+        let sailing = &item.sailing;
+        assert_eq!(sailing.depart_datetime(service_date), datetime!(2024-11-03 01:30 -07:00));
+        assert_eq!(sailing.arrive_datetime(service_date), datetime!(2024-11-03 02:30 -08:00));
+        assert_eq!(sailing.duration(service_date), Duration::hours(2));
+        assert_eq!(sailing.duration(date!(2024 - 11 - 09)), Duration::hours(1));
+        assert!(!sailing.arrives_next_day());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_items_keeps_vessels_distinct() -> Result<()> {
+        let item = |weekday: Weekday, vessel: &str| ScheduleItem {
+            sailing: Sailing {
+                depart_time: time!(07:00),
+                arrive_time: time!(08:35),
+                stops: vec![],
+                depart_day_offset: 0,
+                arrive_day_offset: 0,
+                vessel: Some(vessel.to_string()),
+            },
+            weekdays: HashMap::from([(weekday, DateRestriction::All)]),
+            notes: HashMap::new(),
+            cancellations: HashMap::new(),
+        };
+        let items = ScheduleItem::merge_items(vec![
+            item(Weekday::Monday, "Queen of Cumberland"),
+            item(Weekday::Tuesday, "Salish Raven"),
+            item(Weekday::Wednesday, "Queen of Cumberland"),
+        ])?;
+        assert_eq!(items.len(), 2);
+        let cumberland = items
+            .iter()
+            .find(|i| i.sailing.vessel.as_deref() == Some("Queen of Cumberland"))
+            .context("Expect Queen of Cumberland sailing")?;
+        assert_eq!(cumberland.weekdays.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_restriction_exclude() -> Result<()> {
+        let dates = |dates: &[Date]| dates.iter().copied().collect::<DateSet>();
+        let mut only = DateRestriction::Only(dates(&[date!(2024 - 07 - 01), date!(2024 - 07 - 08)]));
+        only.exclude(&dates(&[date!(2024 - 07 - 08)]));
+        assert_eq!(only, DateRestriction::Only(dates(&[date!(2024 - 07 - 01)])));
+        let mut all = DateRestriction::All;
+        all.exclude(&dates(&[date!(2024 - 07 - 08)]));
+        assert_eq!(all, DateRestriction::Except(dates(&[date!(2024 - 07 - 08)])));
+        // Merging only and except dates is a conflict whichever order they are in
+        let except = DateRestriction::Except(dates(&[date!(2024 - 07 - 08)]));
+        assert!(only.clone().merge(&except).is_err());
+        assert!(except.clone().merge(&only).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_notes_for_date_are_sorted() -> Result<()> {
+        let notes = ["Foot passengers only", "Dangerous goods sailing", "Reservations recommended"];
+        let item = notes
+            .iter()
+            .fold(ScheduleItemBuilder::new(time!(07:00), time!(08:35)), |builder, note| {
+                builder.note(note, DateRestriction::All)
+            })
+            .note("Holiday sailing", DateRestriction::Only([date!(2023 - 07 - 01)].into_iter().collect()))
+            .build();
+        assert_eq!(
+            item.notes_for_date(date!(2023 - 06 - 10)).collect::<Vec<_>>(),
+            vec!["Dangerous goods sailing", "Foot passengers only", "Reservations recommended"]
+        );
+        Ok(())
+    }
 }
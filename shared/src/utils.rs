@@ -1,5 +1,6 @@
 use crate::imports::*;
 use crate::types::*;
+use std::sync::Mutex;
 
 const ISO8601_DATE_FORMAT: &TimeFormat = format_description!("[year]-[month]-[day]");
 
@@ -14,6 +15,20 @@ pub fn now_utc() -> OffsetDateTime {
         .expect("Expect current timestamp to convert to offset date/time")
 }
 
+/// Keeps one copy of each string for the life of the program, which suits small sets such as terminal codes
+pub fn intern(text: &str) -> &'static str {
+    static INTERNED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+    let mut interned = INTERNED.lock().expect("Expect interned strings lock to not be poisoned");
+    match interned.get(text) {
+        Some(interned_text) => interned_text,
+        None => {
+            let interned_text: &'static str = Box::leak(text.to_string().into_boxed_str());
+            interned.insert(interned_text);
+            interned_text
+        }
+    }
+}
+
 pub fn now_vancouver() -> OffsetDateTime {
     now_utc().to_timezone(timezones::db::america::VANCOUVER)
 }
@@ -22,6 +37,38 @@ pub fn today_vancouver() -> Date {
     now_vancouver().date()
 }
 
+/// Calendar date in Vancouver at the instant
+pub fn vancouver_date(datetime: OffsetDateTime) -> Date {
+    datetime.to_timezone(timezones::db::america::VANCOUVER).date()
+}
+
+/// Resolves a local date and time in Vancouver. A time skipped by the spring DST change resolves to the same instant
+/// as an hour later, and a time repeated by the fall change resolves to its first occurrence.
+pub fn vancouver_datetime(date: Date, time: Time) -> OffsetDateTime {
+    let timezone = timezones::db::america::VANCOUVER;
+    let local = PrimitiveDateTime::new(date, time);
+    // The offsets a day either side are the only candidates, since DST changes are months apart
+    let offsets = [local.assume_utc() - Duration::days(1), local.assume_utc() + Duration::days(1)]
+        .map(|probe| probe.to_timezone(timezone).offset());
+    offsets
+        .iter()
+        .map(|&offset| local.assume_offset(offset))
+        .find(|candidate| {
+            let converted = candidate.to_timezone(timezone);
+            converted.date() == date && converted.time() == time
+        })
+        .unwrap_or_else(|| local.assume_offset(offsets[0]))
+        .to_timezone(timezone)
+}
+
+pub fn is_zero(n: &u8) -> bool {
+    *n == 0
+}
+
+pub fn is_false(b: &bool) -> bool {
+    !*b
+}
+
 pub fn format_iso8601_date(date: Date) -> String {
     date.format(ISO8601_DATE_FORMAT).expect("Expect iso8601 date to format")
 }
@@ -66,10 +113,31 @@ where
     })
 }
 
+pub fn into_schedules_map<I: IntoIterator<Item = Schedule>>(schedules: I) -> SchedulesMap {
+    into_vec_group_map(schedules, |s| s.terminal_pair).into_iter().map(|(tp, v)| (tp, Rc::new(v))).collect()
+}
+
+/// Hex digest of the contents, using FNV-1a because it is stable across builds (unlike `DefaultHasher`)
+pub fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_vancouver_datetime_dst_changes() -> Result<()> {
+        // Spring forward: 2:30 AM doesn't exist on 2024-03-10
+        assert_eq!(vancouver_datetime(date!(2024 - 03 - 10), time!(02:30)), datetime!(2024-03-10 03:30 -07:00));
+        assert_eq!(vancouver_datetime(date!(2024 - 03 - 10), time!(01:30)), datetime!(2024-03-10 01:30 -08:00));
+        // Fall back: 1:30 AM happens twice on 2024-11-03
+        assert_eq!(vancouver_datetime(date!(2024 - 11 - 03), time!(01:30)), datetime!(2024-11-03 01:30 -07:00));
+        assert_eq!(vancouver_datetime(date!(2024 - 11 - 03), time!(02:30)), datetime!(2024-11-03 02:30 -08:00));
+        Ok(())
+    }
+
     #[test]
     fn test_format_iso8601_date() -> Result<()> {
         assert_eq!(format_iso8601_date(date!(2021 - 03 - 31)), "2021-03-31");
@@ -0,0 +1,405 @@
+use crate::imports::*;
+use crate::registry::*;
+use crate::types::*;
+
+// A sailing is implausibly long or short if it takes more than this many times longer (or shorter) than is typical for
+// sailings with the same stops
+const IMPLAUSIBLE_DURATION_FACTOR: i32 = 2;
+
+// Fewer sailings than this with the same stops aren't enough to say what a typical crossing takes
+const MIN_TYPICAL_DURATION_SAMPLES: usize = 3;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScheduleFindingKind {
+    ArriveNotAfterDepart { sailing: Sailing },
+    OverlappingDateRanges { other_date_range: DateRange },
+    DuplicateSailing { depart_time: Time, date: Date },
+    OnlyDatesOutsideRange { depart_time: Time, dates: Vec<Date> },
+    WeekdayWithoutSailings { weekday: Weekday },
+    StopNotOnRoute { depart_time: Time, stop: Stop },
+    ImplausibleDuration { depart_time: Time, duration: Duration, typical_duration: Duration },
+    UnrecognizedNote { note: String },
+}
+
+/// Something suspicious about a scraped schedule
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScheduleFinding {
+    pub terminal_pair: TerminalPair,
+    pub date_range: DateRange,
+    pub kind: ScheduleFindingKind,
+}
+
+fn format_finding_time(time: Time) -> String {
+    time.format(format_description!("[hour]:[minute]")).expect("Expect finding time to format")
+}
+
+fn date_ranges_overlap(a: &DateRange, b: &DateRange) -> bool {
+    a.from <= b.to && b.from <= a.to
+}
+
+fn stop_terminals(sailing: &Sailing) -> Vec<Terminal> {
+    sailing.stops.iter().map(|s| s.terminal).collect()
+}
+
+impl ScheduleFinding {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            // These would make the schedule display wrong or ambiguous
+            ScheduleFindingKind::ArriveNotAfterDepart { .. } | ScheduleFindingKind::OverlappingDateRanges { .. } => {
+                Severity::Error
+            }
+            ScheduleFindingKind::StopNotOnRoute { stop, .. } if stop.type_ == StopType::Stop => Severity::Error,
+            // These happen in legitimate schedules now and then, but are worth a look
+            ScheduleFindingKind::DuplicateSailing { .. }
+            | ScheduleFindingKind::OnlyDatesOutsideRange { .. }
+            | ScheduleFindingKind::WeekdayWithoutSailings { .. }
+            | ScheduleFindingKind::StopNotOnRoute { .. }
+            | ScheduleFindingKind::ImplausibleDuration { .. }
+            | ScheduleFindingKind::UnrecognizedNote { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: ", self.terminal_pair, self.date_range)?;
+        match &self.kind {
+            ScheduleFindingKind::ArriveNotAfterDepart { sailing } => write!(
+                f,
+                "sailing {} arrives at {}, which is not after it departs",
+                format_finding_time(sailing.depart_time),
+                format_finding_time(sailing.arrive_time)
+            ),
+            ScheduleFindingKind::OverlappingDateRanges { other_date_range } => {
+                write!(f, "date range overlaps another schedule for {}", other_date_range)
+            }
+            ScheduleFindingKind::DuplicateSailing { depart_time, date } => {
+                write!(f, "more than one sailing at {} on {}", format_finding_time(*depart_time), date)
+            }
+            ScheduleFindingKind::OnlyDatesOutsideRange { depart_time, dates } => write!(
+                f,
+                "sailing {} is only on dates outside the schedule: {}",
+                format_finding_time(*depart_time),
+                dates.iter().join(", ")
+            ),
+            ScheduleFindingKind::WeekdayWithoutSailings { weekday } => write!(f, "no sailings on {}s", weekday),
+            ScheduleFindingKind::StopNotOnRoute { depart_time, stop } => match stop.type_ {
+                StopType::Stop => write!(
+                    f,
+                    "sailing {} stops at {}, which is not on a route between the terminals",
+                    format_finding_time(*depart_time),
+                    stop.terminal
+                ),
+                StopType::Transfer | StopType::Thrufare => write!(
+                    f,
+                    "sailing {} connects at {}, which is not on a route from either terminal",
+                    format_finding_time(*depart_time),
+                    stop.terminal
+                ),
+            },
+            ScheduleFindingKind::ImplausibleDuration { depart_time, duration, typical_duration } => write!(
+                f,
+                "sailing {} takes {} minutes, but similar sailings take {} minutes",
+                format_finding_time(*depart_time),
+                duration.whole_minutes(),
+                typical_duration.whole_minutes()
+            ),
+            ScheduleFindingKind::UnrecognizedNote { note } => {
+                write!(f, "note was not recognized, so it applies to its sailings as is: {:?}", note)
+            }
+        }
+    }
+}
+
+struct ScheduleValidator<'a> {
+    schedule: &'a Schedule,
+    findings: Vec<ScheduleFinding>,
+}
+
+impl<'a> ScheduleValidator<'a> {
+    fn add(&mut self, kind: ScheduleFindingKind) {
+        self.findings.push(ScheduleFinding {
+            terminal_pair: self.schedule.terminal_pair,
+            date_range: self.schedule.date_range,
+            kind,
+        });
+    }
+
+    fn check_sailing_times(&mut self) {
+        for item in &self.schedule.items {
+            if item.sailing.duration(self.schedule.date_range.from) <= Duration::ZERO {
+                self.add(ScheduleFindingKind::ArriveNotAfterDepart { sailing: item.sailing.clone() });
+            }
+        }
+    }
+
+    fn check_overlapping_date_ranges(&mut self, schedules: &[Schedule]) {
+        for other in schedules {
+            if !std::ptr::eq(other, self.schedule)
+                && other.terminal_pair == self.schedule.terminal_pair
+                && date_ranges_overlap(&other.date_range, &self.schedule.date_range)
+            {
+                self.add(ScheduleFindingKind::OverlappingDateRanges { other_date_range: other.date_range });
+            }
+        }
+    }
+
+    // Two sailings departing at the same time on the same day (only reported for the first such day)
+    fn check_duplicate_sailings(&mut self) {
+        let mut reported = HashSet::new();
+        for date in self.schedule.date_range.iter_days() {
+            let items = self.schedule.items.iter().filter(|item| item.includes_date(date));
+            for (depart_service_time, count) in items.map(|item| item.sailing.depart_service_time()).counts() {
+                if count > 1 && reported.insert(depart_service_time) {
+                    let depart_time = Time::MIDNIGHT + depart_service_time;
+                    self.add(ScheduleFindingKind::DuplicateSailing { depart_time, date });
+                }
+            }
+        }
+    }
+
+    fn check_only_dates(&mut self) {
+        for item in &self.schedule.items {
+            let dates: Vec<_> = item
+                .weekdays
+                .values()
+                .chain(item.notes.values())
+                .filter_map(|dr| match dr {
+                    DateRestriction::Only(dates) => Some(dates.iter()),
+                    DateRestriction::All | DateRestriction::Except(_) => None,
+                })
+                .flatten()
+                .filter(|date| !self.schedule.date_range.includes_date_inclusive(*date))
+                .sorted()
+                .dedup()
+                .collect();
+            if !dates.is_empty() {
+                self.add(ScheduleFindingKind::OnlyDatesOutsideRange { depart_time: item.sailing.depart_time, dates });
+            }
+        }
+    }
+
+    fn check_weekdays(&mut self) {
+        let weekdays: HashSet<_> = self.schedule.date_range.iter_days().take(7).map(|d| d.weekday()).collect();
+        for weekday in iter::successors(Some(Weekday::Monday), |w| Some(w.next())).take(7) {
+            if weekdays.contains(&weekday)
+                && !self
+                    .schedule
+                    .items
+                    .iter()
+                    .any(|item| item.weekdays.get(&weekday).map(|dr| !dr.is_never()).unwrap_or(false))
+            {
+                self.add(ScheduleFindingKind::WeekdayWithoutSailings { weekday });
+            }
+        }
+    }
+
+    // A sailing stops only on a route between its terminals, but it may connect (transfer or thru-fare) with a route
+    // that only one of them is on, such as at Swartz Bay for sailings between Tsawwassen and the Gulf Islands
+    fn check_stops(&mut self) {
+        let registry = Registry::get();
+        let terminal_pair = self.schedule.terminal_pair;
+        let route_terminals: HashSet<_> =
+            registry.routes_with_terminals(terminal_pair).flat_map(|r| r.terminals.iter().copied()).collect();
+        let connecting_terminals: HashSet<_> = registry
+            .routes_with_terminal(terminal_pair.from)
+            .chain(registry.routes_with_terminal(terminal_pair.to))
+            .flat_map(|r| r.terminals.iter().copied())
+            .collect();
+        for item in &self.schedule.items {
+            for stop in &item.sailing.stops {
+                let terminals = match stop.type_ {
+                    StopType::Stop => &route_terminals,
+                    StopType::Transfer | StopType::Thrufare => &connecting_terminals,
+                };
+                if !terminals.contains(&stop.terminal) {
+                    self.add(ScheduleFindingKind::StopNotOnRoute {
+                        depart_time: item.sailing.depart_time,
+                        stop: *stop,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_durations(&mut self, typical_durations: &HashMap<(TerminalPair, Vec<Terminal>), Duration>) {
+        for item in &self.schedule.items {
+            let duration = item.sailing.duration(self.schedule.date_range.from);
+            let key = (self.schedule.terminal_pair, stop_terminals(&item.sailing));
+            if let Some(&typical_duration) = typical_durations.get(&key) {
+                if duration > Duration::ZERO
+                    && (duration > typical_duration * IMPLAUSIBLE_DURATION_FACTOR
+                        || duration * IMPLAUSIBLE_DURATION_FACTOR < typical_duration)
+                {
+                    self.add(ScheduleFindingKind::ImplausibleDuration {
+                        depart_time: item.sailing.depart_time,
+                        duration,
+                        typical_duration,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_notes_to_review(&mut self) {
+        for note in &self.schedule.notes_to_review {
+            self.add(ScheduleFindingKind::UnrecognizedNote { note: note.clone() });
+        }
+    }
+}
+
+// Median duration of the sailings on each route with the same stops, where there are enough of them to tell
+fn typical_durations(schedules: &[Schedule]) -> HashMap<(TerminalPair, Vec<Terminal>), Duration> {
+    let mut durations: HashMap<_, Vec<Duration>> = HashMap::new();
+    for schedule in schedules {
+        for item in &schedule.items {
+            let key = (schedule.terminal_pair, stop_terminals(&item.sailing));
+            durations.entry(key).or_default().push(item.sailing.duration(schedule.date_range.from));
+        }
+    }
+    durations
+        .into_iter()
+        .filter(|(_, durations)| durations.len() >= MIN_TYPICAL_DURATION_SAMPLES)
+        .map(|(key, mut durations)| {
+            durations.sort_unstable();
+            (key, durations[durations.len() / 2])
+        })
+        .collect()
+}
+
+/// Check the schedules for data that is probably the result of a scraping problem or a mistake on the schedule page
+pub fn validate_schedules(schedules: &[Schedule]) -> Vec<ScheduleFinding> {
+    let typical_durations = typical_durations(schedules);
+    let mut findings = Vec::new();
+    for schedule in schedules {
+        let mut validator = ScheduleValidator { schedule, findings: Vec::new() };
+        validator.check_sailing_times();
+        validator.check_overlapping_date_ranges(schedules);
+        validator.check_duplicate_sailings();
+        validator.check_only_dates();
+        validator.check_weekdays();
+        validator.check_stops();
+        validator.check_durations(&typical_durations);
+        validator.check_notes_to_review();
+        findings.extend(validator.findings);
+    }
+    findings
+}
+
+/// Log the findings, and fail if any of them are errors
+pub fn report_findings(findings: &[ScheduleFinding]) -> Result<()> {
+    for finding in findings {
+        match finding.severity() {
+            Severity::Warning => warn!("Suspicious schedule data: {}", finding),
+            Severity::Error => error!("Invalid schedule data: {}", finding),
+        }
+    }
+    let error_count = findings.iter().filter(|f| f.severity() == Severity::Error).count();
+    ensure!(error_count == 0, "Schedule validation found {} error(s)", error_count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date_set::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn test_validate_schedules() -> Result<()> {
+        let swb_psb = TerminalPair { from: Terminal::new("SWB"), to: Terminal::new("PSB") };
+        let date_range = DateRange { from: date!(2023 - 06 - 01), to: date!(2023 - 06 - 30) };
+        let only_item = ScheduleItemBuilder::new(time!(15:00), time!(16:00))
+            .weekdays(&[])
+            .weekday(
+                Weekday::Friday,
+                DateRestriction::Only([date!(2023 - 06 - 30), date!(2023 - 07 - 07)].into_iter().collect::<DateSet>()),
+            )
+            .build();
+        let mut sunday_item = ScheduleItemBuilder::new(time!(18:00), time!(18:50)).build();
+        sunday_item.weekdays.remove(&Weekday::Sunday);
+        let schedules = vec![
+            ScheduleBuilder::new(swb_psb)
+                .date_range(date_range)
+                .items([
+                    ScheduleItemBuilder::new(time!(07:00), time!(07:50)).build(),
+                    ScheduleItemBuilder::new(time!(07:00), time!(08:40))
+                        .stops(vec![Stop::new(StopType::Stop, Terminal::new("PVB"))])
+                        .build(),
+                    ScheduleItemBuilder::new(time!(09:00), time!(08:50)).build(),
+                    ScheduleItemBuilder::new(time!(12:00), time!(16:00)).build(),
+                    ScheduleItemBuilder::new(time!(13:00), time!(14:00))
+                        .stops(vec![Stop::new(StopType::Stop, Terminal::new("NAN"))])
+                        .build(),
+                    only_item,
+                ])
+                .build(),
+            ScheduleBuilder::new(swb_psb)
+                .date_range(DateRange { from: date!(2023 - 06 - 30), to: date!(2023 - 07 - 31) })
+                .item(sunday_item)
+                .note_to_review("Vehicle reservations are required")
+                .build(),
+        ];
+        let findings: Vec<_> = validate_schedules(&schedules).into_iter().map(|f| f.kind).collect();
+        assert_eq!(
+            findings,
+            vec![
+                ScheduleFindingKind::ArriveNotAfterDepart { sailing: schedules[0].items[2].sailing.clone() },
+                ScheduleFindingKind::OverlappingDateRanges { other_date_range: schedules[1].date_range },
+                ScheduleFindingKind::DuplicateSailing { depart_time: time!(07:00), date: date!(2023 - 06 - 01) },
+                ScheduleFindingKind::OnlyDatesOutsideRange {
+                    depart_time: time!(15:00),
+                    dates: vec![date!(2023 - 07 - 07)]
+                },
+                ScheduleFindingKind::StopNotOnRoute {
+                    depart_time: time!(13:00),
+                    stop: Stop::new(StopType::Stop, Terminal::new("NAN"))
+                },
+                ScheduleFindingKind::ImplausibleDuration {
+                    depart_time: time!(12:00),
+                    duration: Duration::hours(4),
+                    typical_duration: Duration::minutes(50),
+                },
+                ScheduleFindingKind::OverlappingDateRanges { other_date_range: date_range },
+                ScheduleFindingKind::WeekdayWithoutSailings { weekday: Weekday::Sunday },
+                ScheduleFindingKind::UnrecognizedNote { note: "Vehicle reservations are required".to_string() },
+            ]
+        );
+        assert!(report_findings(&validate_schedules(&schedules[1..])).is_ok());
+        assert!(report_findings(&validate_schedules(&schedules)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_connecting_stops() -> Result<()> {
+        // Route 9 doesn't include Swartz Bay, but route 1 does
+        let schedule = ScheduleBuilder::new(TerminalPair { from: Terminal::new("TSA"), to: Terminal::new("PSB") })
+            .item(
+                ScheduleItemBuilder::new(time!(08:00), time!(11:00))
+                    .stops(vec![Stop::new(StopType::Thrufare, Terminal::new("SWB"))])
+                    .build(),
+            )
+            .item(
+                ScheduleItemBuilder::new(time!(12:00), time!(15:00))
+                    .stops(vec![Stop::new(StopType::Transfer, Terminal::new("NAN"))])
+                    .build(),
+            )
+            .build();
+        let findings = validate_schedules(&[schedule]);
+        assert_eq!(
+            findings.iter().map(|f| &f.kind).collect::<Vec<_>>(),
+            vec![&ScheduleFindingKind::StopNotOnRoute {
+                depart_time: time!(12:00),
+                stop: Stop::new(StopType::Transfer, Terminal::new("NAN"))
+            }]
+        );
+        assert!(report_findings(&findings).is_ok());
+        Ok(())
+    }
+}